@@ -39,16 +39,7 @@ pub struct DumpClass {
 pub enum DumpClassMember {
     Property(DumpClassProperty),
 
-    #[serde(rename_all = "PascalCase")]
-    Function {
-        name: String,
-    },
-
-    #[serde(rename_all = "PascalCase")]
-    Event {
-        name: String,
-    },
-
+    /// Functions, events, and callbacks, which the database doesn't describe.
     #[serde(other)]
     Unknown,
 }
@@ -109,7 +100,6 @@ pub struct PropertySecurity {
 #[serde(rename_all = "PascalCase")]
 pub struct Serialization {
     pub can_save: bool,
-    pub can_load: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -290,8 +280,7 @@ fn variant_type_from_str(value: &str) -> Option<VariantType> {
         "int64" => VariantType::Int64,
         "string" => VariantType::String,
 
-        // ProtectedString is handled as the same as string
-        "ProtectedString" => VariantType::String,
+        "ProtectedString" => VariantType::ProtectedString,
 
         // TweenInfo is not supported by rbx_types yet
         "TweenInfo" => return None,
@@ -4,32 +4,49 @@
 
 use std::{
     borrow::Cow,
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     fmt::{self, Write},
     fs::{self, File},
     io::BufReader,
+    path::Path,
     process::Command,
-    sync::mpsc,
-    time::Duration,
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
 };
 
 use anyhow::Context;
 #[cfg(target_os = "windows")]
 use innerput::{Innerput, Key, Keyboard};
 use notify::{DebouncedEvent, Watcher};
-use rbx_dom_weak::types::VariantType;
+use rbx_dom_weak::types::{Variant, VariantType};
 use rbx_dom_weak::WeakDom;
-use rbx_reflection::{PropertyDescriptor, PropertyKind, PropertySerialization, ReflectionDatabase};
+use rbx_reflection::{
+    DataType, PropertyDescriptor, PropertyKind, PropertySerialization, ReflectionDatabase,
+};
 use roblox_install::RobloxStudio;
 use tempfile::tempdir;
 
 use crate::plugin_injector::{PluginInjector, StudioInfo};
 
+/// Controls how the fixture place is saved by Roblox Studio.
+pub struct StudioOptions {
+    /// Whether the operator should save the place by hand instead of having
+    /// a save keystroke sent to Studio automatically.
+    pub manual_save: bool,
+
+    /// How long to wait for the place to be saved before giving up and
+    /// killing Studio.
+    pub save_timeout: Duration,
+}
+
 /// Use Roblox Studio to populate the reflection database with default values
 /// for as many properties as possible.
-pub fn measure_default_properties(database: &mut ReflectionDatabase) -> anyhow::Result<()> {
+pub fn measure_default_properties(
+    database: &mut ReflectionDatabase,
+    options: &StudioOptions,
+) -> anyhow::Result<()> {
     let fixture_place = generate_fixture_place(database);
-    let output = roundtrip_place_through_studio(&fixture_place)?;
+    let output = roundtrip_place_through_studio(&fixture_place, options)?;
 
     database.version = output.info.version;
 
@@ -43,6 +60,9 @@ fn apply_defaults_from_fixture_place(database: &mut ReflectionDatabase, tree: &W
     // Perform a breadth-first search to find the instance shallowest in the
     // tree of each class.
 
+    let index = PropertyIndex::new(database);
+    let mut defaults = Vec::new();
+
     let mut found_classes = HashSet::new();
     let mut to_visit = VecDeque::new();
 
@@ -60,7 +80,7 @@ fn apply_defaults_from_fixture_place(database: &mut ReflectionDatabase, tree: &W
         found_classes.insert(instance.class.clone());
 
         for (prop_name, prop_value) in &instance.properties {
-            let descriptors = match find_descriptors(database, &instance.class, prop_name) {
+            let descriptors = match index.get(&instance.class, prop_name) {
                 Some(descriptor) => descriptor,
                 None => {
                     log::warn!(
@@ -114,38 +134,134 @@ fn apply_defaults_from_fixture_place(database: &mut ReflectionDatabase, tree: &W
                     }
                 },
 
-                _ => panic!(
-                    "find_descriptors must not return a non-canonical descriptor as canonical"
-                ),
+                _ => {
+                    panic!("PropertyIndex must not return a non-canonical descriptor as canonical")
+                }
             }
 
             let canonical_name = Cow::Owned(descriptors.canonical.name.clone().into_owned());
 
-            match prop_value.ty() {
-                // We don't support usefully emitting these types yet.
-                VariantType::Ref | VariantType::SharedString => {}
+            match prop_value {
+                // Referents only mean something inside the place they came
+                // from, so nil is the only default worth recording.
+                Variant::Ref(referent) if referent.is_some() => {
+                    log::debug!(
+                        "Skipping non-nil default for Ref property {}.{}",
+                        instance.class,
+                        canonical_name
+                    );
+                }
 
-                _ => {
-                    let class_descriptor = match database.classes.get_mut(instance.class.as_str()) {
-                        Some(descriptor) => descriptor,
-                        None => {
-                            log::warn!(
-                                "Class {} found in default place but not API dump",
-                                instance.class
-                            );
-                            continue;
-                        }
-                    };
+                // SharedString can't be serialized as part of a Variant, so
+                // these defaults can't be written into the database.
+                Variant::SharedString(_) => {}
 
-                    class_descriptor
-                        .default_properties
-                        .insert(canonical_name, prop_value.clone());
-                }
+                _ => defaults.push((&instance.class, canonical_name, prop_value)),
             }
         }
     }
+
+    apply_defaults(database, defaults);
+    report_missing_defaults(database, &found_classes);
+}
+
+/// Logs every serializable property of the classes found in the fixture place
+/// that didn't get a default value, followed by a count, so that gaps in the
+/// place's coverage are easy to spot.
+fn report_missing_defaults(database: &ReflectionDatabase, found_classes: &HashSet<String>) {
+    let index = PropertyIndex::new(database);
+
+    let mut class_names: Vec<_> = found_classes.iter().collect();
+    class_names.sort();
+
+    let mut total = 0;
+    let mut missing = 0;
+
+    for class_name in class_names {
+        let (class, properties) = match (
+            database.classes.get(class_name.as_str()),
+            index.classes.get(class_name.as_str()),
+        ) {
+            (Some(class), Some(properties)) => (class, properties),
+            _ => continue,
+        };
+
+        let mut canonical: Vec<_> = properties
+            .values()
+            .map(|descriptors| descriptors.canonical)
+            .filter(|descriptor| expects_default(descriptor))
+            .map(|descriptor| descriptor.name.as_ref())
+            .collect();
+        canonical.sort_unstable();
+        canonical.dedup();
+
+        for prop_name in canonical {
+            total += 1;
+
+            if !class.default_properties.contains_key(prop_name) {
+                missing += 1;
+                log::warn!(
+                    "Property {}.{} serializes, but has no default value in the default place",
+                    class_name,
+                    prop_name
+                );
+            }
+        }
+    }
+
+    log::info!(
+        "{} of {} serializable properties have no default value",
+        missing,
+        total
+    );
+}
+
+/// Tells whether a canonical property should have gotten a default value
+/// from the fixture place.
+fn expects_default(descriptor: &PropertyDescriptor) -> bool {
+    let serializes = matches!(
+        descriptor.kind,
+        PropertyKind::Canonical {
+            serialization: PropertySerialization::Serializes
+                | PropertySerialization::SerializesAs(_),
+        }
+    );
+
+    // Defaults of this type can't be recorded.
+    let skipped_type = matches!(
+        descriptor.data_type,
+        DataType::Value(VariantType::SharedString)
+    );
+
+    serializes && !skipped_type
+}
+
+/// Inserts default values that were found in the fixture place into the
+/// database. This happens after the place has been searched so that the
+/// `PropertyIndex` used for the search can borrow the database.
+fn apply_defaults(
+    database: &mut ReflectionDatabase,
+    defaults: Vec<(&String, Cow<'static, str>, &Variant)>,
+) {
+    for (class_name, canonical_name, value) in defaults {
+        let class_descriptor = match database.classes.get_mut(class_name.as_str()) {
+            Some(descriptor) => descriptor,
+            None => {
+                log::warn!(
+                    "Class {} found in default place but not API dump",
+                    class_name
+                );
+                continue;
+            }
+        };
+
+        class_descriptor
+            .default_properties
+            .insert(canonical_name, value.clone());
+    }
 }
 
+#[derive(Clone, Copy)]
 struct Descriptors<'a> {
     // This descriptor might be useful in the future, but is currently unused.
     #[allow(unused)]
@@ -154,48 +270,70 @@ struct Descriptors<'a> {
     canonical: &'a PropertyDescriptor<'a>,
 }
 
-fn find_descriptors<'a>(
-    database: &'a ReflectionDatabase,
-    class_name: &str,
-    prop_name: &str,
-) -> Option<Descriptors<'a>> {
-    let mut input_descriptor = None;
-    let mut next_class_name = Some(class_name);
+/// Maps every class to all of the properties it has, including the ones it
+/// inherits, so that finding a property's descriptors doesn't require walking
+/// up the superclass chain every time.
+struct PropertyIndex<'a> {
+    classes: HashMap<&'a str, HashMap<&'a str, Descriptors<'a>>>,
+}
 
-    while let Some(current_class_name) = next_class_name {
-        let class = database.classes.get(current_class_name).unwrap();
+impl<'a> PropertyIndex<'a> {
+    fn new(database: &'a ReflectionDatabase) -> Self {
+        let mut index = Self {
+            classes: HashMap::new(),
+        };
 
-        if let Some(prop) = class.properties.get(prop_name) {
-            if input_descriptor.is_none() {
-                input_descriptor = Some(prop);
-            }
+        for class_name in database.classes.keys() {
+            index.add_class(database, class_name);
+        }
 
-            match &prop.kind {
-                PropertyKind::Canonical { .. } => {
-                    return Some(Descriptors {
-                        input: input_descriptor.unwrap(),
-                        canonical: prop,
-                    });
-                }
-                PropertyKind::Alias { alias_for } => {
-                    let aliased_prop = class.properties.get(alias_for).unwrap();
+        index
+    }
 
-                    return Some(Descriptors {
-                        input: input_descriptor.unwrap(),
-                        canonical: aliased_prop,
-                    });
-                }
+    /// Adds a class to the index, adding its superclasses first so that their
+    /// properties can be copied. A property declared by a class hides any
+    /// property with the same name from its superclasses.
+    fn add_class(&mut self, database: &'a ReflectionDatabase, class_name: &'a str) {
+        if self.classes.contains_key(class_name) {
+            return;
+        }
+
+        let class = &database.classes[class_name];
+
+        let mut properties = match class.superclass.as_deref() {
+            Some(superclass) if database.classes.contains_key(superclass) => {
+                self.add_class(database, superclass);
+                self.classes[superclass].clone()
+            }
+            _ => HashMap::new(),
+        };
+
+        for (prop_name, prop) in &class.properties {
+            let canonical = match &prop.kind {
+                PropertyKind::Canonical { .. } => prop,
+                PropertyKind::Alias { alias_for } => class.properties.get(alias_for).unwrap(),
                 unknown => {
                     log::warn!("Unknown property kind {:?}", unknown);
-                    return None;
+                    properties.remove(prop_name.as_ref());
+                    continue;
                 }
-            }
+            };
+
+            properties.insert(
+                prop_name,
+                Descriptors {
+                    input: prop,
+                    canonical,
+                },
+            );
         }
 
-        next_class_name = class.superclass.as_ref().map(|name| name.as_ref());
+        self.classes.insert(class_name, properties);
     }
 
-    None
+    fn get(&self, class_name: &str, prop_name: &str) -> Option<Descriptors<'a>> {
+        self.classes.get(class_name)?.get(prop_name).copied()
+    }
 }
 
 struct StudioOutput {
@@ -205,7 +343,10 @@ struct StudioOutput {
 
 /// Generate a new fixture place from the given reflection database, open it in
 /// Studio, coax Studio to re-save it, and reads back the resulting place.
-fn roundtrip_place_through_studio(place_contents: &str) -> anyhow::Result<StudioOutput> {
+fn roundtrip_place_through_studio(
+    place_contents: &str,
+    options: &StudioOptions,
+) -> anyhow::Result<StudioOutput> {
     let output_dir = tempdir()?;
     let output_path = output_dir.path().join("GenerateReflectionRoundtrip.rbxlx");
     log::info!("Generating place at {}", output_path.display());
@@ -228,29 +369,29 @@ fn roundtrip_place_through_studio(place_contents: &str) -> anyhow::Result<Studio
 
     log::info!("Waiting for Roblox Studio to re-save place...");
 
-    #[cfg(target_os = "windows")]
-    {
-        let did_send_chord =
-            Innerput::new().send_chord(&[Key::Control, Key::Char('s')], &studio_process);
-
-        match did_send_chord {
-            Ok(()) => (),
-            Err(err) => {
-                log::error!("{}", err);
-
-                println!(
-                "Failed to send key chord to Roblox Studio. Please save the opened place manually."
-                )
-            }
-        }
+    if options.manual_save {
+        println!("Please save the opened place in Roblox Studio (ctrl+s).");
+    } else {
+        send_save_chord(&studio_process);
     }
 
-    #[cfg(not(target_os = "windows"))]
-    println!("Please save the opened place in Roblox Studio (ctrl+s).");
+    let deadline = Instant::now() + options.save_timeout;
 
     loop {
-        if let DebouncedEvent::Write(_) = rx.recv()? {
-            break;
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        match rx.recv_timeout(remaining) {
+            Ok(DebouncedEvent::Write(_)) => break,
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout) => {
+                studio_process.kill()?;
+
+                anyhow::bail!(
+                    "Roblox Studio did not save the place within {} seconds",
+                    options.save_timeout.as_secs()
+                );
+            }
+            Err(err @ RecvTimeoutError::Disconnected) => return Err(err.into()),
         }
     }
 
@@ -277,6 +418,79 @@ fn roundtrip_place_through_studio(place_contents: &str) -> anyhow::Result<Studio
     Ok(StudioOutput { info, tree })
 }
 
+/// How long to wait after the plugin reports in before sending a save
+/// keystroke to Studio, which may still be settling its window focus.
+#[cfg(target_os = "windows")]
+const SAVE_CHORD_DELAY: Duration = Duration::from_secs(1);
+
+/// Sends ctrl+s to Studio so that it saves the opened place. Falls back to
+/// asking the operator if the keystroke can't be sent.
+#[cfg(target_os = "windows")]
+fn send_save_chord(studio_process: &std::process::Child) {
+    std::thread::sleep(SAVE_CHORD_DELAY);
+
+    let did_send_chord =
+        Innerput::new().send_chord(&[Key::Control, Key::Char('s')], studio_process);
+
+    if let Err(err) = did_send_chord {
+        log::error!("{}", err);
+
+        println!(
+            "Failed to send key chord to Roblox Studio. Please save the opened place manually."
+        )
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn send_save_chord(_studio_process: &std::process::Child) {
+    println!("Please save the opened place in Roblox Studio (ctrl+s).");
+}
+
+/// Classes that are left out of the fixture place entirely.
+const SKIPPED_CLASSES: &[&str] = &[
+    // These types can't be put into place files by default.
+    "DebuggerWatch",
+    "DebuggerBreakpoint",
+    "AdvancedDragger",
+    "Dragger",
+    "ScriptDebugger",
+    "PackageLink",
+    // Ad and AdGui instances cause Studio to crash immediately on launch.
+    "Ad",
+    "AdGui",
+    // AdPortal instances cause an angry message about a product feature not
+    // being enabled yet.
+    "AdPortal",
+    // WorldModel is not yet enabled.
+    "WorldModel",
+];
+
+/// Classes whose instances in the fixture place are given children, usually
+/// because those children can only be parented to them. Classes listed as
+/// children here are only placed as children, never at the top level.
+const REQUIRED_CHILDREN: &[(&str, &[&str])] = &[
+    (
+        "StarterPlayer",
+        &["StarterPlayerScripts", "StarterCharacterScripts"],
+    ),
+    ("Workspace", &["Terrain"]),
+    ("Part", &["Attachment", "Bone"]),
+    ("Humanoid", &["Animator"]),
+    // Without this special case, Studio will fail to open the resulting file,
+    // complaining about "BaseWrap".
+    ("MeshPart", &["BaseWrap", "WrapLayer", "WrapTarget"]),
+];
+
+/// Writes the fixture place for the given database to `path` without
+/// starting Roblox Studio, so that it can be inspected by hand.
+pub fn write_fixture_place(database: &ReflectionDatabase, path: &Path) -> anyhow::Result<()> {
+    let fixture_place = generate_fixture_place(database);
+    fs::write(path, fixture_place)
+        .with_context(|| format!("failed to write fixture place to {}", path.display()))?;
+
+    Ok(())
+}
+
 /// Create a place file that contains a copy of every Roblox class and no
 /// properties defined.
 ///
@@ -285,63 +499,33 @@ fn roundtrip_place_through_studio(place_contents: &str) -> anyhow::Result<Studio
 fn generate_fixture_place(database: &ReflectionDatabase) -> String {
     log::info!("Generating place with every instance...");
 
+    let child_classes: HashSet<&str> = REQUIRED_CHILDREN
+        .iter()
+        .flat_map(|(_, children)| children.iter().copied())
+        .collect();
+
+    let mut class_names: Vec<&str> = database
+        .classes
+        .keys()
+        .map(|name| name.as_ref())
+        .filter(|name| !SKIPPED_CLASSES.contains(name) && !child_classes.contains(name))
+        .collect();
+    class_names.sort_unstable();
+
     let mut output = String::new();
 
     writeln!(&mut output, "<roblox version=\"4\">").unwrap();
 
-    for descriptor in database.classes.values() {
-        let mut instance = FixtureInstance::named(&descriptor.name);
-
-        match &*descriptor.name {
-            // These types can't be put into place files by default.
-            "DebuggerWatch" | "DebuggerBreakpoint" | "AdvancedDragger" | "Dragger"
-            | "ScriptDebugger" | "PackageLink" => continue,
-
-            // These types have specific parenting restrictions handled
-            // elsewhere.
-            "Terrain"
-            | "Attachment"
-            | "Animator"
-            | "StarterPlayerScripts"
-            | "StarterCharacterScripts"
-            | "Bone"
-            | "BaseWrap"
-            | "WrapLayer"
-            | "WrapTarget" => continue,
-
-            // Ad and AdGui instances cause Studio to crash immediately on
-            // launch.
-            "Ad" | "AdGui" => continue,
-
-            // AdPortal instances cause an angry message about a product feature
-            // not being enabled yet.
-            "AdPortal" => continue,
-
-            // WorldModel is not yet enabled.
-            "WorldModel" => continue,
-
-            "StarterPlayer" => {
-                instance.add_child(FixtureInstance::named("StarterPlayerScripts"));
-                instance.add_child(FixtureInstance::named("StarterCharacterScripts"));
-            }
-            "Workspace" => {
-                instance.add_child(FixtureInstance::named("Terrain"));
-            }
-            "Part" => {
-                instance.add_child(FixtureInstance::named("Attachment"));
-                instance.add_child(FixtureInstance::named("Bone"));
-            }
-            "Humanoid" => {
-                instance.add_child(FixtureInstance::named("Animator"));
-            }
-            "MeshPart" => {
-                // Without this special case, Studio will fail to open the
-                // resulting file, complaining about "BaseWrap".
-                instance.add_child(FixtureInstance::named("BaseWrap"));
-                instance.add_child(FixtureInstance::named("WrapLayer"));
-                instance.add_child(FixtureInstance::named("WrapTarget"));
-            }
-            _ => {}
+    for class_name in class_names {
+        let mut instance = FixtureInstance::named(class_name);
+
+        let children = REQUIRED_CHILDREN
+            .iter()
+            .filter(|(parent, _)| *parent == class_name)
+            .flat_map(|(_, children)| children.iter());
+
+        for child in children {
+            instance.add_child(FixtureInstance::named(child));
         }
 
         write!(output, "{}", instance).unwrap();
@@ -386,3 +570,23 @@ impl fmt::Display for FixtureInstance<'_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rbx_reflection::ClassDescriptor;
+
+    #[test]
+    fn fixture_place() {
+        let mut database = ReflectionDatabase::new();
+
+        for name in &["Workspace", "Terrain", "Folder", "Dragger", "Part", "Bone"] {
+            database
+                .classes
+                .insert(Cow::Borrowed(*name), ClassDescriptor::new(*name));
+        }
+
+        insta::assert_snapshot!(generate_fixture_place(&database));
+    }
+}
@@ -6,12 +6,13 @@ mod values;
 
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use rbx_reflection::ReflectionDatabase;
 use structopt::StructOpt;
 
 use crate::api_dump::Dump;
-use crate::defaults_place::measure_default_properties;
+use crate::defaults_place::{measure_default_properties, write_fixture_place, StudioOptions};
 use crate::property_patches::PropertyPatches;
 
 #[derive(Debug, StructOpt)]
@@ -27,6 +28,21 @@ struct Options {
 
     #[structopt(long = "values")]
     values_path: Option<PathBuf>,
+
+    /// Write the place file that would be opened in Studio to this path and
+    /// exit without starting Studio.
+    #[structopt(long = "fixture-place")]
+    fixture_place_path: Option<PathBuf>,
+
+    /// Wait for the place to be saved in Studio by hand instead of sending
+    /// ctrl+s to Studio automatically.
+    #[structopt(long = "manual-save")]
+    manual_save: bool,
+
+    /// How many seconds to wait for Studio to save the place before giving
+    /// up.
+    #[structopt(long = "save-timeout", default_value = "300")]
+    save_timeout_secs: u64,
 }
 
 fn run(options: Options) -> anyhow::Result<()> {
@@ -40,7 +56,16 @@ fn run(options: Options) -> anyhow::Result<()> {
         property_patches.apply(&mut database)?;
     }
 
-    measure_default_properties(&mut database)?;
+    if let Some(path) = &options.fixture_place_path {
+        return write_fixture_place(&database, path);
+    }
+
+    let studio_options = StudioOptions {
+        manual_save: options.manual_save,
+        save_timeout: Duration::from_secs(options.save_timeout_secs),
+    };
+
+    measure_default_properties(&mut database, &studio_options)?;
 
     // TODO
     // database.validate();
@@ -52,11 +77,11 @@ fn run(options: Options) -> anyhow::Result<()> {
 
     if let Some(path) = &options.json_path {
         let encoded = serde_json::to_string_pretty(&database)?;
-        fs::write(&path, encoded)?;
+        fs::write(path, encoded)?;
     }
 
     if let Some(path) = &options.values_path {
-        fs::write(&path, values::encode()?)?;
+        fs::write(path, values::encode()?)?;
     }
 
     Ok(())
@@ -1,4 +1,8 @@
 use criterion::{criterion_group, criterion_main, Criterion};
+use rbx_dom_weak::{
+    types::{Color3uint8, Vector3},
+    InstanceBuilder, WeakDom,
+};
 
 pub fn de_folders_100(c: &mut Criterion) {
     static BUFFER: &[u8] = include_bytes!("../bench-files/folders-100.rbxm");
@@ -30,6 +34,63 @@ pub fn de_modulescripts_100_lines_100(c: &mut Criterion) {
     });
 }
 
+pub fn de_skeleton_modulescripts_100_lines_100(c: &mut Criterion) {
+    static BUFFER: &[u8] = include_bytes!("../bench-files/modulescripts-100-lines-100.rbxm");
+
+    c.bench_function("Deserialize skeleton of 100 100-line ModuleScripts", |b| {
+        b.iter(|| {
+            rbx_binary::Deserializer::new()
+                .deserialize_skeleton(BUFFER)
+                .unwrap();
+        });
+    });
+}
+
+pub fn de_large_place(c: &mut Criterion) {
+    let buffer = large_place();
+
+    c.bench_function("Deserialize large place", |b| {
+        b.iter(|| {
+            rbx_binary::Deserializer::new()
+                .deserialize(buffer.as_slice())
+                .unwrap();
+        });
+    });
+
+    c.bench_function("Deserialize large place in parallel", |b| {
+        b.iter(|| {
+            rbx_binary::Deserializer::new()
+                .deserialize_parallel(buffer.as_slice())
+                .unwrap();
+        });
+    });
+}
+
+/// Builds a place with many instances of several classes, so that it has lots
+/// of large PROP chunks to decode.
+fn large_place() -> Vec<u8> {
+    let mut tree = WeakDom::new(InstanceBuilder::new("Folder"));
+    let root_ref = tree.root_ref();
+
+    for i in 0..5000 {
+        for class in &["Part", "WedgePart", "CornerWedgePart", "TrussPart"] {
+            tree.insert(
+                root_ref,
+                InstanceBuilder::new(*class)
+                    .with_name(format!("{} {}", class, i))
+                    .with_property("Anchored", i % 2 == 0)
+                    .with_property("Transparency", i as f32 / 5000.0)
+                    .with_property("Size", Vector3::new(i as f32, 1.0, 2.0))
+                    .with_property("Color", Color3uint8::new(i as u8, 0, 255)),
+            );
+        }
+    }
+
+    let mut buffer = Vec::new();
+    rbx_binary::to_writer(&mut buffer, &tree, &[root_ref]).unwrap();
+    buffer
+}
+
 #[inline(always)]
 fn deserialize_bench(buffer: &[u8]) {
     rbx_binary::from_reader(buffer).unwrap();
@@ -39,6 +100,8 @@ criterion_group!(
     deserializer,
     de_folders_100,
     de_deep_folders_100,
-    de_modulescripts_100_lines_100
+    de_modulescripts_100_lines_100,
+    de_skeleton_modulescripts_100_lines_100,
+    de_large_place
 );
 criterion_main!(deserializer);
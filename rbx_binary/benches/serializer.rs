@@ -1,5 +1,6 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 
+use rbx_binary::Serializer;
 use rbx_dom_weak::{InstanceBuilder, WeakDom};
 
 pub fn ser_folders_100(c: &mut Criterion) {
@@ -13,16 +14,28 @@ pub fn ser_folders_100(c: &mut Criterion) {
         );
     }
 
+    let serializer = Serializer::new();
     let mut buffer = Vec::new();
 
     // Encode once into the buffer to pre-size it.
-    rbx_binary::to_writer(&mut buffer, &tree, &[root_ref]).unwrap();
-    buffer.clear();
+    serializer
+        .serialize_into(&mut buffer, &tree, &[root_ref])
+        .unwrap();
 
     c.bench_function("Serialize 100 Folders", |b| {
         b.iter(|| {
+            serializer
+                .serialize_into(&mut buffer, &tree, &[root_ref])
+                .unwrap();
+        });
+    });
+
+    // For comparison, allocating a new buffer for every model.
+    c.bench_function("Serialize 100 Folders (new buffer each time)", |b| {
+        b.iter(|| {
+            let mut buffer = Vec::new();
             rbx_binary::to_writer(&mut buffer, &tree, &[root_ref]).unwrap();
-            buffer.clear();
+            buffer
         });
     });
 }
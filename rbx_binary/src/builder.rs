@@ -0,0 +1,95 @@
+use std::io::Write;
+
+use rbx_dom_weak::{types::Ref, InstanceBuilder, WeakDom};
+
+use crate::serializer::{Error, Serializer};
+
+/// Assembles a binary model from instances, without needing to build a
+/// [`WeakDom`] first.
+///
+/// Type IDs, referents, and the grouping of properties into chunks are all
+/// handled by the [`Serializer`], the same way they are when writing a
+/// `WeakDom`.
+///
+/// ## Example
+/// ```
+/// use rbx_binary::ModelBuilder;
+/// use rbx_dom_weak::InstanceBuilder;
+///
+/// let mut builder = ModelBuilder::new();
+/// builder.add_instance(
+///     InstanceBuilder::new("Folder")
+///         .with_name("Assets")
+///         .with_child(InstanceBuilder::new("StringValue").with_property("Value", "Hello")),
+/// );
+///
+/// let model = builder.build()?;
+/// let dom = rbx_binary::from_reader(model.as_slice())?;
+/// assert_eq!(dom.root().children().len(), 1);
+///
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct ModelBuilder<'db> {
+    dom: WeakDom,
+    serializer: Serializer<'db>,
+}
+
+impl<'db> ModelBuilder<'db> {
+    /// Create a new, empty `ModelBuilder` that writes using the default
+    /// [`Serializer`] settings.
+    pub fn new() -> Self {
+        ModelBuilder {
+            dom: WeakDom::new(InstanceBuilder::new("DataModel")),
+            serializer: Serializer::new(),
+        }
+    }
+
+    /// Writes the model with the given `Serializer`, which controls things
+    /// like compression and metadata.
+    pub fn serializer(mut self, serializer: Serializer<'db>) -> Self {
+        self.serializer = serializer;
+        self
+    }
+
+    /// Adds an instance and its children to the top level of the model.
+    pub fn with_instance(mut self, instance: InstanceBuilder) -> Self {
+        self.add_instance(instance);
+        self
+    }
+
+    /// Adds an instance and its children to the top level of the model,
+    /// returning its referent.
+    ///
+    /// `Ref` properties can point at any instance added to the builder, using
+    /// the referent returned here or from [`InstanceBuilder::referent`].
+    pub fn add_instance(&mut self, instance: InstanceBuilder) -> Ref {
+        let root_ref = self.dom.root_ref();
+        self.dom.insert(root_ref, instance)
+    }
+
+    /// The instances added to the model so far. The top-level instances are
+    /// the children of its root.
+    pub fn dom(&self) -> &WeakDom {
+        &self.dom
+    }
+
+    /// Writes the model to the given stream.
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), Error> {
+        self.serializer
+            .serialize(writer, &self.dom, self.dom.root().children())
+    }
+
+    /// Writes the model into a new buffer.
+    pub fn build(&self) -> Result<Vec<u8>, Error> {
+        let mut buffer = Vec::new();
+        self.write(&mut buffer)?;
+
+        Ok(buffer)
+    }
+}
+
+impl Default for ModelBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -145,6 +145,37 @@ pub(crate) fn from_basic_rotation_id(id: u8) -> Option<Matrix3> {
     }
 }
 
+/// Returns the rotation matrix that the given orientation ID stands for, as
+/// its rows in order, or `None` if the ID isn't one of the 24 that Roblox
+/// uses.
+///
+/// Roblox stores rotations that only swap and flip axes as a single byte
+/// instead of a full matrix. The ID is `6 * x + y + 1`, where `x` and `y` are
+/// the `NormalId`s of the directions that the rotated X and Y axes point in.
+pub fn orientation_id_to_matrix(id: u8) -> Option<[f32; 9]> {
+    let matrix = from_basic_rotation_id(id)?;
+
+    Some([
+        matrix.x.x, matrix.x.y, matrix.x.z, matrix.y.x, matrix.y.y, matrix.y.z, matrix.z.x,
+        matrix.z.y, matrix.z.z,
+    ])
+}
+
+/// Returns the orientation ID for the given rotation matrix, given as its rows
+/// in order, or `None` if the rotation can't be stored as one. This is the ID
+/// that the serializer writes in place of the matrix.
+///
+/// Components within a small tolerance of -1, 0, or 1 are treated as those
+/// values, so a rotation that has picked up floating point error still gets
+/// an ID.
+pub fn matrix_to_orientation_id(matrix: [f32; 9]) -> Option<u8> {
+    to_basic_rotation_id(Matrix3::new(
+        Vector3::new(matrix[0], matrix[1], matrix[2]),
+        Vector3::new(matrix[3], matrix[4], matrix[5]),
+        Vector3::new(matrix[6], matrix[7], matrix[8]),
+    ))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -157,4 +188,54 @@ mod test {
             }
         }
     }
+    #[test]
+    fn orientation_ids() {
+        let ids: Vec<u8> = (0..=u8::MAX)
+            .filter(|&id| orientation_id_to_matrix(id).is_some())
+            .collect();
+
+        // Every ID is made from two different axes that aren't opposites.
+        let expected: Vec<u8> = (0..6u8)
+            .flat_map(|x| (0..6u8).map(move |y| (x, y)))
+            .filter(|(x, y)| x % 3 != y % 3)
+            .map(|(x, y)| 6 * x + y + 1)
+            .collect();
+        assert_eq!(ids, expected);
+
+        for id in ids {
+            let matrix = orientation_id_to_matrix(id).unwrap();
+            assert_eq!(matrix_to_orientation_id(matrix), Some(id));
+        }
+
+        assert_eq!(
+            orientation_id_to_matrix(0x02),
+            Some([1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0])
+        );
+        assert_eq!(
+            matrix_to_orientation_id([1.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, 1.0, 0.0]),
+            Some(0x03)
+        );
+        assert_eq!(orientation_id_to_matrix(0x00), None);
+        assert_eq!(orientation_id_to_matrix(0x04), None);
+    }
+
+    #[test]
+    fn orientation_id_tolerance() {
+        let nearly_identity = [1.0, 1e-7, 0.0, 0.0, 0.9999999, 0.0, 0.0, 0.0, 1.0];
+        assert_eq!(matrix_to_orientation_id(nearly_identity), Some(0x02));
+
+        let angle = 0.5f32;
+        let rotated = [
+            angle.cos(),
+            -angle.sin(),
+            0.0,
+            angle.sin(),
+            angle.cos(),
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+        ];
+        assert_eq!(matrix_to_orientation_id(rotated), None);
+    }
 }
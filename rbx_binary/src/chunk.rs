@@ -4,46 +4,216 @@ use std::{
     str,
 };
 
+#[cfg(any(test, feature = "unstable_text_format"))]
+use serde::Serialize;
+
 use crate::core::{RbxReadExt, RbxWriteExt};
 
+/// The bytes that every ZSTD frame begins with. Roblox has started writing
+/// some chunks with ZSTD instead of LZ4.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
 /// Represents one chunk from a binary model file.
 #[derive(Debug)]
 pub struct Chunk {
     pub name: [u8; 4],
     pub data: Vec<u8>,
+
+    /// How the chunk was stored in the file it was read from.
+    #[cfg_attr(not(any(test, feature = "unstable_text_format")), allow(dead_code))]
+    pub compression: StoredCompression,
+}
+
+/// How a chunk's contents were stored in the file it was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    any(test, feature = "unstable_text_format"),
+    derive(Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum StoredCompression {
+    /// The contents were stored as-is.
+    Raw,
+
+    /// The contents were compressed with LZ4.
+    Lz4,
+
+    /// The contents were compressed with ZSTD.
+    Zstd,
+}
+
+impl StoredCompression {
+    /// Works out how a chunk was stored. Chunks with a compressed length of
+    /// zero are stored as-is. Otherwise, LZ4 blocks have no header of their
+    /// own, so compressed chunks are LZ4 unless they start with the ZSTD magic
+    /// number.
+    fn detect(header: &ChunkHeader, stored: &[u8]) -> Self {
+        if header.compressed_len == 0 {
+            StoredCompression::Raw
+        } else if stored.starts_with(&ZSTD_MAGIC) {
+            StoredCompression::Zstd
+        } else {
+            StoredCompression::Lz4
+        }
+    }
 }
 
 impl Chunk {
     /// Reads and decodes a `Chunk` from the given reader.
-    pub fn decode<R: Read>(mut reader: R) -> io::Result<Chunk> {
+    #[cfg_attr(not(any(test, feature = "unstable_text_format")), allow(dead_code))]
+    pub fn decode<R: Read>(reader: R) -> io::Result<Chunk> {
+        Self::decode_or_skip(reader, |_| false)
+    }
+
+    /// Reads and decodes a `Chunk` from the given reader, unless `skip`
+    /// returns true for the chunk's name. Skipped chunks are read past without
+    /// being decompressed and are returned with no data.
+    pub fn decode_or_skip<R, F>(mut reader: R, skip: F) -> io::Result<Chunk>
+    where
+        R: Read,
+        F: Fn(&[u8; 4]) -> bool,
+    {
         let header = decode_chunk_header(&mut reader)?;
 
         log::trace!("{}", header);
 
-        let data = if header.compressed_len == 0 {
-            let mut data = Vec::with_capacity(header.len as usize);
-            reader.take(header.len as u64).read_to_end(&mut data)?;
-            data
-        } else {
-            let mut compressed_data = Vec::with_capacity(header.compressed_len as usize);
-            reader
-                .take(header.compressed_len as u64)
-                .read_to_end(&mut compressed_data)?;
+        if skip(&header.name) {
+            io::copy(
+                &mut (&mut reader).take(header.stored_len() as u64),
+                &mut io::sink(),
+            )?;
+
+            return Ok(Chunk {
+                name: header.name,
+                data: Vec::new(),
+                compression: header.compression(),
+            });
+        }
 
-            lz4::block::decompress(&compressed_data, Some(header.len as i32))?
+        let stored = read_stored(reader, &header)?;
+        Self::from_stored(header, stored)
+    }
+
+    /// Decodes a chunk from its header and the bytes stored after it.
+    fn from_stored(header: ChunkHeader, stored: Vec<u8>) -> io::Result<Chunk> {
+        let compression = StoredCompression::detect(&header, &stored);
+        let data = match compression {
+            StoredCompression::Raw => stored,
+            StoredCompression::Lz4 => lz4::block::decompress(&stored, Some(header.len as i32))?,
+            StoredCompression::Zstd => zstd::bulk::decompress(&stored, header.len as usize)?,
         };
 
-        assert_eq!(data.len(), header.len as usize);
+        if data.len() != header.len as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("{} ended after only {} bytes", header, data.len()),
+            ));
+        }
 
         Ok(Chunk {
             name: header.name,
             data,
+            compression,
         })
     }
 }
 
+/// Reads the bytes stored in the file after a chunk's header.
+fn read_stored<R: Read>(reader: R, header: &ChunkHeader) -> io::Result<Vec<u8>> {
+    let mut stored = Vec::with_capacity(header.stored_len() as usize);
+    reader
+        .take(header.stored_len() as u64)
+        .read_to_end(&mut stored)?;
+
+    Ok(stored)
+}
+
+/// Reads the chunks of a file one after another.
+///
+/// Some tools write chunks whose declared length is longer than their
+/// contents, so that the declared length runs into the next chunk. When
+/// `recover_lengths` is set, a chunk that isn't followed by something that
+/// looks like a chunk header is searched for the start of the next chunk, and
+/// is cut short there if one is found. This is a heuristic, so it's opt-in.
+pub struct ChunkReader<R> {
+    reader: R,
+
+    /// Bytes that were read past the end of a chunk that was cut short, which
+    /// need to be read again before anything else from `reader`.
+    leftover: io::Cursor<Vec<u8>>,
+
+    recover_lengths: bool,
+}
+
+impl<R: Read> ChunkReader<R> {
+    /// Creates a new `ChunkReader` that reads chunks from `reader`.
+    pub fn new(reader: R, recover_lengths: bool) -> Self {
+        Self {
+            reader,
+            leftover: io::Cursor::new(Vec::new()),
+            recover_lengths,
+        }
+    }
+
+    /// Reads and decodes the next `Chunk`.
+    pub fn next_chunk(&mut self) -> io::Result<Chunk> {
+        if !self.recover_lengths {
+            return Chunk::decode(&mut self.reader);
+        }
+
+        let mut input = (&mut self.leftover).chain(&mut self.reader);
+
+        let mut header = decode_chunk_header(&mut input)?;
+        log::trace!("{}", header);
+
+        let mut stored = Vec::with_capacity(header.stored_len() as usize);
+        (&mut input)
+            .take(header.stored_len() as u64)
+            .read_to_end(&mut stored)?;
+
+        // Nothing comes after the END chunk, so there's nothing to check it
+        // against.
+        let mut next = Vec::with_capacity(HEADER_LEN);
+        if &header.name != b"END\0" {
+            input.take(HEADER_LEN as u64).read_to_end(&mut next)?;
+
+            if !looks_like_header(&next) {
+                // The next chunk's header can start inside of this chunk and
+                // end in the bytes we read ahead.
+                let stored_len = stored.len();
+                stored.append(&mut next);
+
+                let next_start = (1..stored_len).find(|&start| looks_like_header(&stored[start..]));
+                next = stored.split_off(next_start.unwrap_or(stored_len));
+
+                if let Some(next_start) = next_start {
+                    log::warn!(
+                        "{} was cut short to {} bytes because it overlaps the next chunk",
+                        header,
+                        next_start
+                    );
+
+                    if header.compressed_len == 0 {
+                        header.len = next_start as u32;
+                    } else {
+                        header.compressed_len = next_start as u32;
+                    }
+                }
+            }
+        }
+
+        // Whatever we read ahead has to be read again as part of the next
+        // chunk, ahead of anything left over from before.
+        let position = self.leftover.position() as usize;
+        next.extend_from_slice(&self.leftover.get_ref()[position..]);
+        self.leftover = io::Cursor::new(next);
+
+        Chunk::from_stored(header, stored)
+    }
+}
+
 /// The compression format of a chunk in the binary model format.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChunkCompression {
     /// The contents of the chunk should be LZ4 compressed.
     Compressed,
@@ -59,7 +229,7 @@ pub enum ChunkCompression {
 /// automatically.
 #[must_use]
 pub struct ChunkBuilder {
-    chunk_name: &'static [u8],
+    chunk_name: [u8; 4],
     compression: ChunkCompression,
     buffer: Vec<u8>,
 }
@@ -67,9 +237,9 @@ pub struct ChunkBuilder {
 impl ChunkBuilder {
     /// Creates a new `ChunkBuilder` with the given name and compression
     /// setting.
-    pub fn new(chunk_name: &'static [u8], compression: ChunkCompression) -> Self {
+    pub fn new(chunk_name: &[u8; 4], compression: ChunkCompression) -> Self {
         ChunkBuilder {
-            chunk_name,
+            chunk_name: *chunk_name,
             compression,
             buffer: Vec::new(),
         }
@@ -77,7 +247,7 @@ impl ChunkBuilder {
 
     /// Consume the chunk and write it to the given writer.
     pub fn dump<W: Write>(self, mut writer: W) -> io::Result<()> {
-        writer.write_all(self.chunk_name)?;
+        writer.write_all(&self.chunk_name)?;
 
         match self.compression {
             ChunkCompression::Compressed => {
@@ -112,6 +282,24 @@ impl Write for ChunkBuilder {
     }
 }
 
+/// The number of bytes in a chunk header.
+const HEADER_LEN: usize = 16;
+
+/// The names of every chunk that we know how to read.
+const KNOWN_CHUNK_NAMES: [&[u8; 4]; 7] = [
+    b"META", b"SSTR", b"INST", b"PROP", b"PRNT", b"SIGN", b"END\0",
+];
+
+/// Tells whether `bytes` starts with something that looks like the header of
+/// a chunk we know about.
+fn looks_like_header(bytes: &[u8]) -> bool {
+    bytes.len() >= HEADER_LEN
+        && KNOWN_CHUNK_NAMES
+            .iter()
+            .any(|name| bytes.starts_with(*name))
+        && bytes[12..HEADER_LEN] == [0; 4]
+}
+
 #[derive(Debug)]
 struct ChunkHeader {
     /// 4-byte short name for the chunk, like "INST" or "PRNT"
@@ -129,6 +317,28 @@ struct ChunkHeader {
     reserved: u32,
 }
 
+impl ChunkHeader {
+    /// How the chunk is stored, judging only by the header. ZSTD chunks look
+    /// like LZ4 chunks here; [`StoredCompression::detect`] tells them apart
+    /// by looking at the stored data.
+    fn compression(&self) -> StoredCompression {
+        if self.compressed_len == 0 {
+            StoredCompression::Raw
+        } else {
+            StoredCompression::Lz4
+        }
+    }
+
+    /// The number of bytes stored in the file after this header.
+    fn stored_len(&self) -> u32 {
+        if self.compressed_len == 0 {
+            self.len
+        } else {
+            self.compressed_len
+        }
+    }
+}
+
 impl fmt::Display for ChunkHeader {
     fn fmt(&self, output: &mut fmt::Formatter) -> fmt::Result {
         let name = if let Ok(name) = str::from_utf8(&self.name) {
@@ -154,10 +364,13 @@ fn decode_chunk_header<R: Read>(source: &mut R) -> io::Result<ChunkHeader> {
     let reserved = source.read_le_u32()?;
 
     if reserved != 0 {
-        panic!(
-            "Chunk reserved space was not zero, it was {}. This chunk may be malformed.",
-            reserved
-        );
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Chunk reserved space was not zero, it was {}. This chunk may be malformed.",
+                reserved
+            ),
+        ));
     }
 
     Ok(ChunkHeader {
@@ -167,3 +380,119 @@ fn decode_chunk_header<R: Read>(source: &mut R) -> io::Result<ChunkHeader> {
         reserved,
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn chunk(name: &[u8; 4], compression: ChunkCompression, data: &[u8]) -> Vec<u8> {
+        let mut builder = ChunkBuilder::new(name, compression);
+        builder.write_all(data).unwrap();
+
+        let mut output = Vec::new();
+        builder.dump(&mut output).unwrap();
+        output
+    }
+
+    fn zstd_chunk(name: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let compressed = zstd::bulk::compress(data, 0).unwrap();
+
+        let mut output = name.to_vec();
+        output.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        output.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        output.extend_from_slice(&0u32.to_le_bytes());
+        output.extend_from_slice(&compressed);
+        output
+    }
+
+    /// Every chunk picks its own compression, so one file can contain any
+    /// mix of them. Reading one chunk must leave the reader exactly at the
+    /// start of the next.
+    #[test]
+    fn mixed_compression() {
+        let lz4_data = b"lz4 lz4 lz4 lz4 lz4 lz4 lz4 lz4".to_vec();
+        let stored_data = b"stored".to_vec();
+
+        let mut file = Vec::new();
+        file.extend(chunk(b"LZ4A", ChunkCompression::Compressed, &lz4_data));
+        file.extend(chunk(b"RAWA", ChunkCompression::Uncompressed, &stored_data));
+        file.extend(chunk(b"LZ4B", ChunkCompression::Compressed, &stored_data));
+        file.extend(chunk(b"RAWB", ChunkCompression::Uncompressed, &lz4_data));
+        file.extend(zstd_chunk(b"ZSTD", &lz4_data));
+        file.extend(chunk(
+            b"END\0",
+            ChunkCompression::Uncompressed,
+            b"</roblox>",
+        ));
+
+        let mut reader = file.as_slice();
+        let expected: [(&[u8; 4], &[u8]); 5] = [
+            (b"LZ4A", &lz4_data),
+            (b"RAWA", &stored_data),
+            (b"LZ4B", &stored_data),
+            (b"RAWB", &lz4_data),
+            (b"ZSTD", &lz4_data),
+        ];
+
+        for (name, data) in &expected {
+            let chunk = Chunk::decode(&mut reader).unwrap();
+            assert_eq!(&chunk.name, *name);
+            assert_eq!(chunk.data.as_slice(), *data);
+        }
+
+        let end = Chunk::decode(&mut reader).unwrap();
+        assert_eq!(&end.name, b"END\0");
+        assert_eq!(end.data.as_slice(), b"</roblox>");
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn stored_compression() {
+        let mut file = Vec::new();
+        file.extend(chunk(b"LZ4A", ChunkCompression::Compressed, b"lz4"));
+        file.extend(chunk(b"RAWA", ChunkCompression::Uncompressed, b"raw"));
+        file.extend(zstd_chunk(b"ZSTD", b"zstd"));
+        file.extend(chunk(b"END\0", ChunkCompression::Uncompressed, b""));
+
+        let mut reader = file.as_slice();
+        let expected = [
+            StoredCompression::Lz4,
+            StoredCompression::Raw,
+            StoredCompression::Zstd,
+            StoredCompression::Raw,
+        ];
+
+        for compression in &expected {
+            let chunk = Chunk::decode(&mut reader).unwrap();
+            assert_eq!(chunk.compression, *compression);
+        }
+
+        assert!(reader.is_empty());
+    }
+
+    /// A chunk holding a ZSTD frame written by the reference `zstd` tool for
+    /// the text "PROP PROP PROP PROP" is recognized from its magic number and
+    /// decompressed.
+    #[test]
+    fn zstd_frame() {
+        let frame = [
+            0x28, 0xb5, 0x2f, 0xfd, 0x20, 0x13, 0x5d, 0x00, 0x00, 0x28, 0x50, 0x52, 0x4f, 0x50,
+            0x20, 0x01, 0x00, 0x90, 0x8b, 0x16,
+        ];
+
+        let mut file = b"PROP".to_vec();
+        file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(&19u32.to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&frame);
+
+        let chunk = Chunk::decode(file.as_slice()).unwrap();
+        assert_eq!(chunk.compression, StoredCompression::Zstd);
+        assert_eq!(chunk.data.as_slice(), b"PROP PROP PROP PROP");
+
+        // A frame that holds more than the header says is rejected instead of
+        // being decompressed in full.
+        file[8..12].copy_from_slice(&4u32.to_le_bytes());
+        Chunk::decode(file.as_slice()).unwrap_err();
+    }
+}
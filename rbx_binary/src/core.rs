@@ -3,6 +3,7 @@ use std::{
     mem,
 };
 
+use rbx_dom_weak::types::UniqueId;
 use rbx_reflection::{
     ClassDescriptor, PropertyDescriptor, PropertyKind, PropertySerialization, ReflectionDatabase,
 };
@@ -19,6 +20,23 @@ pub trait RbxReadExt: Read {
         Ok(u32::from_le_bytes(buffer))
     }
 
+    // Only SIGN chunks use these, and only the text format decodes them.
+    #[cfg(any(test, feature = "unstable_text_format"))]
+    fn read_le_i32(&mut self) -> io::Result<i32> {
+        let mut buffer = [0; 4];
+        self.read_exact(&mut buffer)?;
+
+        Ok(i32::from_le_bytes(buffer))
+    }
+
+    #[cfg(any(test, feature = "unstable_text_format"))]
+    fn read_le_i64(&mut self) -> io::Result<i64> {
+        let mut buffer = [0; 8];
+        self.read_exact(&mut buffer)?;
+
+        Ok(i64::from_le_bytes(buffer))
+    }
+
     fn read_le_u16(&mut self) -> io::Result<u16> {
         let mut bytes = [0; 2];
         self.read_exact(&mut bytes)?;
@@ -85,7 +103,7 @@ pub trait RbxReadExt: Read {
     }
 
     fn read_interleaved_i32_array(&mut self, output: &mut [i32]) -> io::Result<()> {
-        let mut buffer = vec![0; output.len() * mem::size_of::<i32>()];
+        let mut buffer = vec![0; mem::size_of_val(output)];
         self.read_exact(&mut buffer)?;
 
         for i in 0..output.len() {
@@ -101,7 +119,7 @@ pub trait RbxReadExt: Read {
     }
 
     fn read_interleaved_u32_array(&mut self, output: &mut [u32]) -> io::Result<()> {
-        let mut buffer = vec![0; output.len() * mem::size_of::<u32>()];
+        let mut buffer = vec![0; mem::size_of_val(output)];
         self.read_exact(&mut buffer)?;
 
         for i in 0..output.len() {
@@ -118,8 +136,24 @@ pub trait RbxReadExt: Read {
         Ok(())
     }
 
+    // No property type is stored as interleaved i16s. Vector3int16 is stored
+    // as plain little-endian values, so this is only built for tests.
+    #[cfg(test)]
+    fn read_interleaved_i16_array(&mut self, output: &mut [i16]) -> io::Result<()> {
+        let mut buffer = vec![0; mem::size_of_val(output)];
+        self.read_exact(&mut buffer)?;
+
+        for i in 0..output.len() {
+            let bytes = [buffer[i], buffer[i + output.len()]];
+
+            output[i] = untransform_i16(i16::from_be_bytes(bytes));
+        }
+
+        Ok(())
+    }
+
     fn read_interleaved_f32_array(&mut self, output: &mut [f32]) -> io::Result<()> {
-        let mut buf = vec![0; output.len() * mem::size_of::<f32>()];
+        let mut buf = vec![0; mem::size_of_val(output)];
         self.read_exact(&mut buf)?;
 
         for i in 0..output.len() {
@@ -135,19 +169,13 @@ pub trait RbxReadExt: Read {
 
     fn read_referent_array(&mut self, output: &mut [i32]) -> io::Result<()> {
         self.read_interleaved_i32_array(output)?;
-
-        let mut last = 0;
-
-        for referent in output.iter_mut() {
-            *referent += last;
-            last = *referent;
-        }
+        accumulate_referents(output);
 
         Ok(())
     }
 
     fn read_interleaved_i64_array(&mut self, output: &mut [i64]) -> io::Result<()> {
-        let mut buf = vec![0; output.len() * mem::size_of::<i64>()];
+        let mut buf = vec![0; mem::size_of_val(output)];
         self.read_exact(&mut buf)?;
 
         for i in 0..output.len() {
@@ -174,6 +202,22 @@ pub trait RbxReadExt: Read {
 
         Ok(())
     }
+
+    /// Reads values that are `N` bytes long each, stored with the first byte
+    /// of every value, then the second byte of every value, and so on.
+    fn read_interleaved_bytes<const N: usize>(&mut self, output: &mut [[u8; N]]) -> io::Result<()> {
+        let mut buffer = vec![0; N * output.len()];
+        self.read_exact(&mut buffer)?;
+
+        let len = output.len();
+        for (i, value) in output.iter_mut().enumerate() {
+            for (byte_index, byte) in value.iter_mut().enumerate() {
+                *byte = buffer[i + byte_index * len];
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl<R> RbxReadExt for R where R: Read {}
@@ -185,6 +229,20 @@ pub trait RbxWriteExt: Write {
         Ok(())
     }
 
+    #[cfg(any(test, feature = "unstable_text_format"))]
+    fn write_le_i32(&mut self, value: i32) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())?;
+
+        Ok(())
+    }
+
+    #[cfg(any(test, feature = "unstable_text_format"))]
+    fn write_le_i64(&mut self, value: i64) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())?;
+
+        Ok(())
+    }
+
     fn write_le_u16(&mut self, value: u16) -> io::Result<()> {
         self.write_all(&value.to_le_bytes())?;
 
@@ -257,6 +315,24 @@ pub trait RbxWriteExt: Write {
         Ok(())
     }
 
+    // See `read_interleaved_i16_array`.
+    #[cfg(test)]
+    fn write_interleaved_i16_array<I>(&mut self, values: I) -> io::Result<()>
+    where
+        I: Iterator<Item = i16>,
+    {
+        let values: Vec<_> = values.collect();
+
+        for shift in &[8, 0] {
+            for value in values.iter().copied() {
+                let encoded = transform_i16(value) >> shift;
+                self.write_u8(encoded as u8)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn write_interleaved_f32_array<I>(&mut self, values: I) -> io::Result<()>
     where
         I: Iterator<Item = f32>,
@@ -277,14 +353,10 @@ pub trait RbxWriteExt: Write {
     where
         I: Iterator<Item = i32>,
     {
-        let mut last_value = 0;
-        let delta_encoded = values.map(|value| {
-            let encoded = value - last_value;
-            last_value = value;
-            encoded
-        });
+        let mut values: Vec<_> = values.collect();
+        deaccumulate_referents(&mut values);
 
-        self.write_interleaved_i32_array(delta_encoded)
+        self.write_interleaved_i32_array(values.into_iter())
     }
 
     fn write_interleaved_i64_array<I>(&mut self, values: I) -> io::Result<()>
@@ -302,10 +374,44 @@ pub trait RbxWriteExt: Write {
 
         Ok(())
     }
+
+    /// The inverse of `read_interleaved_bytes`.
+    fn write_interleaved_bytes<const N: usize>(&mut self, values: &[[u8; N]]) -> io::Result<()> {
+        for byte_index in 0..N {
+            for value in values {
+                self.write_u8(value[byte_index])?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl<W> RbxWriteExt for W where W: Write {}
 
+/// Turns referents stored as differences from the previous referent, the way
+/// Roblox stores arrays of referents, back into the referents themselves.
+pub fn accumulate_referents(values: &mut [i32]) {
+    let mut last = 0i32;
+
+    for value in values {
+        *value = value.wrapping_add(last);
+        last = *value;
+    }
+}
+
+/// The inverse of `accumulate_referents`, which turns each referent into its
+/// difference from the previous one.
+pub fn deaccumulate_referents(values: &mut [i32]) {
+    let mut last = 0i32;
+
+    for value in values {
+        let current = *value;
+        *value = current.wrapping_sub(last);
+        last = current;
+    }
+}
+
 /// Applies the integer transformation generally used in property data in the
 /// Roblox binary format.
 pub fn transform_i32(value: i32) -> i32 {
@@ -317,6 +423,20 @@ pub fn untransform_i32(value: i32) -> i32 {
     ((value as u32) >> 1) as i32 ^ -(value & 1)
 }
 
+/// The integer transformation used in property data, for 16-bit integers.
+/// Only the interleaved i16 array helpers use it, so it's only built for
+/// tests.
+#[cfg(test)]
+pub fn transform_i16(value: i16) -> i16 {
+    (value << 1) ^ (value >> 15)
+}
+
+/// The inverse of `transform_i16`.
+#[cfg(test)]
+pub fn untransform_i16(value: i16) -> i16 {
+    ((value as u16) >> 1) as i16 ^ -(value & 1)
+}
+
 pub fn transform_i64(value: i64) -> i64 {
     (value << 1) ^ (value >> 63)
 }
@@ -325,6 +445,27 @@ pub fn untransform_i64(value: i64) -> i64 {
     ((value as u64) >> 1) as i64 ^ -(value & 1)
 }
 
+/// Converts a `UniqueId` into the 16 bytes that the binary format stores for
+/// it. This is the same layout as `UniqueId::to_bytes`, except that the
+/// random part is rotated left by one bit.
+pub fn transform_unique_id(value: UniqueId) -> [u8; 16] {
+    UniqueId {
+        random: value.random.rotate_left(1),
+        ..value
+    }
+    .to_bytes()
+}
+
+/// The inverse of `transform_unique_id`.
+pub fn untransform_unique_id(bytes: [u8; 16]) -> UniqueId {
+    let value = UniqueId::from_bytes(bytes);
+
+    UniqueId {
+        random: value.random.rotate_right(1),
+        ..value
+    }
+}
+
 pub struct PropertyDescriptors<'a> {
     pub canonical: &'a PropertyDescriptor<'a>,
     pub serialized: Option<&'a PropertyDescriptor<'a>>,
@@ -456,3 +597,183 @@ fn find_serialized_from_canonical<'a>(
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interleaved_u32() {
+        // 0x01020304 and 0xAABBCCDD, split into their big-endian bytes.
+        let bytes = [0x01, 0xAA, 0x02, 0xBB, 0x03, 0xCC, 0x04, 0xDD];
+        let mut output = [0; 2];
+        bytes
+            .as_ref()
+            .read_interleaved_u32_array(&mut output)
+            .unwrap();
+
+        assert_eq!(output, [0x01020304, 0xAABBCCDD]);
+
+        let mut written = Vec::new();
+        written.write_interleaved_u32_array(&output).unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn interleaved_i16() {
+        // 1, -1, 300, and -32768, transformed into 2, 1, 600, and 65535.
+        let bytes = [0x00, 0x00, 0x02, 0xFF, 0x02, 0x01, 0x58, 0xFF];
+        let mut output = [0; 4];
+        bytes
+            .as_ref()
+            .read_interleaved_i16_array(&mut output)
+            .unwrap();
+
+        assert_eq!(output, [1, -1, 300, i16::MIN]);
+
+        let mut written = Vec::new();
+        written
+            .write_interleaved_i16_array(output.iter().copied())
+            .unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn interleaved_round_trips() {
+        let i32s = [0, 1, -1, 1000, i32::MIN, i32::MAX];
+        let mut buffer = Vec::new();
+        buffer
+            .write_interleaved_i32_array(i32s.iter().copied())
+            .unwrap();
+        let mut output = [0; 6];
+        buffer
+            .as_slice()
+            .read_interleaved_i32_array(&mut output)
+            .unwrap();
+        assert_eq!(output, i32s);
+
+        let u32s = [0, 1, 0xDEADBEEF, u32::MAX];
+        let mut buffer = Vec::new();
+        buffer.write_interleaved_u32_array(&u32s).unwrap();
+        let mut output = [0; 4];
+        buffer
+            .as_slice()
+            .read_interleaved_u32_array(&mut output)
+            .unwrap();
+        assert_eq!(output, u32s);
+
+        let i16s = [0, 1, -1, 300, i16::MIN, i16::MAX];
+        let mut buffer = Vec::new();
+        buffer
+            .write_interleaved_i16_array(i16s.iter().copied())
+            .unwrap();
+        let mut output = [0; 6];
+        buffer
+            .as_slice()
+            .read_interleaved_i16_array(&mut output)
+            .unwrap();
+        assert_eq!(output, i16s);
+
+        let i64s = [0, 1, -1, i64::MIN, i64::MAX];
+        let mut buffer = Vec::new();
+        buffer
+            .write_interleaved_i64_array(i64s.iter().copied())
+            .unwrap();
+        let mut output = [0; 5];
+        buffer
+            .as_slice()
+            .read_interleaved_i64_array(&mut output)
+            .unwrap();
+        assert_eq!(output, i64s);
+
+        let f32s = [0.0, -0.0, 1.5, -2.25, f32::MAX, f32::INFINITY];
+        let mut buffer = Vec::new();
+        buffer
+            .write_interleaved_f32_array(f32s.iter().copied())
+            .unwrap();
+        let mut output = [0.0; 6];
+        buffer
+            .as_slice()
+            .read_interleaved_f32_array(&mut output)
+            .unwrap();
+        assert_eq!(
+            output.map(f32::to_bits),
+            f32s.map(f32::to_bits),
+            "floats should keep their exact bits"
+        );
+
+        let referents = [0, 1, 5, 2, -1, 100];
+        let mut buffer = Vec::new();
+        buffer
+            .write_referent_array(referents.iter().copied())
+            .unwrap();
+        let mut output = [0; 6];
+        buffer.as_slice().read_referent_array(&mut output).unwrap();
+        assert_eq!(output, referents);
+
+        let bytes = [[1, 2, 3], [4, 5, 6]];
+        let mut buffer = Vec::new();
+        buffer.write_interleaved_bytes(&bytes).unwrap();
+        assert_eq!(buffer, [1, 4, 2, 5, 3, 6]);
+        let mut output = [[0; 3]; 2];
+        buffer
+            .as_slice()
+            .read_interleaved_bytes(&mut output)
+            .unwrap();
+        assert_eq!(output, bytes);
+    }
+
+    #[test]
+    fn referent_accumulation() {
+        let mut values = [0, 1, 5, 2, -1];
+        deaccumulate_referents(&mut values);
+        assert_eq!(values, [0, 1, 4, -3, -3]);
+
+        accumulate_referents(&mut values);
+        assert_eq!(values, [0, 1, 5, 2, -1]);
+    }
+
+    /// Accumulating and deaccumulating should undo each other for any array,
+    /// including ones whose differences overflow.
+    #[test]
+    fn referent_accumulation_inverse() {
+        // A small xorshift generator, so that this test is reproducible.
+        let mut state = 0x2545F491u32;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as i32
+        };
+
+        for len in 0..200 {
+            let original: Vec<i32> = (0..len)
+                .map(|i| match i % 3 {
+                    0 => next(),
+                    1 => next() % 100,
+                    _ => [i32::MIN, i32::MAX, -1, 0][next().rem_euclid(4) as usize],
+                })
+                .collect();
+
+            let mut values = original.clone();
+            deaccumulate_referents(&mut values);
+            accumulate_referents(&mut values);
+            assert_eq!(values, original);
+
+            accumulate_referents(&mut values);
+            deaccumulate_referents(&mut values);
+            assert_eq!(values, original);
+        }
+    }
+
+    #[test]
+    fn interleaved_i16_too_short() {
+        let mut output = [0; 2];
+        let error = [0x00, 0x00, 0x02]
+            .as_ref()
+            .read_interleaved_i16_array(&mut output)
+            .unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -1,4 +1,4 @@
-use std::io;
+use std::{fmt, io};
 
 use thiserror::Error;
 
@@ -11,6 +11,17 @@ pub struct Error {
     source: Box<InnerError>,
 }
 
+impl Error {
+    /// The references to undeclared referents that made decoding fail. This
+    /// is empty for any other kind of error.
+    pub fn dangling_references(&self) -> &[DanglingReference] {
+        match &*self.source {
+            InnerError::DanglingReferences { references } => references,
+            _ => &[],
+        }
+    }
+}
+
 impl From<InnerError> for Error {
     fn from(inner: InnerError) -> Self {
         Self {
@@ -63,6 +74,12 @@ pub(crate) enum InnerError {
         actual_value: String,
     },
 
+    #[error("Invalid property name: A property of {type_name} had a name that was not valid UTF-8 ({prop_name:?})")]
+    InvalidPropName {
+        type_name: String,
+        prop_name: String,
+    },
+
     #[error("File referred to type ID {type_id}, which was not declared")]
     InvalidTypeId { type_id: u32 },
 
@@ -79,4 +96,72 @@ pub(crate) enum InnerError {
         expected_type_id: u8,
         actual_type_id: u8,
     },
+
+    #[error("{chunk_name} chunk declared {count} instances, but only has room for {max}")]
+    TooManyInstances {
+        chunk_name: &'static str,
+        count: u32,
+        max: usize,
+    },
+
+    #[error("File referred to {} referents that were not declared, starting with {}", references.len(), references[0])]
+    DanglingReferences { references: Vec<DanglingReference> },
+}
+
+/// A reference in a file to a referent that no `INST` chunk declares. See
+/// [`Error::dangling_references`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum DanglingReference {
+    /// A `PRNT` chunk gave a parent to an instance that wasn't declared.
+    Subject {
+        /// The undeclared referent.
+        subject: i32,
+    },
+
+    /// A `PRNT` chunk gave an instance a parent that wasn't declared.
+    Parent {
+        /// The referent of the instance.
+        subject: i32,
+
+        /// The undeclared referent of its parent.
+        parent: i32,
+    },
+
+    /// A `Ref` property pointed at an instance that wasn't declared. These are
+    /// only checked when
+    /// [`Deserializer::validate_referents`](crate::Deserializer::validate_referents)
+    /// is turned on.
+    Property {
+        /// The referent of the instance that has the property.
+        instance: i32,
+
+        /// The name of the property.
+        property: String,
+
+        /// The undeclared referent that the property pointed at.
+        value: i32,
+    },
+}
+
+impl fmt::Display for DanglingReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DanglingReference::Subject { subject } => {
+                write!(f, "PRNT subject {}", subject)
+            }
+            DanglingReference::Parent { subject, parent } => {
+                write!(f, "PRNT parent {} of instance {}", parent, subject)
+            }
+            DanglingReference::Property {
+                instance,
+                property,
+                value,
+            } => write!(
+                f,
+                "value {} of Ref property {} on instance {}",
+                value, property, instance
+            ),
+        }
+    }
 }
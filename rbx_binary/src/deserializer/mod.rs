@@ -1,17 +1,29 @@
 mod error;
 mod header;
+mod skeleton;
 mod state;
 
-use std::{io::Read, str};
+use std::{
+    io::{self, Read},
+    str,
+};
 
 use rbx_dom_weak::WeakDom;
 use rbx_reflection::ReflectionDatabase;
 
+use crate::gzip::{self, GzipReader};
+
 use self::state::DeserializerState;
 
+pub(crate) use self::error::InnerError;
+
+#[cfg(any(test, feature = "unstable_text_format"))]
 pub(crate) use self::header::FileHeader;
 
-pub use self::error::Error;
+pub use self::{
+    error::{DanglingReference, Error},
+    skeleton::{SkeletonInstance, SkeletonTree},
+};
 
 /// A configurable deserializer for Roblox binary models and places.
 ///
@@ -40,45 +52,119 @@ pub use self::error::Error;
 /// ```
 pub struct Deserializer<'a> {
     database: Option<&'a ReflectionDatabase<'a>>,
+    recover_chunk_lengths: bool,
+    validate_referents: bool,
+    gzip_size_limit: u64,
 }
 
+/// The default for [`Deserializer::gzip_size_limit`], 1 GiB.
+const DEFAULT_GZIP_SIZE_LIMIT: u64 = 1 << 30;
+
 impl<'a> Deserializer<'a> {
     /// Create a new `Deserializer` with the default settings.
     pub fn new() -> Self {
         Self {
             database: Some(rbx_reflection_database::get()),
+            recover_chunk_lengths: false,
+            validate_referents: false,
+            gzip_size_limit: DEFAULT_GZIP_SIZE_LIMIT,
         }
     }
 
+    /// Tries to recover from chunks whose declared length is longer than
+    /// their contents, which some tools write by mistake. When a chunk isn't
+    /// followed by the start of another chunk, the deserializer looks for the
+    /// start of the next chunk inside of it and cuts it short there, logging
+    /// a warning.
+    ///
+    /// This is a heuristic that could cut a chunk short in the wrong place, so
+    /// it's off by default.
+    pub fn recover_chunk_lengths(mut self, recover_chunk_lengths: bool) -> Self {
+        self.recover_chunk_lengths = recover_chunk_lengths;
+        self
+    }
+
+    /// Checks that every `Ref` property points at an instance declared in the
+    /// file, failing with a list of every [`DanglingReference`] if any don't.
+    /// Off by default, in which case those properties are read as `None`.
+    ///
+    /// Parent links in `PRNT` chunks are always checked, since an instance
+    /// with an undeclared parent can't be put into the tree.
+    pub fn validate_referents(mut self, validate_referents: bool) -> Self {
+        self.validate_referents = validate_referents;
+        self
+    }
+
+    /// The most bytes that a gzip compressed file is allowed to decompress to,
+    /// which is 1 GiB by default. Decompressing any more than this fails with
+    /// an error instead of using an unbounded amount of memory.
+    pub fn gzip_size_limit(mut self, gzip_size_limit: u64) -> Self {
+        self.gzip_size_limit = gzip_size_limit;
+        self
+    }
+
     /// Deserialize a Roblox binary model or place from the given stream using
     /// this deserializer.
     pub fn deserialize<R: Read>(&self, reader: R) -> Result<WeakDom, Error> {
         profiling::scope!("rbx_binary::deserialize");
 
-        let mut deserializer = DeserializerState::new(self, reader)?;
-
-        loop {
-            let chunk = deserializer.next_chunk()?;
-
-            match &chunk.name {
-                b"META" => deserializer.decode_meta_chunk(&chunk.data)?,
-                b"SSTR" => deserializer.decode_sstr_chunk(&chunk.data)?,
-                b"INST" => deserializer.decode_inst_chunk(&chunk.data)?,
-                b"PROP" => deserializer.decode_prop_chunk(&chunk.data)?,
-                b"PRNT" => deserializer.decode_prnt_chunk(&chunk.data)?,
-                b"END\0" => {
-                    deserializer.decode_end_chunk(&chunk.data)?;
-                    break;
-                }
-                _ => match str::from_utf8(&chunk.name) {
-                    Ok(name) => log::info!("Unknown binary chunk name {}", name),
-                    Err(_) => log::info!("Unknown binary chunk name {:?}", chunk.name),
-                },
-            }
-        }
+        let mut deserializer = DeserializerState::new(self, self.unwrap_gzip(reader)?)?;
+        decode_chunks(&mut deserializer)?;
 
         Ok(deserializer.finish())
     }
+
+    /// Deserialize a Roblox binary model or place from the given stream,
+    /// decoding property values on multiple threads when the `parallel`
+    /// feature is enabled.
+    ///
+    /// The instance hierarchy is read first, then every PROP chunk is decoded,
+    /// and then the results are put together in file order. The resulting DOM
+    /// is the same as the one returned by [`Deserializer::deserialize`].
+    /// Decoding properties is most of the work of reading a large place, so
+    /// with the `parallel` feature this can be much faster for big files.
+    /// Without it, PROP chunks are decoded one after another.
+    pub fn deserialize_parallel<R: Read>(&self, reader: R) -> Result<WeakDom, Error> {
+        profiling::scope!("rbx_binary::deserialize_parallel");
+
+        let mut deserializer = DeserializerState::new(self, self.unwrap_gzip(reader)?)?;
+        deserializer.defer_prop_chunks();
+        decode_chunks(&mut deserializer)?;
+
+        Ok(deserializer.finish())
+    }
+
+    /// Read only the instance hierarchy of a Roblox binary model or place from
+    /// the given stream, skipping all property values.
+    ///
+    /// This is much faster than [`Deserializer::deserialize`] and uses much
+    /// less memory, which makes it useful for tools that only care about the
+    /// shape of a file, like which classes it contains.
+    pub fn deserialize_skeleton<R: Read>(&self, reader: R) -> Result<SkeletonTree, Error> {
+        profiling::scope!("rbx_binary::deserialize_skeleton");
+
+        Ok(skeleton::decode_skeleton(self.unwrap_gzip(reader)?)?)
+    }
+}
+
+impl<'a> Deserializer<'a> {
+    /// Decompresses `reader` if it starts with gzip's magic number. Binary
+    /// files start with `<roblox!`, so they can never be mistaken for gzip
+    /// data.
+    fn unwrap_gzip<R: Read>(&self, mut reader: R) -> Result<MaybeGzip<R>, InnerError> {
+        let mut magic = [0; 2];
+        reader.read_exact(&mut magic)?;
+
+        let reader = io::Cursor::new(magic).chain(reader);
+        if magic == gzip::GZIP_MAGIC {
+            Ok(MaybeGzip::Gzip(Box::new(GzipReader::new(
+                reader,
+                self.gzip_size_limit,
+            ))))
+        } else {
+            Ok(MaybeGzip::Plain(reader))
+        }
+    }
 }
 
 impl<'a> Default for Deserializer<'a> {
@@ -86,3 +172,65 @@ impl<'a> Default for Deserializer<'a> {
         Self::new()
     }
 }
+
+/// Either a file's original contents, or its decompressed contents if it was
+/// compressed with gzip.
+enum MaybeGzip<R> {
+    Plain(io::Chain<io::Cursor<[u8; 2]>, R>),
+    Gzip(Box<GzipReader<io::Chain<io::Cursor<[u8; 2]>, R>>>),
+}
+
+impl<R: Read> Read for MaybeGzip<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            MaybeGzip::Plain(reader) => reader.read(buf),
+            MaybeGzip::Gzip(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Checks that `count` instances, each taking up `bytes_per_instance` bytes,
+/// fit in what's left of `chunk`. A corrupt count fails here instead of
+/// causing an enormous allocation.
+fn check_instance_count(
+    chunk_name: &'static str,
+    count: u32,
+    chunk: &[u8],
+    bytes_per_instance: usize,
+) -> Result<(), InnerError> {
+    let max = chunk.len() / bytes_per_instance;
+    if count as usize > max {
+        return Err(InnerError::TooManyInstances {
+            chunk_name,
+            count,
+            max,
+        });
+    }
+
+    Ok(())
+}
+
+/// Reads and decodes every chunk in a file, up to and including the END chunk.
+fn decode_chunks<R: Read>(deserializer: &mut DeserializerState<'_, R>) -> Result<(), InnerError> {
+    loop {
+        let chunk = deserializer.next_chunk()?;
+
+        match &chunk.name {
+            b"META" => deserializer.decode_meta_chunk(&chunk.data)?,
+            b"SSTR" => deserializer.decode_sstr_chunk(&chunk.data)?,
+            b"INST" => deserializer.decode_inst_chunk(&chunk.data)?,
+            b"PROP" => deserializer.decode_prop_chunk(chunk.data)?,
+            b"PRNT" => deserializer.decode_prnt_chunk(&chunk.data)?,
+            b"END\0" => {
+                deserializer.decode_end_chunk(&chunk.data)?;
+                break;
+            }
+            _ => match str::from_utf8(&chunk.name) {
+                Ok(name) => log::info!("Unknown binary chunk name {}", name),
+                Err(_) => log::info!("Unknown binary chunk name {:?}", chunk.name),
+            },
+        }
+    }
+
+    Ok(())
+}
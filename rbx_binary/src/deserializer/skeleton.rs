@@ -0,0 +1,157 @@
+use std::{collections::HashMap, io::Read, sync::Arc};
+
+use crate::{chunk::Chunk, core::RbxReadExt};
+
+use super::{check_instance_count, error::InnerError, header::FileHeader};
+
+/// The instance hierarchy of a binary model or place, without any property
+/// values. Created by [`Deserializer::deserialize_skeleton`].
+///
+/// Instances are identified by their index in the file, in the order their
+/// `INST` chunks declared them.
+///
+/// [`Deserializer::deserialize_skeleton`]: crate::Deserializer::deserialize_skeleton
+#[derive(Debug, Clone)]
+pub struct SkeletonTree {
+    instances: Vec<SkeletonInstance>,
+    root_instances: Vec<usize>,
+}
+
+impl SkeletonTree {
+    /// Returns the indices of the instances that have no parent, in the order
+    /// they appear in the file.
+    pub fn root_instances(&self) -> &[usize] {
+        &self.root_instances
+    }
+
+    /// Returns every instance in the tree.
+    pub fn instances(&self) -> &[SkeletonInstance] {
+        &self.instances
+    }
+
+    /// Returns the instance with the given index, if it exists.
+    pub fn get(&self, index: usize) -> Option<&SkeletonInstance> {
+        self.instances.get(index)
+    }
+}
+
+/// A single instance in a [`SkeletonTree`].
+#[derive(Debug, Clone)]
+pub struct SkeletonInstance {
+    class: Arc<str>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+impl SkeletonInstance {
+    /// Returns the class name of this instance.
+    pub fn class(&self) -> &str {
+        &self.class
+    }
+
+    /// Returns the index of this instance's parent, or `None` if it is at the
+    /// top level of the file.
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// Returns the indices of this instance's children, in order.
+    pub fn children(&self) -> &[usize] {
+        &self.children
+    }
+}
+
+/// Reads only the `INST` and `PRNT` chunks of a file. Every other chunk,
+/// including all `PROP` chunks, is skipped without being decompressed.
+pub(super) fn decode_skeleton<R: Read>(mut input: R) -> Result<SkeletonTree, InnerError> {
+    // The header's instance count isn't checked against anything, so room
+    // is only made for instances as each INST chunk's count is checked.
+    FileHeader::decode(&mut input)?;
+
+    let mut instances = Vec::new();
+    let mut index_by_ref = HashMap::new();
+    let mut root_instances = Vec::new();
+
+    loop {
+        let chunk = Chunk::decode_or_skip(&mut input, |name| {
+            name != b"INST" && name != b"PRNT" && name != b"END\0"
+        })?;
+        let mut data = chunk.data.as_slice();
+
+        match &chunk.name {
+            b"INST" => {
+                let _type_id = data.read_le_u32()?;
+                let type_name: Arc<str> = data.read_string()?.into();
+                let _object_format = data.read_u8()?;
+                let number_instances = data.read_le_u32()?;
+                check_instance_count("INST", number_instances, data, 4)?;
+
+                instances.reserve(number_instances as usize);
+                index_by_ref.reserve(number_instances as usize);
+
+                let mut referents = vec![0; number_instances as usize];
+                data.read_referent_array(&mut referents)?;
+
+                for referent in referents {
+                    index_by_ref.insert(referent, instances.len());
+                    instances.push(SkeletonInstance {
+                        class: Arc::clone(&type_name),
+                        parent: None,
+                        children: Vec::new(),
+                    });
+                }
+            }
+            b"PRNT" => {
+                let version = data.read_u8()?;
+
+                if version != 0 {
+                    return Err(InnerError::UnknownChunkVersion {
+                        chunk_name: "PRNT",
+                        version: version as u32,
+                    });
+                }
+
+                let number_objects = data.read_le_u32()?;
+                check_instance_count("PRNT", number_objects, data, 8)?;
+
+                let mut subjects = vec![0; number_objects as usize];
+                let mut parents = vec![0; number_objects as usize];
+
+                data.read_referent_array(&mut subjects)?;
+                data.read_referent_array(&mut parents)?;
+
+                for (subject_ref, parent_ref) in subjects.into_iter().zip(parents) {
+                    let subject = match index_by_ref.get(&subject_ref) {
+                        Some(&index) => index,
+                        None => {
+                            log::warn!("PRNT chunk referred to unknown referent {}", subject_ref);
+                            continue;
+                        }
+                    };
+
+                    if parent_ref == -1 {
+                        root_instances.push(subject);
+                        continue;
+                    }
+
+                    match index_by_ref.get(&parent_ref) {
+                        Some(&parent) => {
+                            instances[subject].parent = Some(parent);
+                            instances[parent].children.push(subject);
+                        }
+                        None => {
+                            log::warn!("PRNT chunk referred to unknown referent {}", parent_ref);
+                        }
+                    }
+                }
+            }
+            b"END\0" => break,
+            _ => {}
+        }
+    }
+
+    Ok(SkeletonTree {
+        instances,
+        root_instances,
+    })
+}
@@ -2,34 +2,41 @@ use std::{
     collections::{HashMap, HashSet, VecDeque},
     convert::TryInto,
     io::Read,
+    sync::Mutex,
 };
 
 use rbx_dom_weak::{
     types::{
         Attributes, Axes, BinaryString, BrickColor, CFrame, Color3, Color3uint8, ColorSequence,
         ColorSequenceKeypoint, Content, CustomPhysicalProperties, Enum, Faces, Matrix3,
-        NumberRange, NumberSequence, NumberSequenceKeypoint, PhysicalProperties, Ray, Rect, Ref,
-        SharedString, Tags, UDim, UDim2, Variant, VariantType, Vector2, Vector3, Vector3int16,
+        NumberRange, NumberSequence, NumberSequenceKeypoint, PhysicalProperties, ProtectedString,
+        Ray, Rect, Ref, SharedString, Tags, UDim, UDim2, Variant, VariantType, Vector2, Vector3,
+        Vector3int16,
     },
     InstanceBuilder, WeakDom,
 };
-use rbx_reflection::{DataType, PropertyKind, PropertySerialization};
+use rbx_reflection::{DataType, PropertyKind, PropertySerialization, ReflectionDatabase};
 
 use crate::{
     cframe,
-    chunk::Chunk,
-    core::{find_property_descriptors, RbxReadExt},
+    chunk::{Chunk, ChunkReader},
+    core::{find_property_descriptors, untransform_unique_id, RbxReadExt},
     types::Type,
 };
 
-use super::{error::InnerError, header::FileHeader, Deserializer};
+use super::{
+    check_instance_count,
+    error::{DanglingReference, InnerError},
+    header::FileHeader,
+    Deserializer,
+};
 
 pub(super) struct DeserializerState<'a, R> {
     /// The user-provided configuration that we should use.
     deserializer: &'a Deserializer<'a>,
 
     /// The input data encoded as a binary model.
-    input: R,
+    input: ChunkReader<R>,
 
     /// The tree that instances should be written into. Eventually returned to
     /// the user.
@@ -56,7 +63,78 @@ pub(super) struct DeserializerState<'a, R> {
     /// Contains a set of unknown type IDs that we've encountered so far while
     /// deserializing this file. We use this map in order to ensure we only
     /// print one warning per unknown type ID when deserializing a file.
-    unknown_type_ids: HashSet<u8>,
+    unknown_type_ids: Mutex<HashSet<u8>>,
+
+    /// PROP chunks that are waiting to be decoded in parallel once every other
+    /// chunk has been read. `None` if PROP chunks are decoded as soon as
+    /// they're read.
+    deferred_props: Option<Vec<DeferredProp>>,
+
+    /// References to referents that no INST chunk declared. Decoding fails
+    /// with these once the END chunk is reached.
+    dangling_references: Mutex<Vec<DanglingReference>>,
+}
+
+/// A PROP chunk whose decoding has been put off until the end of the file.
+struct DeferredProp {
+    data: Vec<u8>,
+
+    /// How many SharedStrings had been read when this chunk was. Chunks can
+    /// only refer to SharedStrings that come before them.
+    num_shared_strings: usize,
+}
+
+/// The values of one property, decoded from a PROP chunk but not yet added to
+/// any instances.
+enum PropColumn {
+    /// Values of the `Name` property, which is stored on instances directly.
+    Name(Vec<(i32, String)>),
+
+    Property {
+        name: String,
+        values: Vec<(i32, Variant)>,
+    },
+}
+
+type PropResult = Result<Option<PropColumn>, InnerError>;
+
+/// Decodes PROP chunks that were put off until the end of the file. With the
+/// `parallel` feature, chunks are decoded on rayon's thread pool, which
+/// balances the work between threads as chunks finish. Otherwise, they're
+/// decoded one after another.
+fn decode_deferred_props(decoder: PropDecoder<'_>, props: &[DeferredProp]) -> Vec<PropResult> {
+    let decode = |prop: &DeferredProp| {
+        decoder
+            .with_shared_strings(prop.num_shared_strings)
+            .decode(&prop.data)
+    };
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        props.par_iter().map(decode).collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        props.iter().map(decode).collect()
+    }
+}
+
+/// Everything needed to decode a PROP chunk. Decoding only reads from the
+/// deserializer's state, which lets chunks be decoded on multiple threads.
+#[derive(Clone, Copy)]
+struct PropDecoder<'s> {
+    database: &'s ReflectionDatabase<'s>,
+    type_infos: &'s HashMap<u32, TypeInfo>,
+    instances_by_ref: &'s HashMap<i32, Instance>,
+    shared_strings: &'s [SharedString],
+    unknown_type_ids: &'s Mutex<HashSet<u8>>,
+
+    /// Where to record `Ref` values that point at undeclared referents, or
+    /// `None` if they shouldn't be checked.
+    dangling_references: Option<&'s Mutex<Vec<DanglingReference>>>,
 }
 
 /// Represents a unique instance class. Binary models define all their instance
@@ -98,19 +176,27 @@ impl<'a, R: Read> DeserializerState<'a, R> {
 
         Ok(DeserializerState {
             deserializer,
-            input,
+            input: ChunkReader::new(input, deserializer.recover_chunk_lengths),
             tree,
             metadata: HashMap::new(),
             shared_strings: Vec::new(),
             type_infos,
             instances_by_ref,
             root_instance_refs: Vec::new(),
-            unknown_type_ids: HashSet::new(),
+            unknown_type_ids: Mutex::new(HashSet::new()),
+            deferred_props: None,
+            dangling_references: Mutex::new(Vec::new()),
         })
     }
 
+    /// Holds onto PROP chunks instead of decoding them right away, so that
+    /// they can be decoded in parallel when the END chunk is reached.
+    pub(super) fn defer_prop_chunks(&mut self) {
+        self.deferred_props = Some(Vec::new());
+    }
+
     pub(super) fn next_chunk(&mut self) -> Result<Chunk, InnerError> {
-        Ok(Chunk::decode(&mut self.input)?)
+        Ok(self.input.next_chunk()?)
     }
 
     #[profiling::function]
@@ -165,6 +251,9 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             number_instances,
         );
 
+        // Every referent takes up four bytes.
+        check_instance_count("INST", number_instances, chunk, 4)?;
+
         let mut referents = vec![0; number_instances as usize];
         chunk.read_referent_array(&mut referents)?;
 
@@ -192,16 +281,207 @@ impl<'a, R: Read> DeserializerState<'a, R> {
         Ok(())
     }
 
+    pub(super) fn decode_prop_chunk(&mut self, chunk: Vec<u8>) -> Result<(), InnerError> {
+        if let Some(deferred_props) = &mut self.deferred_props {
+            // We still check the type ID now, so that a chunk that refers to a
+            // type declared later in the file fails the same way it would if
+            // it was decoded right away.
+            let type_id = chunk.as_slice().read_le_u32()?;
+            if !self.type_infos.contains_key(&type_id) {
+                return Err(InnerError::InvalidTypeId { type_id });
+            }
+
+            deferred_props.push(DeferredProp {
+                data: chunk,
+                num_shared_strings: self.shared_strings.len(),
+            });
+
+            return Ok(());
+        }
+
+        let column = self.prop_decoder().decode(&chunk)?;
+        if let Some(column) = column {
+            self.add_prop_column(column);
+        }
+
+        Ok(())
+    }
+
+    /// Decodes every deferred PROP chunk on multiple threads. Values are added
+    /// to instances in the same order as the chunks appeared in the file, so
+    /// the result is the same as decoding each chunk as soon as it was read.
+    #[profiling::function]
+    fn decode_deferred_props(&mut self) -> Result<(), InnerError> {
+        let deferred_props = match self.deferred_props.take() {
+            Some(deferred_props) if !deferred_props.is_empty() => deferred_props,
+            _ => return Ok(()),
+        };
+
+        let results = decode_deferred_props(self.prop_decoder(), &deferred_props);
+
+        for result in results {
+            if let Some(column) = result? {
+                self.add_prop_column(column);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn prop_decoder(&self) -> PropDecoder<'_> {
+        PropDecoder {
+            database: self.deserializer.database.unwrap(),
+            type_infos: &self.type_infos,
+            instances_by_ref: &self.instances_by_ref,
+            shared_strings: &self.shared_strings,
+            unknown_type_ids: &self.unknown_type_ids,
+            dangling_references: if self.deserializer.validate_referents {
+                Some(&self.dangling_references)
+            } else {
+                None
+            },
+        }
+    }
+
+    fn add_prop_column(&mut self, column: PropColumn) {
+        match column {
+            PropColumn::Name(names) => {
+                for (referent, name) in names {
+                    let instance = self.instances_by_ref.get_mut(&referent).unwrap();
+                    instance.builder.set_name(name);
+                }
+            }
+            PropColumn::Property { name, values } => {
+                for (referent, value) in values {
+                    let instance = self.instances_by_ref.get_mut(&referent).unwrap();
+                    instance.builder.add_property(&name, value);
+                }
+            }
+        }
+    }
+
+    #[profiling::function]
+    pub(super) fn decode_prnt_chunk(&mut self, mut chunk: &[u8]) -> Result<(), InnerError> {
+        let version = chunk.read_u8()?;
+
+        if version != 0 {
+            return Err(InnerError::UnknownChunkVersion {
+                chunk_name: "PRNT",
+                version: version as u32,
+            });
+        }
+
+        let number_objects = chunk.read_le_u32()?;
+
+        log::trace!("PRNT chunk ({} instances)", number_objects);
+
+        // Every link is two referents, four bytes each.
+        check_instance_count("PRNT", number_objects, chunk, 8)?;
+
+        let mut subjects = vec![0; number_objects as usize];
+        let mut parents = vec![0; number_objects as usize];
+
+        chunk.read_referent_array(&mut subjects)?;
+        chunk.read_referent_array(&mut parents)?;
+
+        let dangling_references = self.dangling_references.get_mut().unwrap();
+
+        for (id, parent_ref) in subjects.iter().copied().zip(parents.iter().copied()) {
+            if !self.instances_by_ref.contains_key(&id) {
+                dangling_references.push(DanglingReference::Subject { subject: id });
+            } else if parent_ref == -1 {
+                self.root_instance_refs.push(id);
+            } else if let Some(instance) = self.instances_by_ref.get_mut(&parent_ref) {
+                instance.children.push(id);
+            } else {
+                dangling_references.push(DanglingReference::Parent {
+                    subject: id,
+                    parent: parent_ref,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     #[profiling::function]
-    pub(super) fn decode_prop_chunk(&mut self, mut chunk: &[u8]) -> Result<(), InnerError> {
+    pub(super) fn decode_end_chunk(&mut self, _chunk: &[u8]) -> Result<(), InnerError> {
+        log::trace!("END chunk");
+
+        // We don't do any validation on the END chunk. There's no useful
+        // information for us here as it just signals that the file hasn't been
+        // truncated.
+
+        self.decode_deferred_props()?;
+
+        let mut references = std::mem::take(self.dangling_references.get_mut().unwrap());
+        if !references.is_empty() {
+            // Deferred PROP chunks are decoded on many threads, so put the
+            // references in an order that doesn't depend on them.
+            references.sort();
+            return Err(InnerError::DanglingReferences { references });
+        }
+
+        Ok(())
+    }
+
+    /// Combines together all the decoded information to build and emplace
+    /// instances in our tree.
+    #[profiling::function]
+    pub(super) fn finish(mut self) -> WeakDom {
+        log::trace!("Constructing tree from deserialized data");
+
+        // Track all the instances we need to construct. Order of construction
+        // is important to preserve for both determinism and sometimes
+        // functionality of models we handle.
+        let mut instances_to_construct = VecDeque::new();
+
+        // Any instance with a parent of -1 will be at the top level of the
+        // tree. Because of the way rbx_dom_weak generally works, we need to
+        // start at the top of the tree to begin construction.
+        let root_ref = self.tree.root_ref();
+        for &referent in &self.root_instance_refs {
+            instances_to_construct.push_back((referent, root_ref));
+        }
+
+        while let Some((referent, parent_ref)) = instances_to_construct.pop_front() {
+            let instance = self.instances_by_ref.remove(&referent).unwrap();
+            let id = self.tree.insert(parent_ref, instance.builder);
+
+            for referent in instance.children {
+                instances_to_construct.push_back((referent, id));
+            }
+        }
+
+        self.tree
+    }
+}
+
+impl PropDecoder<'_> {
+    /// Limits this decoder to the first `count` SharedStrings in the file.
+    fn with_shared_strings(mut self, count: usize) -> Self {
+        self.shared_strings = &self.shared_strings[..count];
+        self
+    }
+
+    /// Decodes the values in a PROP chunk without adding them to any
+    /// instances. Returns `None` for chunks that should be skipped.
+    #[profiling::function]
+    fn decode(&self, mut chunk: &[u8]) -> Result<Option<PropColumn>, InnerError> {
         let type_id = chunk.read_le_u32()?;
-        let prop_name = chunk.read_string()?;
+        let prop_name = chunk.read_binary_string()?;
 
         let type_info = self
             .type_infos
             .get(&type_id)
             .ok_or(InnerError::InvalidTypeId { type_id })?;
 
+        let prop_name =
+            String::from_utf8(prop_name).map_err(|err| InnerError::InvalidPropName {
+                type_name: type_info.type_name.clone(),
+                prop_name: String::from_utf8_lossy(err.as_bytes()).into_owned(),
+            })?;
+
         // PROP chunks that contain no type byte are ignored by Roblox. This can
         // happen when a new type is introduced.
         //
@@ -211,13 +491,18 @@ impl<'a, R: Read> DeserializerState<'a, R> {
         // that end immediately after the prop name, so we do the same.
         let binary_type_byte = match chunk.read_u8() {
             Ok(byte) => byte,
-            Err(_) => return Ok(()),
+            Err(_) => return Ok(None),
         };
 
         let binary_type: Type = match binary_type_byte.try_into() {
             Ok(ty) => ty,
             Err(_) => {
-                if self.unknown_type_ids.insert(binary_type_byte) {
+                if self
+                    .unknown_type_ids
+                    .lock()
+                    .unwrap()
+                    .insert(binary_type_byte)
+                {
                     log::warn!(
                         "Unknown value type ID {byte:#04x} ({byte}) in Roblox \
                          binary model file. Found in property {class}.{prop}.",
@@ -227,7 +512,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     );
                 }
 
-                return Ok(());
+                return Ok(None);
             }
         };
 
@@ -246,23 +531,19 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             // path, we should use the reflection database to figure out its
             // default name. This should be rare: effectively never!
 
-            for referent in &type_info.referents {
-                let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                let value = chunk.read_string()?;
-                instance.builder.set_name(value);
+            let mut names = Vec::with_capacity(type_info.referents.len());
+
+            for &referent in &type_info.referents {
+                names.push((referent, chunk.read_string()?));
             }
 
-            return Ok(());
+            return Ok(Some(PropColumn::Name(names)));
         }
 
         let canonical_name;
         let canonical_type;
 
-        match find_property_descriptors(
-            self.deserializer.database.unwrap(),
-            &type_info.type_name,
-            &prop_name,
-        ) {
+        match find_property_descriptors(self.database, &type_info.type_name, &prop_name) {
             Some(descriptors) => {
                 // If this descriptor is known but wasn't supposed to be
                 // serialized, we should skip it.
@@ -285,7 +566,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                             "Skipping property {} as it is canonical and should not serialize.",
                             descriptors.canonical.name
                         );
-                        return Ok(());
+                        return Ok(None);
                     }
                 }
 
@@ -297,7 +578,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     DataType::Enum(_) => VariantType::Enum,
                     _ => {
                         // TODO: Configurable handling of unknown types?
-                        return Ok(());
+                        return Ok(None);
                     }
                 };
 
@@ -315,7 +596,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     None => {
                         log::warn!("Unsupported prop type {:?}, skipping property", binary_type);
 
-                        return Ok(());
+                        return Ok(None);
                     }
                 }
 
@@ -323,32 +604,36 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             }
         }
 
+        let mut column = Vec::with_capacity(type_info.referents.len());
+
         match binary_type {
             Type::String => match canonical_type {
                 VariantType::String => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let value = chunk.read_string()?;
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 VariantType::Content => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let value: Content = chunk.read_string()?.into();
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
+                    }
+                }
+                VariantType::ProtectedString => {
+                    for referent in &type_info.referents {
+                        let value: ProtectedString = chunk.read_string()?.into();
+                        column.push((*referent, value.into()));
                     }
                 }
                 VariantType::BinaryString => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let value: BinaryString = chunk.read_binary_string()?.into();
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 VariantType::Tags => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let buffer = chunk.read_binary_string()?;
 
                         let value = Tags::decode(buffer.as_ref()).map_err(|_| {
@@ -360,17 +645,16 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                             }
                         })?;
 
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 VariantType::Attributes => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let buffer = chunk.read_binary_string()?;
 
                         match Attributes::from_reader(buffer.as_slice()) {
                             Ok(value) => {
-                                instance.builder.add_property(&canonical_name, value);
+                                column.push((*referent, value.into()));
                             }
                             Err(err) => {
                                 log::warn!(
@@ -379,9 +663,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                                     err
                                 );
 
-                                instance
-                                    .builder
-                                    .add_property(&canonical_name, BinaryString::from(buffer));
+                                column.push((*referent, BinaryString::from(buffer).into()));
                             }
                         }
                     }
@@ -390,7 +672,8 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     return Err(InnerError::PropTypeMismatch {
                         type_name: type_info.type_name.clone(),
                         prop_name,
-                        valid_type_names: "String, Content, Tags, Attributes, or BinaryString",
+                        valid_type_names:
+                            "String, Content, ProtectedString, Tags, Attributes, or BinaryString",
                         actual_type_name: format!("{:?}", invalid_type),
                     });
                 }
@@ -398,9 +681,8 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             Type::Bool => match canonical_type {
                 VariantType::Bool => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let value = chunk.read_bool()?;
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 invalid_type => {
@@ -418,8 +700,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     chunk.read_interleaved_i32_array(&mut values)?;
 
                     for (value, referent) in values.into_iter().zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 invalid_type => {
@@ -437,8 +718,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     chunk.read_interleaved_f32_array(&mut values)?;
 
                     for (value, referent) in values.into_iter().zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 invalid_type => {
@@ -453,9 +733,8 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             Type::Float64 => match canonical_type {
                 VariantType::Float64 => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let value = chunk.read_le_f64()?;
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 invalid_type => {
@@ -481,8 +760,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                         .map(|(scale, offset)| UDim::new(scale, offset));
 
                     for (value, referent) in values.zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 invalid_type => {
@@ -520,8 +798,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     let values = x.zip(y).map(|(x, y)| UDim2::new(x, y));
 
                     for (value, referent) in values.zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 invalid_type => {
@@ -543,15 +820,14 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                         let direction_y = chunk.read_le_f32()?;
                         let direction_z = chunk.read_le_f32()?;
 
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-
-                        instance.builder.add_property(
-                            &canonical_name,
+                        column.push((
+                            *referent,
                             Ray::new(
                                 Vector3::new(origin_x, origin_y, origin_z),
                                 Vector3::new(direction_x, direction_y, direction_z),
-                            ),
-                        );
+                            )
+                            .into(),
+                        ));
                     }
                 }
                 invalid_type => {
@@ -566,7 +842,6 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             Type::Faces => match canonical_type {
                 VariantType::Faces => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let value = chunk.read_u8()?;
                         let faces =
                             Faces::from_bits(value).ok_or_else(|| InnerError::InvalidPropData {
@@ -576,7 +851,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                                 actual_value: value.to_string(),
                             })?;
 
-                        instance.builder.add_property(&canonical_name, faces);
+                        column.push((*referent, faces.into()));
                     }
                 }
                 invalid_type => {
@@ -591,7 +866,6 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             Type::Axes => match canonical_type {
                 VariantType::Axes => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let value = chunk.read_u8()?;
 
                         let axes =
@@ -602,7 +876,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                                 actual_value: value.to_string(),
                             })?;
 
-                        instance.builder.add_property(&canonical_name, axes);
+                        column.push((*referent, axes.into()));
                     }
                 }
                 invalid_type => {
@@ -620,7 +894,6 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     chunk.read_interleaved_u32_array(&mut values)?;
 
                     for (value, referent) in values.into_iter().zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let color = value
                             .try_into()
                             .ok()
@@ -632,7 +905,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                                 actual_value: value.to_string(),
                             })?;
 
-                        instance.builder.add_property(&canonical_name, color);
+                        column.push((*referent, color.into()));
                     }
                 }
                 invalid_type => {
@@ -661,8 +934,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                         .map(|((r, g), b)| Color3::new(r, g, b));
 
                     for (color, referent) in colors.zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, color);
+                        column.push((*referent, color.into()));
                     }
                 }
                 invalid_type => {
@@ -685,8 +957,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     let values = x.into_iter().zip(y).map(|(x, y)| Vector2::new(x, y));
 
                     for (value, referent) in values.zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 invalid_type => {
@@ -715,8 +986,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                         .map(|((x, y), z)| Vector3::new(x, y, z));
 
                     for (value, referent) in values.zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 invalid_type => {
@@ -781,8 +1051,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                         .map(|(position, rotation)| CFrame::new(position, rotation));
 
                     for (cframe, referent) in values.zip(referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, cframe);
+                        column.push((*referent, cframe.into()));
                     }
                 }
                 invalid_type => {
@@ -800,10 +1069,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     chunk.read_interleaved_u32_array(&mut values)?;
 
                     for (value, referent) in values.into_iter().zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance
-                            .builder
-                            .add_property(&canonical_name, Enum::from_u32(value));
+                        column.push((*referent, Enum::from_u32(value).into()));
                     }
                 }
                 invalid_type => {
@@ -824,11 +1090,22 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                         let rbx_value = if let Some(instance) = self.instances_by_ref.get(&value) {
                             instance.builder.referent()
                         } else {
+                            if let (Some(dangling_references), true) =
+                                (self.dangling_references, value != -1)
+                            {
+                                dangling_references.lock().unwrap().push(
+                                    DanglingReference::Property {
+                                        instance: *referent,
+                                        property: prop_name.clone(),
+                                        value,
+                                    },
+                                );
+                            }
+
                             Ref::none()
                         };
 
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, rbx_value);
+                        column.push((*referent, rbx_value.into()));
                     }
                 }
                 invalid_type => {
@@ -843,15 +1120,15 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             Type::Vector3int16 => match canonical_type {
                 VariantType::Vector3int16 => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(
-                            &canonical_name,
+                        column.push((
+                            *referent,
                             Vector3int16::new(
                                 chunk.read_le_i16()?,
                                 chunk.read_le_i16()?,
                                 chunk.read_le_i16()?,
-                            ),
-                        )
+                            )
+                            .into(),
+                        ));
                     }
                 }
                 invalid_type => {
@@ -866,7 +1143,6 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             Type::NumberSequence => match canonical_type {
                 VariantType::NumberSequence => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let keypoint_count = chunk.read_le_u32()?;
                         let mut keypoints = Vec::with_capacity(keypoint_count as usize);
 
@@ -878,9 +1154,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                             ))
                         }
 
-                        instance
-                            .builder
-                            .add_property(&canonical_name, NumberSequence { keypoints })
+                        column.push((*referent, NumberSequence { keypoints }.into()));
                     }
                 }
                 invalid_type => {
@@ -895,7 +1169,6 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             Type::ColorSequence => match canonical_type {
                 VariantType::ColorSequence => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let keypoint_count = chunk.read_le_u32()? as usize;
                         let mut keypoints = Vec::with_capacity(keypoint_count);
 
@@ -913,9 +1186,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                             chunk.read_le_f32()?;
                         }
 
-                        instance
-                            .builder
-                            .add_property(&canonical_name, ColorSequence { keypoints })
+                        column.push((*referent, ColorSequence { keypoints }.into()));
                     }
                 }
                 invalid_type => {
@@ -930,11 +1201,10 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             Type::NumberRange => match canonical_type {
                 VariantType::NumberRange => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(
-                            &canonical_name,
-                            NumberRange::new(chunk.read_le_f32()?, chunk.read_le_f32()?),
-                        )
+                        column.push((
+                            *referent,
+                            NumberRange::new(chunk.read_le_f32()?, chunk.read_le_f32()?).into(),
+                        ));
                     }
                 }
                 invalid_type => {
@@ -966,8 +1236,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     );
 
                     for (value, referent) in values.zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, value)
+                        column.push((*referent, value.into()));
                     }
                 }
                 invalid_type => {
@@ -982,7 +1251,6 @@ impl<'a, R: Read> DeserializerState<'a, R> {
             Type::PhysicalProperties => match canonical_type {
                 VariantType::PhysicalProperties => {
                     for referent in &type_info.referents {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
                         let value = if chunk.read_u8()? == 1 {
                             Variant::PhysicalProperties(PhysicalProperties::Custom(
                                 CustomPhysicalProperties {
@@ -997,7 +1265,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                             Variant::PhysicalProperties(PhysicalProperties::Default)
                         };
 
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value));
                     }
                 }
                 invalid_type => {
@@ -1027,8 +1295,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                         .map(|((r, g), b)| Color3uint8::new(r, g, b));
 
                     for (color, referent) in colors.into_iter().zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, color);
+                        column.push((*referent, color.into()));
                     }
                 }
                 invalid_type => {
@@ -1046,8 +1313,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     chunk.read_interleaved_i64_array(&mut values)?;
 
                     for (value, referent) in values.into_iter().zip(&type_info.referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, value);
+                        column.push((*referent, value.into()));
                     }
                 }
                 invalid_type => {
@@ -1075,11 +1341,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                                 }
                             })?;
 
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-
-                        instance
-                            .builder
-                            .add_property(&canonical_name, shared_string.clone());
+                        column.push((*referent, shared_string.clone().into()));
                     }
                 }
                 invalid_type => {
@@ -1174,8 +1436,7 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                         });
 
                     for (cframe, referent) in values.zip(referents) {
-                        let instance = self.instances_by_ref.get_mut(referent).unwrap();
-                        instance.builder.add_property(&canonical_name, cframe);
+                        column.push((*referent, cframe.into()));
                     }
                 }
                 invalid_type => {
@@ -1187,83 +1448,29 @@ impl<'a, R: Read> DeserializerState<'a, R> {
                     });
                 }
             },
-        }
+            Type::UniqueId => match canonical_type {
+                VariantType::UniqueId => {
+                    let mut values = vec![[0; 16]; type_info.referents.len()];
+                    chunk.read_interleaved_bytes(&mut values)?;
 
-        Ok(())
-    }
-
-    #[profiling::function]
-    pub(super) fn decode_prnt_chunk(&mut self, mut chunk: &[u8]) -> Result<(), InnerError> {
-        let version = chunk.read_u8()?;
-
-        if version != 0 {
-            return Err(InnerError::UnknownChunkVersion {
-                chunk_name: "PRNT",
-                version: version as u32,
-            });
-        }
-
-        let number_objects = chunk.read_le_u32()?;
-
-        log::trace!("PRNT chunk ({} instances)", number_objects);
-
-        let mut subjects = vec![0; number_objects as usize];
-        let mut parents = vec![0; number_objects as usize];
-
-        chunk.read_referent_array(&mut subjects)?;
-        chunk.read_referent_array(&mut parents)?;
-
-        for (id, parent_ref) in subjects.iter().copied().zip(parents.iter().copied()) {
-            if parent_ref == -1 {
-                self.root_instance_refs.push(id);
-            } else {
-                let instance = self.instances_by_ref.get_mut(&parent_ref).unwrap();
-                instance.children.push(id);
-            }
-        }
-
-        Ok(())
-    }
-
-    #[profiling::function]
-    pub(super) fn decode_end_chunk(&mut self, _chunk: &[u8]) -> Result<(), InnerError> {
-        log::trace!("END chunk");
-
-        // We don't do any validation on the END chunk. There's no useful
-        // information for us here as it just signals that the file hasn't been
-        // truncated.
-
-        Ok(())
-    }
-
-    /// Combines together all the decoded information to build and emplace
-    /// instances in our tree.
-    #[profiling::function]
-    pub(super) fn finish(mut self) -> WeakDom {
-        log::trace!("Constructing tree from deserialized data");
-
-        // Track all the instances we need to construct. Order of construction
-        // is important to preserve for both determinism and sometimes
-        // functionality of models we handle.
-        let mut instances_to_construct = VecDeque::new();
-
-        // Any instance with a parent of -1 will be at the top level of the
-        // tree. Because of the way rbx_dom_weak generally works, we need to
-        // start at the top of the tree to begin construction.
-        let root_ref = self.tree.root_ref();
-        for &referent in &self.root_instance_refs {
-            instances_to_construct.push_back((referent, root_ref));
-        }
-
-        while let Some((referent, parent_ref)) = instances_to_construct.pop_front() {
-            let instance = self.instances_by_ref.remove(&referent).unwrap();
-            let id = self.tree.insert(parent_ref, instance.builder);
-
-            for referent in instance.children {
-                instances_to_construct.push_back((referent, id));
-            }
+                    for (value, referent) in values.into_iter().zip(&type_info.referents) {
+                        column.push((*referent, untransform_unique_id(value).into()));
+                    }
+                }
+                invalid_type => {
+                    return Err(InnerError::PropTypeMismatch {
+                        type_name: type_info.type_name.clone(),
+                        prop_name,
+                        valid_type_names: "UniqueId",
+                        actual_type_name: format!("{:?}", invalid_type),
+                    });
+                }
+            },
         }
 
-        self.tree
+        Ok(Some(PropColumn::Property {
+            name: canonical_name,
+            values: column,
+        }))
     }
 }
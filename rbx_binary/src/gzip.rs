@@ -0,0 +1,50 @@
+//! Support for reading files that have been gzip compressed on top of the
+//! binary format. Only decompression is supported.
+
+use std::io::{self, Read, Take};
+
+use flate2::read::MultiGzDecoder;
+
+pub(crate) const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decompresses a gzip stream as it's read. Streams made of several gzip
+/// members are decompressed one after another.
+///
+/// Reading more than `limit` decompressed bytes fails, so that a small file
+/// can't expand into an unbounded amount of data.
+pub(crate) struct GzipReader<R> {
+    inner: Take<MultiGzDecoder<R>>,
+    limit: u64,
+}
+
+impl<R: Read> GzipReader<R> {
+    pub fn new(reader: R, limit: u64) -> Self {
+        Self {
+            inner: MultiGzDecoder::new(reader).take(limit),
+            limit,
+        }
+    }
+}
+
+impl<R: Read> Read for GzipReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+
+        // Running into the limit looks like the end of the stream, so check
+        // whether there was actually more data left.
+        if read == 0 && !buf.is_empty() && self.inner.limit() == 0 {
+            let mut next = [0];
+            if self.inner.get_mut().read(&mut next)? != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "gzip compressed file is larger than the limit of {} bytes",
+                        self.limit
+                    ),
+                ));
+            }
+        }
+
+        Ok(read)
+    }
+}
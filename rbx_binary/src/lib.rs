@@ -53,10 +53,13 @@ rbx_binary::to_writer(output, &dom, &[dom.root_ref()])?;
 
 #![deny(missing_docs)]
 
+mod builder;
 mod cframe;
 mod chunk;
 mod core;
 mod deserializer;
+mod gzip;
+mod metadata;
 mod serializer;
 mod types;
 
@@ -73,15 +76,23 @@ use rbx_dom_weak::{types::Ref, WeakDom};
 /// An unstable textual format that can be used to debug binary models.
 #[cfg(feature = "unstable_text_format")]
 pub mod text_format {
+    pub use crate::chunk::StoredCompression;
     pub use crate::text_deserializer::*;
 }
 
 pub use crate::{
-    deserializer::{Deserializer, Error as DecodeError},
-    serializer::{Error as EncodeError, Serializer},
+    builder::ModelBuilder,
+    cframe::{matrix_to_orientation_id, orientation_id_to_matrix},
+    chunk::ChunkCompression,
+    deserializer::{
+        DanglingReference, Deserializer, Error as DecodeError, SkeletonInstance, SkeletonTree,
+    },
+    metadata::Metadata,
+    serializer::{CompressionTemplate, Error as EncodeError, Serializer},
 };
 
-/// Deserialize a Roblox binary model or place from a stream.
+/// Deserialize a Roblox binary model or place from a stream. Files that have
+/// been compressed with gzip are decompressed automatically.
 pub fn from_reader<R: Read>(reader: R) -> Result<WeakDom, DecodeError> {
     Deserializer::new().deserialize(reader)
 }
@@ -0,0 +1,135 @@
+use std::iter::FromIterator;
+
+#[cfg(any(test, feature = "unstable_text_format"))]
+use serde::Serialize;
+
+/// The key that tells Studio whether joints in a file were made explicitly.
+const EXPLICIT_AUTO_JOINTS: &str = "ExplicitAutoJoints";
+
+/// The key/value entries stored in a file's `META` chunk.
+///
+/// Entries keep the order they were read or inserted in, and entries with
+/// keys that rbx_binary doesn't know about are kept as they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    any(test, feature = "unstable_text_format"),
+    derive(Serialize),
+    serde(transparent)
+)]
+pub struct Metadata {
+    entries: Vec<(String, String)>,
+}
+
+impl Metadata {
+    /// Creates an empty `Metadata`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value of the entry with the given key, if there is one.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the value of the entry with the given key, returning the old
+    /// value. New keys are added after every existing entry.
+    pub fn insert<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) -> Option<String> {
+        let key = key.into();
+        let value = value.into();
+
+        match self
+            .entries
+            .iter_mut()
+            .find(|(entry_key, _)| *entry_key == key)
+        {
+            Some((_, existing)) => Some(std::mem::replace(existing, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes the entry with the given key, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self
+            .entries
+            .iter()
+            .position(|(entry_key, _)| entry_key == key)?;
+
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Iterates over every entry, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Tells whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The value of the `ExplicitAutoJoints` entry, which Studio writes to
+    /// files to say that their joints were made explicitly. Studio treats
+    /// welds in files without it differently when importing them.
+    ///
+    /// Returns `None` if there is no entry or its value isn't `true` or
+    /// `false`.
+    pub fn explicit_auto_joints(&self) -> Option<bool> {
+        match self.get(EXPLICIT_AUTO_JOINTS)? {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Sets the value of the `ExplicitAutoJoints` entry.
+    pub fn set_explicit_auto_joints(&mut self, value: bool) {
+        self.insert(EXPLICIT_AUTO_JOINTS, value.to_string());
+    }
+}
+
+/// Collects entries as they are, keeping their order and any repeated keys.
+impl FromIterator<(String, String)> for Metadata {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn explicit_auto_joints() {
+        let mut metadata = Metadata::new();
+        assert_eq!(metadata.explicit_auto_joints(), None);
+
+        metadata.insert("Unknown", "kept");
+        metadata.set_explicit_auto_joints(true);
+        assert_eq!(metadata.explicit_auto_joints(), Some(true));
+
+        metadata.set_explicit_auto_joints(false);
+        assert_eq!(metadata.explicit_auto_joints(), Some(false));
+        assert_eq!(
+            metadata.iter().collect::<Vec<_>>(),
+            [("Unknown", "kept"), ("ExplicitAutoJoints", "false")]
+        );
+
+        metadata.insert("ExplicitAutoJoints", "maybe");
+        assert_eq!(metadata.explicit_auto_joints(), None);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use crate::chunk::ChunkCompression;
+
+/// Identifies a chunk written by the serializer, independent of where in the
+/// file it ends up.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ChunkKey {
+    Metadata,
+    SharedStrings,
+    Instances(String),
+    Property(String, String),
+    Parents,
+}
+
+/// Chooses whether each chunk the [`Serializer`][crate::Serializer] writes
+/// is compressed. Chunks that the template doesn't mention are compressed.
+///
+/// This is most useful for writing a file back out with the same chunks
+/// compressed as when it was read.
+#[derive(Debug, Clone, Default)]
+pub struct CompressionTemplate {
+    chunks: HashMap<ChunkKey, ChunkCompression>,
+}
+
+impl CompressionTemplate {
+    /// Creates an empty `CompressionTemplate`, which compresses every chunk.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the compression of the `META` chunk.
+    pub fn set_metadata(&mut self, compression: ChunkCompression) {
+        self.chunks.insert(ChunkKey::Metadata, compression);
+    }
+
+    /// Sets the compression of the `SSTR` chunk.
+    pub fn set_shared_strings(&mut self, compression: ChunkCompression) {
+        self.chunks.insert(ChunkKey::SharedStrings, compression);
+    }
+
+    /// Sets the compression of the `INST` chunk for the given class.
+    pub fn set_instances<C: Into<String>>(&mut self, class: C, compression: ChunkCompression) {
+        self.chunks
+            .insert(ChunkKey::Instances(class.into()), compression);
+    }
+
+    /// Sets the compression of the `PROP` chunk for the given class and
+    /// property. The property name is the one stored in the file, which isn't
+    /// always the name used in the DOM.
+    pub fn set_property<C, P>(&mut self, class: C, property: P, compression: ChunkCompression)
+    where
+        C: Into<String>,
+        P: Into<String>,
+    {
+        self.chunks.insert(
+            ChunkKey::Property(class.into(), property.into()),
+            compression,
+        );
+    }
+
+    /// Sets the compression of the `PRNT` chunk.
+    pub fn set_parents(&mut self, compression: ChunkCompression) {
+        self.chunks.insert(ChunkKey::Parents, compression);
+    }
+
+    pub(crate) fn metadata(&self) -> ChunkCompression {
+        self.get(&ChunkKey::Metadata)
+    }
+
+    pub(crate) fn shared_strings(&self) -> ChunkCompression {
+        self.get(&ChunkKey::SharedStrings)
+    }
+
+    pub(crate) fn instances(&self, class: &str) -> ChunkCompression {
+        self.get(&ChunkKey::Instances(class.to_owned()))
+    }
+
+    pub(crate) fn property(&self, class: &str, property: &str) -> ChunkCompression {
+        self.get(&ChunkKey::Property(class.to_owned(), property.to_owned()))
+    }
+
+    pub(crate) fn parents(&self) -> ChunkCompression {
+        self.get(&ChunkKey::Parents)
+    }
+
+    fn get(&self, key: &ChunkKey) -> ChunkCompression {
+        self.chunks
+            .get(key)
+            .copied()
+            .unwrap_or(ChunkCompression::Compressed)
+    }
+}
@@ -10,6 +10,13 @@ pub struct Error {
     source: Box<InnerError>,
 }
 
+impl Error {
+    /// Tells whether this error came from a writer that ran out of room.
+    pub(super) fn is_write_zero(&self) -> bool {
+        matches!(&*self.source, InnerError::Io { source } if source.kind() == io::ErrorKind::WriteZero)
+    }
+}
+
 impl From<InnerError> for Error {
     fn from(inner: InnerError) -> Self {
         Self {
@@ -59,4 +66,14 @@ pub(super) enum InnerError {
 
     #[error("The instance with referent {referent:?} was not present in the dom.")]
     InvalidInstanceId { referent: Ref },
+
+    #[error(
+        "Too many instances: {count} instances were given, but the binary format \
+        can only refer to {max} instances.",
+        max = i32::MAX as u64 + 1
+    )]
+    TooManyInstances { count: usize },
+
+    #[error("The output buffer is too small: the model does not fit in {capacity} bytes.")]
+    BufferTooSmall { capacity: usize },
 }
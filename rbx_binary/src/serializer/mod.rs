@@ -1,13 +1,17 @@
+mod compression;
 mod error;
 mod state;
 
 use std::io::Write;
 
 use rbx_dom_weak::{types::Ref, WeakDom};
+use rbx_reflection::ReflectionDatabase;
 
-use self::state::SerializerState;
+use crate::metadata::Metadata;
 
-pub use self::error::Error;
+use self::{error::InnerError, state::SerializerState};
+
+pub use self::{compression::CompressionTemplate, error::Error};
 
 /// A configurable serializer for Roblox binary models and places.
 ///
@@ -28,15 +32,53 @@ pub use self::error::Error;
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 // future settings:
-// * reflection_database: Option<ReflectionDatabase> = default
 // * recursive: bool = true
 #[non_exhaustive]
-pub struct Serializer {}
+pub struct Serializer<'db> {
+    compression: CompressionTemplate,
+    omit_defaults: Option<&'db ReflectionDatabase<'db>>,
+    metadata: Metadata,
+}
 
-impl Serializer {
+impl<'db> Serializer<'db> {
     /// Create a new `Serializer` with the default settings.
     pub fn new() -> Self {
-        Serializer {}
+        Serializer {
+            compression: CompressionTemplate::new(),
+            omit_defaults: None,
+            metadata: Metadata::new(),
+        }
+    }
+
+    /// Chooses which chunks are compressed using the given template. By
+    /// default, every chunk except `END` is compressed.
+    pub fn compression_template(mut self, compression: CompressionTemplate) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Writes the given entries into the file's `META` chunk. By default, and
+    /// when `metadata` is empty, no `META` chunk is written.
+    ///
+    /// Studio writes `ExplicitAutoJoints=true` into files it saves, which
+    /// changes how it treats welds when the file is imported again. See
+    /// [`Metadata::set_explicit_auto_joints`].
+    pub fn metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Leaves out properties whose value is the default listed in `database`
+    /// for every instance of a class, which makes files smaller. Properties
+    /// with no known default are still written.
+    ///
+    /// rbx_binary doesn't fill in missing properties when reading, so a file
+    /// written this way reads back without the omitted properties. Code that
+    /// reads it has to apply the defaults from the reflection database itself,
+    /// the way Roblox does when it loads the file.
+    pub fn omit_default_properties(mut self, database: &'db ReflectionDatabase<'db>) -> Self {
+        self.omit_defaults = Some(database);
+        self
     }
 
     /// Serialize a Roblox binary model or place into the given stream using
@@ -44,12 +86,15 @@ impl Serializer {
     pub fn serialize<W: Write>(&self, writer: W, dom: &WeakDom, refs: &[Ref]) -> Result<(), Error> {
         profiling::scope!("rbx_binary::seserialize");
 
-        let mut serializer = SerializerState::new(dom, writer);
+        let mut serializer = SerializerState::new(dom, &self.compression, writer);
 
         serializer.add_instances(refs)?;
-        serializer.generate_referents();
+        if let Some(database) = self.omit_defaults {
+            serializer.omit_default_properties(database);
+        }
+        serializer.generate_referents()?;
         serializer.write_header()?;
-        serializer.serialize_metadata()?;
+        serializer.serialize_metadata(&self.metadata)?;
         serializer.serialize_shared_strings()?;
         serializer.serialize_instances()?;
         serializer.serialize_properties()?;
@@ -58,9 +103,56 @@ impl Serializer {
 
         Ok(())
     }
+
+    /// Serialize the given instance and all of its descendants as a
+    /// standalone model, like Roblox Studio's "Save to File" does.
+    ///
+    /// Any `Ref` property that points to an instance outside of the exported
+    /// subtree is written as a null reference.
+    pub fn export_instance(&self, dom: &WeakDom, root: Ref) -> Result<Vec<u8>, Error> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer, dom, &[root])?;
+
+        Ok(buffer)
+    }
+
+    /// Serialize a Roblox binary model or place into `buffer`, replacing its
+    /// contents. Reusing one buffer for many models avoids allocating a new
+    /// one each time.
+    pub fn serialize_into(
+        &self,
+        buffer: &mut Vec<u8>,
+        dom: &WeakDom,
+        refs: &[Ref],
+    ) -> Result<(), Error> {
+        buffer.clear();
+        self.serialize(buffer, dom, refs)
+    }
+
+    /// Serialize a Roblox binary model or place into the start of `buffer`,
+    /// returning the number of bytes written. This is useful for writing into
+    /// memory that's already been set aside, like a memory-mapped file.
+    ///
+    /// Returns an error if the model doesn't fit in `buffer`. The contents of
+    /// `buffer` are unspecified in that case.
+    pub fn serialize_into_slice(
+        &self,
+        buffer: &mut [u8],
+        dom: &WeakDom,
+        refs: &[Ref],
+    ) -> Result<usize, Error> {
+        let capacity = buffer.len();
+        let mut remaining = &mut buffer[..];
+
+        match self.serialize(&mut remaining, dom, refs) {
+            Ok(()) => Ok(capacity - remaining.len()),
+            Err(err) if err.is_write_zero() => Err(InnerError::BufferTooSmall { capacity }.into()),
+            Err(err) => Err(err),
+        }
+    }
 }
 
-impl Default for Serializer {
+impl Default for Serializer<'_> {
     fn default() -> Self {
         Self::new()
     }
@@ -3,31 +3,32 @@ use std::{
     collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     convert::TryInto,
     io::Write,
-    u32,
 };
 
 use rbx_dom_weak::{
     types::{
         Attributes, Axes, BinaryString, BrickColor, CFrame, Color3, Color3uint8, ColorSequence,
         ColorSequenceKeypoint, Content, Enum, Faces, Matrix3, NumberRange, NumberSequence,
-        NumberSequenceKeypoint, PhysicalProperties, Ray, Rect, Ref, SharedString, Tags, UDim,
-        UDim2, Variant, VariantType, Vector2, Vector3, Vector3int16,
+        NumberSequenceKeypoint, PhysicalProperties, ProtectedString, Ray, Rect, Ref, SharedString,
+        Tags, UDim, UDim2, UniqueId, Variant, VariantType, Vector2, Vector3, Vector3int16,
     },
     Instance, WeakDom,
 };
 
-use rbx_reflection::{ClassDescriptor, ClassTag, DataType};
+use rbx_reflection::{ClassDescriptor, ClassTag, DataType, ReflectionDatabase};
 
 use crate::{
     cframe,
     chunk::{ChunkBuilder, ChunkCompression},
     core::{
-        find_property_descriptors, RbxWriteExt, FILE_MAGIC_HEADER, FILE_SIGNATURE, FILE_VERSION,
+        find_property_descriptors, transform_unique_id, RbxWriteExt, FILE_MAGIC_HEADER,
+        FILE_SIGNATURE, FILE_VERSION,
     },
+    metadata::Metadata,
     types::Type,
 };
 
-use super::error::InnerError;
+use super::{compression::CompressionTemplate, error::InnerError};
 
 static FILE_FOOTER: &[u8] = b"</roblox>";
 
@@ -38,6 +39,9 @@ pub(super) struct SerializerState<'dom, W> {
     /// The dom containing all of the instances that we're serializing.
     dom: &'dom WeakDom,
 
+    /// Which chunks should be compressed.
+    compression: &'dom CompressionTemplate,
+
     /// Where the binary output should be written.
     output: W,
 
@@ -137,6 +141,38 @@ struct PropInfo {
     default_value: Cow<'static, Variant>,
 }
 
+impl PropInfo {
+    /// Finds the value that will be written for this property on the given
+    /// instance, whose canonical name is `prop_name`.
+    fn value_for<'a>(&'a self, prop_name: &str, instance: &'a Instance) -> Cow<'a, Variant> {
+        // We store the Name property in a different field for convenience, but
+        // when serializing to the binary model format we need to handle it
+        // just like other properties.
+        if prop_name == "Name" {
+            return Cow::Owned(Variant::String(instance.name.clone()));
+        }
+
+        // Most properties will be stored on instances using the property's
+        // canonical name, so we'll try that first.
+        if let Some(property) = instance.properties.get(prop_name) {
+            return Cow::Borrowed(property);
+        }
+
+        // If there were any known aliases for this property used as part of
+        // this file, we can check those next.
+        for alias in &self.aliases {
+            if let Some(property) = instance.properties.get(alias) {
+                return Cow::Borrowed(property);
+            }
+        }
+
+        // Finally, we can fall back to the default value we computed for this
+        // PropInfo. This is sourced from the reflection database if available,
+        // or falls back to a reasonable default.
+        Cow::Borrowed(self.default_value.borrow())
+    }
+}
+
 /// Contains all of the `TypeInfo` objects known to the serializer so far. This
 /// struct was broken out to help encapsulate the behavior here and to ease
 /// self-borrowing issues from BinarySerializer getting too large.
@@ -217,9 +253,10 @@ impl<'dom> TypeInfos<'dom> {
 }
 
 impl<'dom, W: Write> SerializerState<'dom, W> {
-    pub fn new(dom: &'dom WeakDom, output: W) -> Self {
+    pub fn new(dom: &'dom WeakDom, compression: &'dom CompressionTemplate, output: W) -> Self {
         SerializerState {
             dom,
+            compression,
             output,
             relevant_instances: Vec::new(),
             id_to_referent: HashMap::new(),
@@ -395,10 +432,50 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
         Ok(())
     }
 
+    /// Stop writing any property whose value on every instance of a class is
+    /// that class's default in `database`. Properties without a known default
+    /// are always written, as is `Name`.
+    ///
+    /// A PROP chunk holds a value for every instance of its class, so a
+    /// property can only be left out if none of those instances need it.
+    #[profiling::function]
+    pub fn omit_default_properties(&mut self, database: &ReflectionDatabase) {
+        for (type_name, type_info) in &mut self.type_infos.values {
+            let instances = &type_info.instances;
+
+            type_info.properties.retain(|prop_name, prop_info| {
+                if prop_name == "Name" {
+                    return true;
+                }
+
+                let default = match database.find_default_property(type_name, prop_name) {
+                    Some(default) => default,
+                    None => return true,
+                };
+
+                let all_default = instances
+                    .iter()
+                    .all(|instance| prop_info.value_for(prop_name, instance).as_ref() == default);
+
+                if all_default {
+                    log::trace!(
+                        "Omitting {}.{}, which is always default",
+                        type_name,
+                        prop_name
+                    );
+                }
+
+                !all_default
+            });
+        }
+    }
+
     /// Populate the map from rbx-dom's instance ID space to the IDs that we'll
     /// be serializing to the model.
     #[profiling::function]
-    pub fn generate_referents(&mut self) {
+    pub fn generate_referents(&mut self) -> Result<(), InnerError> {
+        check_instance_count(self.relevant_instances.len())?;
+
         self.id_to_referent.reserve(self.relevant_instances.len());
 
         for (next_referent, id) in self.relevant_instances.iter().enumerate() {
@@ -407,6 +484,8 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
         }
 
         log::trace!("Referents constructed: {:#?}", self.id_to_referent);
+
+        Ok(())
     }
 
     pub fn write_header(&mut self) -> Result<(), InnerError> {
@@ -426,9 +505,24 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
     }
 
     /// Write out any metadata about this file, stored in a chunk named META.
-    pub fn serialize_metadata(&mut self) -> Result<(), InnerError> {
-        log::trace!("Writing metadata (currently no-op)");
-        // TODO: There is no concept of metadata in a dom yet.
+    pub fn serialize_metadata(&mut self, metadata: &Metadata) -> Result<(), InnerError> {
+        log::trace!("Writing metadata");
+
+        if metadata.is_empty() {
+            return Ok(());
+        }
+
+        let mut chunk = ChunkBuilder::new(b"META", self.compression.metadata());
+
+        chunk.write_le_u32(metadata.len() as u32)?;
+
+        for (key, value) in metadata.iter() {
+            chunk.write_string(key)?;
+            chunk.write_string(value)?;
+        }
+
+        chunk.dump(&mut self.output)?;
+
         Ok(())
     }
 
@@ -442,7 +536,7 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
             return Ok(());
         }
 
-        let mut chunk = ChunkBuilder::new(b"SSTR", ChunkCompression::Compressed);
+        let mut chunk = ChunkBuilder::new(b"SSTR", self.compression.shared_strings());
 
         chunk.write_le_u32(0)?; // SSTR version number
         chunk.write_le_u32(self.shared_strings.len() as u32)?;
@@ -471,7 +565,7 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
                 type_info.instances.len()
             );
 
-            let mut chunk = ChunkBuilder::new(b"INST", ChunkCompression::Compressed);
+            let mut chunk = ChunkBuilder::new(b"INST", self.compression.instances(type_name));
 
             chunk.write_le_u32(type_info.type_id)?;
             chunk.write_string(type_name)?;
@@ -529,7 +623,11 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
                     prop_info.prop_type
                 );
 
-                let mut chunk = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+                let mut chunk = ChunkBuilder::new(
+                    b"PROP",
+                    self.compression
+                        .property(type_name, &prop_info.serialized_name),
+                );
 
                 chunk.write_le_u32(type_info.type_id)?;
                 chunk.write_string(&prop_info.serialized_name)?;
@@ -538,34 +636,7 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
                 let values = type_info
                     .instances
                     .iter()
-                    .map(|instance| {
-                        // We store the Name property in a different field for
-                        // convenience, but when serializing to the binary model
-                        // format we need to handle it just like other properties.
-                        if prop_name == "Name" {
-                            return Cow::Owned(Variant::String(instance.name.clone()));
-                        }
-
-                        // Most properties will be stored on instances using the
-                        // property's canonical name, so we'll try that first.
-                        if let Some(property) = instance.properties.get(prop_name.as_ref()) {
-                            return Cow::Borrowed(property);
-                        }
-
-                        // If there were any known aliases for this property
-                        // used as part of this file, we can check those next.
-                        for alias in &prop_info.aliases {
-                            if let Some(property) = instance.properties.get(alias) {
-                                return Cow::Borrowed(property);
-                            }
-                        }
-
-                        // Finally, we can fall back to the default value we
-                        // computed for this PropInfo. This is sourced from the
-                        // reflection database if available, or falls back to a
-                        // reasonable default.
-                        Cow::Borrowed(prop_info.default_value.borrow())
-                    })
+                    .map(|instance| prop_info.value_for(prop_name, instance))
                     .enumerate();
 
                 // Helper to generate a type mismatch error with context from
@@ -599,6 +670,9 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
                                 Variant::Content(value) => {
                                     chunk.write_string(value.as_ref())?;
                                 }
+                                Variant::ProtectedString(value) => {
+                                    chunk.write_string(value.as_str())?;
+                                }
                                 Variant::BinaryString(value) => {
                                     chunk.write_binary_string(value.as_ref())?;
                                 }
@@ -619,7 +693,8 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
                                     return type_mismatch(
                                         i,
                                         &rbx_value,
-                                        "String, Content, Tags, Attributes, or BinaryString",
+                                        "String, Content, ProtectedString, Tags, Attributes, or \
+                                         BinaryString",
                                     );
                                 }
                             }
@@ -719,7 +794,7 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
                                 chunk.write_le_f32(value.origin.z)?;
                                 chunk.write_le_f32(value.direction.x)?;
                                 chunk.write_le_f32(value.direction.y)?;
-                                chunk.write_le_f32(value.direction.x)?;
+                                chunk.write_le_f32(value.direction.z)?;
                             } else {
                                 return type_mismatch(i, &rbx_value, "Ray");
                             }
@@ -1036,6 +1111,19 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
 
                         chunk.write_interleaved_u32_array(&entries)?;
                     }
+                    Type::UniqueId => {
+                        let mut blobs = Vec::with_capacity(values.len());
+
+                        for (i, rbx_value) in values {
+                            if let Variant::UniqueId(value) = rbx_value.as_ref() {
+                                blobs.push(transform_unique_id(*value));
+                            } else {
+                                return type_mismatch(i, &rbx_value, "UniqueId");
+                            }
+                        }
+
+                        chunk.write_interleaved_bytes(&blobs)?;
+                    }
                     Type::OptionalCFrame => {
                         let mut rotations = Vec::with_capacity(values.len());
                         let mut bools = Vec::with_capacity(values.len());
@@ -1107,7 +1195,7 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
     pub fn serialize_parents(&mut self) -> Result<(), InnerError> {
         log::trace!("Writing parent relationships");
 
-        let mut chunk = ChunkBuilder::new(b"PRNT", ChunkCompression::Compressed);
+        let mut chunk = ChunkBuilder::new(b"PRNT", self.compression.parents());
 
         chunk.write_u8(0)?; // PRNT version 0
         chunk.write_le_u32(self.relevant_instances.len() as u32)?;
@@ -1179,6 +1267,7 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
     fn fallback_default_value(rbx_type: VariantType) -> Option<Variant> {
         Some(match rbx_type {
             VariantType::String => Variant::String(String::new()),
+            VariantType::ProtectedString => Variant::ProtectedString(ProtectedString::new()),
             VariantType::BinaryString => Variant::BinaryString(BinaryString::new()),
             VariantType::Bool => Variant::Bool(false),
             VariantType::Int32 => Variant::Int32(0),
@@ -1228,6 +1317,7 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
             VariantType::Int64 => Variant::Int64(0),
             VariantType::SharedString => Variant::SharedString(SharedString::new(Vec::new())),
             VariantType::OptionalCFrame => Variant::OptionalCFrame(None),
+            VariantType::UniqueId => Variant::UniqueId(UniqueId::nil()),
             VariantType::Tags => Variant::Tags(Tags::new()),
             VariantType::Content => Variant::Content(Content::new()),
             VariantType::Attributes => Variant::Attributes(Attributes::new()),
@@ -1235,3 +1325,34 @@ impl<'dom, W: Write> SerializerState<'dom, W> {
         })
     }
 }
+
+/// Referents are written as `i32` values counting up from zero, so a file
+/// can't hold more instances than there are non-negative `i32` values.
+fn check_instance_count(count: usize) -> Result<(), InnerError> {
+    if count > i32::MAX as usize + 1 {
+        return Err(InnerError::TooManyInstances { count });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn too_many_instances() {
+        let max = i32::MAX as usize + 1;
+
+        assert!(check_instance_count(0).is_ok());
+        assert!(check_instance_count(max).is_ok());
+
+        let error = check_instance_count(max + 1).unwrap_err();
+        assert!(matches!(error, InnerError::TooManyInstances { count } if count == max + 1));
+        assert_eq!(
+            error.to_string(),
+            "Too many instances: 2147483649 instances were given, but the binary format \
+            can only refer to 2147483648 instances."
+        );
+    }
+}
@@ -0,0 +1,512 @@
+use std::{convert::TryInto, io::Write};
+
+use rbx_dom_weak::{
+    types::{Color3, Color3uint8, Ref, SharedString, Variant},
+    DomViewer, InstanceBuilder, WeakDom,
+};
+
+use crate::{
+    chunk::{ChunkBuilder, ChunkCompression},
+    core::RbxWriteExt,
+    from_reader, to_writer, DanglingReference, Deserializer, SkeletonTree,
+};
+
+use super::util::{
+    build_raw_model, end_chunk, gzip, huge_inst_chunk, huge_prnt_chunk, inst_chunk, prnt_chunk,
+};
+
+/// A PROP chunk whose property name isn't valid UTF-8 should produce an error
+/// instead of panicking.
+#[test]
+fn non_utf8_prop_name() {
+    let mut prop = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    prop.write_le_u32(0).unwrap();
+    prop.write_binary_string(b"Bad\xffName").unwrap();
+    prop.write_u8(0x03).unwrap();
+    prop.write_interleaved_i32_array([7].iter().copied())
+        .unwrap();
+
+    let contents = build_raw_model(
+        1,
+        1,
+        vec![
+            inst_chunk(0, "IntValue", &[0]),
+            prop,
+            prnt_chunk(&[(0, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let error = from_reader(contents.as_slice()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid property name: A property of IntValue had a name that was not \
+         valid UTF-8 (\"Bad\u{fffd}Name\")"
+    );
+}
+
+/// Names in INST and PROP chunks are stored inline; version 0 of the format
+/// has no separate string pool for them. If a later version adds one, its
+/// files must be rejected rather than having their names misread.
+#[test]
+fn unknown_file_version() {
+    let mut contents = build_raw_model(
+        1,
+        1,
+        vec![
+            inst_chunk(0, "Folder", &[0]),
+            prnt_chunk(&[(0, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    // The version immediately follows the 8 byte magic and 6 byte signature.
+    contents[14] = 1;
+
+    let error = from_reader(contents.as_slice()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Unknown file version 1. Known versions are: 0"
+    );
+}
+
+/// An INST chunk that declares more instances than it has referents for should
+/// be rejected before anything is allocated for them.
+#[test]
+fn huge_instance_count() {
+    let contents = build_raw_model(1, 1, vec![huge_inst_chunk(), end_chunk()]);
+
+    let error = from_reader(contents.as_slice()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "INST chunk declared 4294967295 instances, but only has room for 0"
+    );
+
+    let error = Deserializer::new()
+        .deserialize_skeleton(contents.as_slice())
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "INST chunk declared 4294967295 instances, but only has room for 0"
+    );
+}
+
+/// PRNT chunks are checked the same way, counting the eight bytes that each
+/// link takes up.
+#[test]
+fn huge_parent_count() {
+    let contents = build_raw_model(
+        1,
+        1,
+        vec![
+            inst_chunk(0, "Folder", &[0]),
+            huge_prnt_chunk(),
+            end_chunk(),
+        ],
+    );
+
+    let error = from_reader(contents.as_slice()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "PRNT chunk declared 4294967295 instances, but only has room for 0"
+    );
+
+    let error = Deserializer::new()
+        .deserialize_skeleton(contents.as_slice())
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "PRNT chunk declared 4294967295 instances, but only has room for 0"
+    );
+}
+
+/// The instance count in the file header isn't used to make room for
+/// instances, so a bogus one doesn't cause an enormous allocation.
+#[test]
+fn huge_header_instance_count() {
+    let contents = build_raw_model(
+        1,
+        u32::MAX,
+        vec![
+            inst_chunk(0, "Folder", &[0]),
+            prnt_chunk(&[(0, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let skeleton = Deserializer::new()
+        .deserialize_skeleton(contents.as_slice())
+        .unwrap();
+    assert_eq!(skeleton.instances().len(), 1);
+    assert_eq!(skeleton.root_instances(), [0]);
+}
+
+/// `Part.Color` is declared as a Color3, but Studio stores it as Color3uint8.
+/// The type byte in the file decides how the value is read, whatever the
+/// reflection database says.
+#[test]
+fn color3_uses_on_disk_type() {
+    let mut as_floats = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    as_floats.write_le_u32(0).unwrap();
+    as_floats.write_string("Color").unwrap();
+    as_floats.write_u8(0x0C).unwrap();
+    as_floats
+        .write_interleaved_f32_array([0.5].iter().copied())
+        .unwrap();
+    as_floats
+        .write_interleaved_f32_array([0.25].iter().copied())
+        .unwrap();
+    as_floats
+        .write_interleaved_f32_array([1.0].iter().copied())
+        .unwrap();
+
+    let mut as_bytes = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    as_bytes.write_le_u32(0).unwrap();
+    as_bytes.write_string("Color").unwrap();
+    as_bytes.write_u8(0x1A).unwrap();
+    as_bytes.write_all(&[128, 64, 255]).unwrap();
+
+    let decode_color = |prop: ChunkBuilder| {
+        let contents = build_raw_model(
+            1,
+            1,
+            vec![
+                inst_chunk(0, "Part", &[0]),
+                prop,
+                prnt_chunk(&[(0, -1)]),
+                end_chunk(),
+            ],
+        );
+
+        let dom = from_reader(contents.as_slice()).unwrap();
+        let part = dom.get_by_ref(dom.root().children()[0]).unwrap();
+        part.properties.get("Color").cloned()
+    };
+
+    assert_eq!(
+        decode_color(as_floats),
+        Some(Variant::Color3(Color3::new(0.5, 0.25, 1.0)))
+    );
+    assert_eq!(
+        decode_color(as_bytes),
+        Some(Variant::Color3uint8(Color3uint8::new(128, 64, 255)))
+    );
+}
+
+/// The skeleton of a file should have exactly the same hierarchy as the fully
+/// decoded tree.
+#[test]
+fn skeleton_matches_full_tree() {
+    fn assert_same_shape(dom: &WeakDom, referent: Ref, skeleton: &SkeletonTree, index: usize) {
+        let instance = dom.get_by_ref(referent).unwrap();
+        let skeleton_instance = skeleton.get(index).unwrap();
+
+        assert_eq!(instance.class, skeleton_instance.class());
+        assert_eq!(
+            instance.children().len(),
+            skeleton_instance.children().len()
+        );
+
+        for (&child_ref, &child_index) in
+            instance.children().iter().zip(skeleton_instance.children())
+        {
+            assert_eq!(skeleton.get(child_index).unwrap().parent(), Some(index));
+            assert_same_shape(dom, child_ref, skeleton, child_index);
+        }
+    }
+
+    let tree = WeakDom::new(
+        InstanceBuilder::new("Folder")
+            .with_child(
+                InstanceBuilder::new("Model")
+                    .with_child(InstanceBuilder::new("Part").with_property("Anchored", true))
+                    .with_child(InstanceBuilder::new("Part"))
+                    .with_child(InstanceBuilder::new("Folder").with_child(
+                        InstanceBuilder::new("StringValue").with_property("Value", "hi"),
+                    )),
+            )
+            .with_child(InstanceBuilder::new("Folder")),
+    );
+
+    let mut contents = Vec::new();
+    to_writer(&mut contents, &tree, &[tree.root_ref()]).unwrap();
+
+    let full = from_reader(contents.as_slice()).unwrap();
+    let skeleton = Deserializer::new()
+        .deserialize_skeleton(contents.as_slice())
+        .unwrap();
+
+    assert_eq!(skeleton.instances().len(), 7);
+    assert_eq!(skeleton.root_instances().len(), 1);
+    assert_eq!(
+        skeleton.get(skeleton.root_instances()[0]).unwrap().parent(),
+        None
+    );
+
+    let full_root = full.root().children()[0];
+    assert_same_shape(&full, full_root, &skeleton, skeleton.root_instances()[0]);
+}
+
+/// Decoding PROP chunks in parallel should build exactly the same tree as
+/// decoding them one at a time.
+#[test]
+fn parallel_matches_sequential() {
+    let mut tree = WeakDom::new(InstanceBuilder::new("Folder"));
+    let root_ref = tree.root_ref();
+
+    for i in 0..50 {
+        let model = tree.insert(
+            root_ref,
+            InstanceBuilder::new("Model").with_name(format!("Model {}", i)),
+        );
+        let part = tree.insert(
+            model,
+            InstanceBuilder::new("Part")
+                .with_property("Anchored", i % 2 == 0)
+                .with_property("Transparency", i as f32 / 50.0)
+                .with_property("Color", Color3uint8::new(i as u8, 0, 255)),
+        );
+        tree.insert(
+            model,
+            InstanceBuilder::new("ObjectValue").with_property("Value", part),
+        );
+        tree.insert(
+            model,
+            InstanceBuilder::new("UnionOperation")
+                .with_property("PhysicsData", SharedString::new(vec![i as u8; 4])),
+        );
+    }
+
+    let mut contents = Vec::new();
+    to_writer(&mut contents, &tree, &[root_ref]).unwrap();
+
+    let sequential = Deserializer::new()
+        .deserialize(contents.as_slice())
+        .unwrap();
+    let parallel = Deserializer::new()
+        .deserialize_parallel(contents.as_slice())
+        .unwrap();
+
+    assert_eq!(
+        format!("{:?}", DomViewer::new().view_children(&parallel)),
+        format!("{:?}", DomViewer::new().view_children(&sequential))
+    );
+}
+
+/// Files compressed with gzip should decode the same as the file they contain.
+#[test]
+fn gzip_matches_plain() {
+    let mut tree = WeakDom::new(InstanceBuilder::new("Folder"));
+    let root_ref = tree.root_ref();
+
+    let mut seed = 1u32;
+    for i in 0..5000 {
+        let value: String = (0..16)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                char::from(b'a' + (seed >> 16) as u8 % 26)
+            })
+            .collect();
+
+        tree.insert(
+            root_ref,
+            InstanceBuilder::new("StringValue")
+                .with_name(format!("Value {}", i))
+                .with_property("Value", value),
+        );
+    }
+
+    let mut contents = Vec::new();
+    to_writer(&mut contents, &tree, &[root_ref]).unwrap();
+    let compressed = gzip(&contents);
+
+    let plain = from_reader(contents.as_slice()).unwrap();
+    let unwrapped = from_reader(compressed.as_slice()).unwrap();
+    assert_eq!(
+        format!("{:?}", DomViewer::new().view_children(&unwrapped)),
+        format!("{:?}", DomViewer::new().view_children(&plain))
+    );
+
+    let skeleton = Deserializer::new()
+        .deserialize_skeleton(compressed.as_slice())
+        .unwrap();
+    assert_eq!(skeleton.instances().len(), 5001);
+
+    // Files made of several gzip members are read as one.
+    let (first, second) = contents.split_at(contents.len() / 2);
+    let mut members = gzip(first);
+    members.extend(gzip(second));
+    let unwrapped = from_reader(members.as_slice()).unwrap();
+    assert_eq!(
+        format!("{:?}", DomViewer::new().view_children(&unwrapped)),
+        format!("{:?}", DomViewer::new().view_children(&plain))
+    );
+}
+
+/// Gzip compressed files that decompress to more than the limit are rejected.
+#[test]
+fn gzip_size_limit() {
+    let tree = WeakDom::new(InstanceBuilder::new("Folder").with_child(
+        InstanceBuilder::new("StringValue").with_property("Value", "a".repeat(100_000)),
+    ));
+
+    let mut contents = Vec::new();
+    to_writer(&mut contents, &tree, tree.root().children()).unwrap();
+    let compressed = gzip(&contents);
+
+    // The whole file has to be read, so an exact limit is fine.
+    Deserializer::new()
+        .gzip_size_limit(contents.len() as u64)
+        .deserialize(compressed.as_slice())
+        .unwrap();
+
+    let error = Deserializer::new()
+        .gzip_size_limit(contents.len() as u64 - 1)
+        .deserialize(compressed.as_slice())
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("larger than the limit"),
+        "{}",
+        error
+    );
+}
+
+/// Files with chunks that claim to be longer than they are can be read with
+/// `recover_chunk_lengths`, but not without it.
+#[test]
+fn recover_chunk_lengths() {
+    let mut names = ChunkBuilder::new(b"PROP", ChunkCompression::Uncompressed);
+    names.write_le_u32(0).unwrap();
+    names.write_string("Name").unwrap();
+    names.write_u8(0x01).unwrap();
+    names.write_string("First").unwrap();
+    names.write_string("Second").unwrap();
+
+    let mut file = build_raw_model(
+        1,
+        2,
+        vec![
+            inst_chunk(0, "Folder", &[0, 1]),
+            names,
+            prnt_chunk(&[(0, -1), (1, 0)]),
+            end_chunk(),
+        ],
+    );
+
+    let expected = {
+        let dom = from_reader(file.as_slice()).unwrap();
+        format!("{:?}", DomViewer::new().view_children(&dom))
+    };
+
+    // Make the INST, PROP, and PRNT chunks each claim 10 more bytes than they
+    // have. The INST and PRNT chunks are compressed, so their compressed
+    // length is the one that's stored in the file.
+    let mut offset = 32;
+    while &file[offset..offset + 4] != b"END\0" {
+        let field = if file[offset + 4..offset + 8] == [0; 4] {
+            offset + 8
+        } else {
+            offset + 4
+        };
+
+        let stored_len = u32::from_le_bytes(file[field..field + 4].try_into().unwrap());
+        file[field..field + 4].copy_from_slice(&(stored_len + 10).to_le_bytes());
+
+        offset += 16 + stored_len as usize;
+    }
+
+    assert!(from_reader(file.as_slice()).is_err());
+
+    let dom = Deserializer::new()
+        .recover_chunk_lengths(true)
+        .deserialize(file.as_slice())
+        .unwrap();
+    assert_eq!(
+        format!("{:?}", DomViewer::new().view_children(&dom)),
+        expected
+    );
+}
+
+fn ref_prop_chunk(type_id: u32, name: &str, values: &[i32]) -> ChunkBuilder {
+    let mut chunk = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    chunk.write_le_u32(type_id).unwrap();
+    chunk.write_string(name).unwrap();
+    chunk.write_u8(0x13).unwrap();
+    chunk.write_referent_array(values.iter().copied()).unwrap();
+    chunk
+}
+
+/// PRNT links to referents that no INST chunk declares should produce an
+/// error listing them instead of panicking.
+#[test]
+fn dangling_parents() {
+    let contents = build_raw_model(
+        1,
+        2,
+        vec![
+            inst_chunk(0, "Folder", &[0, 1]),
+            prnt_chunk(&[(0, -1), (1, 7), (9, 0)]),
+            end_chunk(),
+        ],
+    );
+
+    let error = from_reader(contents.as_slice()).unwrap_err();
+    assert_eq!(
+        error.dangling_references(),
+        &[
+            DanglingReference::Subject { subject: 9 },
+            DanglingReference::Parent {
+                subject: 1,
+                parent: 7
+            },
+        ]
+    );
+}
+
+/// Dangling `Ref` properties are only reported when asked for; otherwise
+/// they're read as `None`.
+#[test]
+fn dangling_ref_properties() {
+    let contents = build_raw_model(
+        1,
+        2,
+        vec![
+            inst_chunk(0, "ObjectValue", &[0, 1]),
+            ref_prop_chunk(0, "Value", &[1, 5]),
+            prnt_chunk(&[(0, -1), (1, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let dom = from_reader(contents.as_slice()).unwrap();
+    let second = dom.get_by_ref(dom.root().children()[1]).unwrap();
+    assert_eq!(
+        second.properties.get("Value"),
+        Some(&Variant::Ref(Ref::none()))
+    );
+
+    let expected = [DanglingReference::Property {
+        instance: 1,
+        property: "Value".to_owned(),
+        value: 5,
+    }];
+    let deserializer = Deserializer::new().validate_referents(true);
+
+    let error = deserializer.deserialize(contents.as_slice()).unwrap_err();
+    assert_eq!(error.dangling_references(), &expected);
+    assert!(error.to_string().contains("Value"), "{}", error);
+
+    let error = deserializer
+        .deserialize_parallel(contents.as_slice())
+        .unwrap_err();
+    assert_eq!(error.dangling_references(), &expected);
+
+    // A file whose references all resolve should still be read normally.
+    let dom = WeakDom::new(
+        InstanceBuilder::new("Folder")
+            .with_child(InstanceBuilder::new("ObjectValue").with_property("Value", Ref::none())),
+    );
+    let mut encoded = Vec::new();
+    to_writer(&mut encoded, &dom, &[dom.root_ref()]).unwrap();
+    deserializer.deserialize(encoded.as_slice()).unwrap();
+}
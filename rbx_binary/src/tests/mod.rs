@@ -1,3 +1,5 @@
+mod deserializer;
 mod models;
 mod serializer;
+mod text_deserializer;
 mod util;
@@ -1,9 +1,19 @@
+use std::collections::HashMap;
+
 use rbx_dom_weak::{
-    types::{Color3, Color3uint8, Ref, Region3, Vector3},
+    types::{
+        CFrame, Color3, Color3uint8, Matrix3, Ray, Ref, Region3, SharedString, UniqueId, Variant,
+        Vector3, Vector3int16,
+    },
     InstanceBuilder, WeakDom,
 };
 
-use crate::{text_deserializer::DecodedModel, to_writer};
+use crate::{
+    chunk::{ChunkCompression, StoredCompression},
+    from_reader,
+    text_deserializer::{DecodedChunk, DecodedModel},
+    to_writer, CompressionTemplate, Metadata, ModelBuilder, Serializer,
+};
 
 /// A basic test to make sure we can serialize the simplest instance: a Folder.
 #[test]
@@ -50,6 +60,147 @@ fn unknown_property() {
     insta::assert_yaml_snapshot!(decoded);
 }
 
+/// Properties that hold their default value on every instance of a class are
+/// left out when asked, but a property is kept if any instance needs it or if
+/// its default isn't known.
+#[test]
+fn omit_default_properties() {
+    let tree = WeakDom::new(InstanceBuilder::new("Folder").with_children(vec![
+        InstanceBuilder::new("BoolValue").with_property("Value", false),
+        InstanceBuilder::new("BoolValue"),
+        InstanceBuilder::new("IntValue").with_property("Value", 0),
+        InstanceBuilder::new("IntValue").with_property("Value", 5),
+        InstanceBuilder::new("Folder").with_property("WILL_NEVER_EXIST", "Hi, mom!"),
+    ]));
+
+    let mut buffer = Vec::new();
+    Serializer::new()
+        .omit_default_properties(rbx_reflection_database::get())
+        .serialize(&mut buffer, &tree, tree.root().children())
+        .unwrap();
+
+    let decoded = DecodedModel::from_reader(buffer.as_slice());
+    let mut type_names = HashMap::new();
+    let mut props = Vec::new();
+
+    for chunk in &decoded.chunks {
+        match chunk {
+            DecodedChunk::Inst {
+                type_id, type_name, ..
+            } => {
+                type_names.insert(*type_id, type_name.as_str());
+            }
+            DecodedChunk::Prop {
+                type_id, prop_name, ..
+            } => props.push(format!("{}.{}", type_names[type_id], prop_name)),
+            _ => {}
+        }
+    }
+
+    assert_eq!(
+        props,
+        [
+            "BoolValue.Name",
+            "Folder.Name",
+            "Folder.WILL_NEVER_EXIST",
+            "IntValue.Name",
+            "IntValue.Value",
+        ]
+    );
+
+    let round_tripped = from_reader(buffer.as_slice()).unwrap();
+    let int_values: Vec<_> = round_tripped
+        .root()
+        .children()
+        .iter()
+        .map(|&referent| round_tripped.get_by_ref(referent).unwrap())
+        .filter(|instance| instance.class == "IntValue")
+        .map(|instance| instance.properties.get("Value").cloned())
+        .collect();
+    assert_eq!(
+        int_values,
+        [Some(Variant::Int64(0)), Some(Variant::Int64(5))]
+    );
+}
+
+/// Omitted properties aren't filled back in when the file is read, so they're
+/// missing from the instances that come back.
+#[test]
+fn omitted_properties_are_missing_when_read() {
+    let tree = WeakDom::new(
+        InstanceBuilder::new("Folder")
+            .with_child(InstanceBuilder::new("BoolValue").with_property("Value", false)),
+    );
+
+    let bool_value = |serializer: Serializer| {
+        let mut buffer = Vec::new();
+        serializer
+            .serialize(&mut buffer, &tree, tree.root().children())
+            .unwrap();
+
+        let decoded = from_reader(buffer.as_slice()).unwrap();
+        let referent = decoded.root().children()[0];
+        decoded
+            .get_by_ref(referent)
+            .unwrap()
+            .properties
+            .get("Value")
+            .cloned()
+    };
+
+    assert_eq!(bool_value(Serializer::new()), Some(Variant::Bool(false)));
+    assert_eq!(
+        bool_value(Serializer::new().omit_default_properties(rbx_reflection_database::get())),
+        None
+    );
+}
+
+/// Metadata given to the serializer is written into a META chunk at the start
+/// of the file, with entries we don't know about kept as they are.
+#[test]
+fn metadata() {
+    let tree = WeakDom::new(InstanceBuilder::new("Folder"));
+
+    let mut metadata: Metadata = vec![("SomethingNew".to_owned(), "1".to_owned())]
+        .into_iter()
+        .collect();
+    metadata.set_explicit_auto_joints(true);
+
+    let mut compression = CompressionTemplate::new();
+    compression.set_metadata(ChunkCompression::Uncompressed);
+
+    let mut buffer = Vec::new();
+    Serializer::new()
+        .metadata(metadata.clone())
+        .compression_template(compression)
+        .serialize(&mut buffer, &tree, &[tree.root_ref()])
+        .unwrap();
+
+    let decoded = DecodedModel::from_reader(buffer.as_slice());
+    match &decoded.chunks[0] {
+        DecodedChunk::Meta {
+            compression,
+            entries,
+            remaining,
+        } => {
+            assert_eq!(*compression, StoredCompression::Raw);
+            assert_eq!(entries, &metadata);
+            assert_eq!(entries.explicit_auto_joints(), Some(true));
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected a META chunk, got {:?}", other),
+    }
+
+    let mut without_metadata = Vec::new();
+    Serializer::new()
+        .metadata(Metadata::new())
+        .serialize(&mut without_metadata, &tree, &[tree.root_ref()])
+        .unwrap();
+
+    let decoded = DecodedModel::from_reader(without_metadata.as_slice());
+    assert!(!matches!(decoded.chunks[0], DecodedChunk::Meta { .. }));
+}
+
 /// Ensures that serializing a tree with an unimplemented property type returns
 /// an error instead of panicking.
 ///
@@ -153,3 +304,445 @@ fn part_color() {
     let decoded = DecodedModel::from_reader(buf.as_slice());
     insta::assert_yaml_snapshot!(decoded);
 }
+
+/// Serializing into a reused buffer or a slice should give exactly the same
+/// bytes as serializing into a new `Vec`.
+#[test]
+fn serialize_into_buffers() {
+    let tree = WeakDom::new(
+        InstanceBuilder::new("Folder")
+            .with_child(InstanceBuilder::new("StringValue").with_property("Value", "Hello")),
+    );
+    let refs = &[tree.root_ref()];
+
+    let mut expected = Vec::new();
+    to_writer(&mut expected, &tree, refs).unwrap();
+
+    let serializer = Serializer::new();
+
+    let mut buffer = b"left over from last time".to_vec();
+    serializer.serialize_into(&mut buffer, &tree, refs).unwrap();
+    assert_eq!(buffer, expected);
+
+    let mut slice = vec![0xFF; expected.len() + 16];
+    let written = serializer
+        .serialize_into_slice(&mut slice, &tree, refs)
+        .unwrap();
+    assert_eq!(&slice[..written], expected.as_slice());
+
+    let mut too_small = vec![0; expected.len() - 1];
+    let error = serializer
+        .serialize_into_slice(&mut too_small, &tree, refs)
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("too small"),
+        "unexpected error: {}",
+        error
+    );
+}
+
+/// Serializing equal trees should always produce identical bytes, even though
+/// each tree has its own randomly generated referents and its properties are
+/// stored in hash maps.
+#[test]
+fn reproducible_output() {
+    fn build() -> WeakDom {
+        let mut tree = WeakDom::new(InstanceBuilder::new("Folder"));
+        let root_ref = tree.root_ref();
+
+        for i in 0..20 {
+            let part = tree.insert(
+                root_ref,
+                InstanceBuilder::new("Part")
+                    .with_name(format!("Part {}", i))
+                    .with_property("Anchored", i % 2 == 0)
+                    .with_property("Size", Vector3::new(i as f32, 1.0, 1.0))
+                    .with_property("Color", Color3uint8::new(i as u8, 0, 0)),
+            );
+            tree.insert(
+                part,
+                InstanceBuilder::new("ObjectValue").with_property("Value", part),
+            );
+            tree.insert(
+                part,
+                InstanceBuilder::new("UnionOperation")
+                    .with_property("PhysicsData", SharedString::new(vec![i as u8; 8])),
+            );
+        }
+
+        tree
+    }
+
+    fn serialize(tree: &WeakDom) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, tree, &[tree.root_ref()]).unwrap();
+        buffer
+    }
+
+    let tree = build();
+    let first = serialize(&tree);
+
+    assert_eq!(serialize(&tree), first);
+    assert_eq!(serialize(&build()), first);
+}
+
+/// Reading a file and writing it back out with its compression template should
+/// compress exactly the same chunks as the original file.
+#[test]
+fn compression_template_round_trip() {
+    let mut tree = WeakDom::new(InstanceBuilder::new("Folder"));
+    let root_ref = tree.root_ref();
+
+    for i in 0..4 {
+        tree.insert(
+            root_ref,
+            InstanceBuilder::new("Part")
+                .with_name(format!("Part {}", i))
+                .with_property("Anchored", true),
+        );
+        tree.insert(
+            root_ref,
+            InstanceBuilder::new("UnionOperation")
+                .with_property("PhysicsData", SharedString::new(vec![i as u8; 8])),
+        );
+    }
+
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, &tree, &[root_ref]).unwrap();
+
+    // Leave a few chunks of every kind uncompressed, like Studio does for
+    // chunks that don't get any smaller.
+    let mut original = DecodedModel::from_reader(buffer.as_slice());
+    for chunk in &mut original.chunks {
+        let uncompressed = match chunk {
+            DecodedChunk::Sstr { .. } | DecodedChunk::Prnt { .. } => true,
+            DecodedChunk::Inst { type_name, .. } => type_name == "Part",
+            DecodedChunk::Prop { prop_name, .. } => prop_name == "Name",
+            _ => false,
+        };
+
+        if uncompressed {
+            chunk.set_compression(StoredCompression::Raw);
+        }
+    }
+
+    let mut original_buffer = Vec::new();
+    original.to_writer(&mut original_buffer).unwrap();
+    let original = DecodedModel::from_reader(original_buffer.as_slice());
+
+    let dom = from_reader(original_buffer.as_slice()).unwrap();
+    let mut round_tripped = Vec::new();
+    Serializer::new()
+        .compression_template(original.compression_template())
+        .serialize(&mut round_tripped, &dom, dom.root().children())
+        .unwrap();
+    let round_tripped = DecodedModel::from_reader(round_tripped.as_slice());
+
+    let compression = |model: &DecodedModel| {
+        model
+            .chunks
+            .iter()
+            .map(DecodedChunk::compression)
+            .collect::<Vec<_>>()
+    };
+
+    let uncompressed = compression(&original)
+        .into_iter()
+        .filter(|&compression| compression == StoredCompression::Raw)
+        .count();
+    // SSTR, PRNT, END, the Part INST, and a Name PROP for each of the three
+    // classes
+    assert_eq!(uncompressed, 7);
+    assert_eq!(compression(&round_tripped), compression(&original));
+}
+
+/// Exporting an instance should write only that instance and its descendants,
+/// with references to anything else cleared.
+#[test]
+fn export_instance() {
+    let mut tree = WeakDom::new(InstanceBuilder::new("DataModel"));
+    let workspace = tree.insert(tree.root_ref(), InstanceBuilder::new("Workspace"));
+    let outside = tree.insert(workspace, InstanceBuilder::new("Part").with_name("Outside"));
+    let model = tree.insert(workspace, InstanceBuilder::new("Model").with_name("Car"));
+    let body = tree.insert(model, InstanceBuilder::new("Part").with_name("Body"));
+    let wheel = tree.insert(model, InstanceBuilder::new("Part").with_name("Wheel"));
+    tree.insert(
+        wheel,
+        InstanceBuilder::new("ObjectValue")
+            .with_name("Inside")
+            .with_property("Value", body),
+    );
+    tree.insert(
+        wheel,
+        InstanceBuilder::new("ObjectValue")
+            .with_name("External")
+            .with_property("Value", outside),
+    );
+
+    let buffer = Serializer::new().export_instance(&tree, model).unwrap();
+    let exported = from_reader(buffer.as_slice()).unwrap();
+
+    let roots = exported.root().children();
+    assert_eq!(roots.len(), 1);
+
+    let model = exported.get_by_ref(roots[0]).unwrap();
+    assert_eq!(model.class, "Model");
+    assert_eq!(model.name, "Car");
+
+    let parts: Vec<_> = model
+        .children()
+        .iter()
+        .map(|&part| exported.get_by_ref(part).unwrap())
+        .collect();
+    let names: Vec<_> = parts.iter().map(|part| part.name.as_str()).collect();
+    assert_eq!(names, ["Body", "Wheel"]);
+
+    let values: Vec<_> = parts[1]
+        .children()
+        .iter()
+        .map(|&value| exported.get_by_ref(value).unwrap())
+        .collect();
+    assert_eq!(values[0].name, "Inside");
+    assert_eq!(
+        values[0].properties.get("Value"),
+        Some(&Variant::Ref(parts[0].referent()))
+    );
+    assert_eq!(values[1].name, "External");
+    assert_eq!(
+        values[1].properties.get("Value"),
+        Some(&Variant::Ref(Ref::none()))
+    );
+}
+
+/// Present and absent OptionalCFrame values must both survive a round trip;
+/// an absent value must not come back as an identity CFrame.
+#[test]
+fn optional_cframe_round_trip() {
+    let pivot = CFrame::new(
+        Vector3::new(1.0, 2.0, 3.0),
+        Matrix3 {
+            x: Vector3::new(0.0, 0.0, 1.0),
+            y: Vector3::new(0.0, 1.0, 0.0),
+            z: Vector3::new(-1.0, 0.0, 0.0),
+        },
+    );
+
+    let tree = WeakDom::new(InstanceBuilder::new("Folder").with_children(vec![
+        InstanceBuilder::new("Model")
+            .with_name("Present")
+            .with_property("WorldPivotData", Variant::OptionalCFrame(Some(pivot))),
+        InstanceBuilder::new("Model")
+            .with_name("Absent")
+            .with_property("WorldPivotData", Variant::OptionalCFrame(None)),
+    ]));
+
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, &tree, &[tree.root_ref()]).unwrap();
+    let decoded = from_reader(buffer.as_slice()).unwrap();
+
+    let folder = decoded.get_by_ref(decoded.root().children()[0]).unwrap();
+    let pivots: Vec<_> = folder
+        .children()
+        .iter()
+        .map(|&model| {
+            let model = decoded.get_by_ref(model).unwrap();
+            (model.name.as_str(), model.properties.get("WorldPivotData"))
+        })
+        .collect();
+
+    assert_eq!(
+        pivots,
+        [
+            ("Present", Some(&Variant::OptionalCFrame(Some(pivot)))),
+            ("Absent", Some(&Variant::OptionalCFrame(None))),
+        ]
+    );
+}
+
+#[test]
+fn unique_id_round_trip() {
+    let ids = [
+        UniqueId::new(-2, 0x0123_4567, 42),
+        UniqueId::new(i64::MAX, u32::MAX, 0),
+        UniqueId::nil(),
+    ];
+
+    let tree = WeakDom::new(
+        InstanceBuilder::new("Folder").with_children(
+            ids.iter()
+                .map(|&id| InstanceBuilder::new("Part").with_property("UniqueId", id)),
+        ),
+    );
+
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, &tree, &[tree.root_ref()]).unwrap();
+    let decoded = from_reader(buffer.as_slice()).unwrap();
+
+    let folder = decoded.get_by_ref(decoded.root().children()[0]).unwrap();
+    let decoded_ids: Vec<_> = folder
+        .children()
+        .iter()
+        .map(|&part| {
+            decoded
+                .get_by_ref(part)
+                .unwrap()
+                .properties
+                .get("UniqueId")
+                .cloned()
+        })
+        .collect();
+
+    assert_eq!(
+        decoded_ids,
+        ids.iter()
+            .map(|&id| Some(Variant::UniqueId(id)))
+            .collect::<Vec<_>>()
+    );
+}
+
+/// Vector3int16 values are stored one after another as little-endian `i16`s,
+/// without interleaving. Negative components make sign mistakes visible.
+#[test]
+fn vector3int16_negative_round_trip() {
+    let values = [
+        Vector3int16::new(1, 2, 3),
+        Vector3int16::new(-1, -2, -3),
+        Vector3int16::new(i16::MIN, 0, i16::MAX),
+    ];
+
+    let tree = WeakDom::new(
+        InstanceBuilder::new("Folder").with_children(
+            values
+                .iter()
+                .map(|&value| InstanceBuilder::new("Model").with_property("Cells", value)),
+        ),
+    );
+
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, &tree, &[tree.root_ref()]).unwrap();
+
+    let model = DecodedModel::from_reader(buffer.as_slice());
+    let cells = model
+        .chunks
+        .iter()
+        .find_map(|chunk| match chunk {
+            DecodedChunk::Prop {
+                prop_name, values, ..
+            } if prop_name == "Cells" => Some(values),
+            _ => None,
+        })
+        .unwrap();
+
+    assert_eq!(
+        format!("{:?}", cells),
+        format!("Some(Vector3int16({:?}))", values.to_vec())
+    );
+
+    let decoded = from_reader(buffer.as_slice()).unwrap();
+    let folder = decoded.get_by_ref(decoded.root().children()[0]).unwrap();
+    let decoded_values: Vec<_> = folder
+        .children()
+        .iter()
+        .map(|&child| {
+            decoded
+                .get_by_ref(child)
+                .unwrap()
+                .properties
+                .get("Cells")
+                .cloned()
+        })
+        .collect();
+
+    assert_eq!(
+        decoded_values,
+        values
+            .iter()
+            .map(|&value| Some(Variant::Vector3int16(value)))
+            .collect::<Vec<_>>()
+    );
+}
+
+/// Roblox doesn't normalize the direction of stored rays, so every component
+/// of a ray with a long, skewed direction must survive a round trip.
+#[test]
+fn ray_round_trip() {
+    let ray = Ray::new(
+        Vector3::new(1.0, -2.0, 3.5),
+        Vector3::new(4.0, 50.0, -600.0),
+    );
+
+    let tree = WeakDom::new(InstanceBuilder::new("RayValue").with_property("Value", ray));
+
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, &tree, &[tree.root_ref()]).unwrap();
+    let decoded = from_reader(buffer.as_slice()).unwrap();
+
+    let instance = decoded.get_by_ref(decoded.root().children()[0]).unwrap();
+    assert_eq!(instance.properties.get("Value"), Some(&Variant::Ray(ray)));
+}
+
+/// A model put together with `ModelBuilder` should decode back into the same
+/// instances, with references between them intact.
+#[test]
+fn model_builder() {
+    let target = InstanceBuilder::new("Part").with_name("Target");
+    let target_ref = target.referent();
+
+    let mut builder = ModelBuilder::new();
+    builder.add_instance(
+        InstanceBuilder::new("Folder")
+            .with_name("Parts")
+            .with_child(target)
+            .with_child(InstanceBuilder::new("Part").with_name("Other")),
+    );
+    let value_ref = builder.add_instance(
+        InstanceBuilder::new("ObjectValue")
+            .with_name("Pointer")
+            .with_property("Value", target_ref),
+    );
+
+    let encoded = builder.build().unwrap();
+    let decoded = from_reader(encoded.as_slice()).unwrap();
+
+    let built = builder.dom();
+    assert_eq!(
+        decoded.root().children().len(),
+        built.root().children().len()
+    );
+
+    let folder = decoded.get_by_ref(decoded.root().children()[0]).unwrap();
+    assert_eq!(folder.name, "Parts");
+    assert_eq!(folder.class, "Folder");
+    let names: Vec<_> = folder
+        .children()
+        .iter()
+        .map(|&child| decoded.get_by_ref(child).unwrap().name.as_str())
+        .collect();
+    assert_eq!(names, ["Target", "Other"]);
+
+    let pointer = decoded.get_by_ref(decoded.root().children()[1]).unwrap();
+    assert_eq!(pointer.name, built.get_by_ref(value_ref).unwrap().name);
+    let pointed_at = match pointer.properties.get("Value") {
+        Some(Variant::Ref(referent)) => decoded.get_by_ref(*referent).unwrap(),
+        other => panic!("expected a Ref, got {:?}", other),
+    };
+    assert_eq!(pointed_at.name, "Target");
+
+    // Settings from a custom serializer should be used when writing.
+    let mut metadata = Metadata::new();
+    metadata.set_explicit_auto_joints(true);
+    let with_metadata = ModelBuilder::new()
+        .serializer(Serializer::new().metadata(metadata.clone()))
+        .with_instance(InstanceBuilder::new("Folder").with_name("Parts"))
+        .build()
+        .unwrap();
+    let has_metadata = DecodedModel::from_reader(with_metadata.as_slice())
+        .chunks
+        .iter()
+        .any(|chunk| matches!(chunk, DecodedChunk::Meta { entries, .. } if *entries == metadata));
+    assert!(has_metadata);
+
+    let decoded = from_reader(with_metadata.as_slice()).unwrap();
+    let folder = decoded.get_by_ref(decoded.root().children()[0]).unwrap();
+    assert_eq!(folder.name, "Parts");
+}
@@ -0,0 +1,1669 @@
+use std::io::{self, Write};
+
+use rbx_dom_weak::{
+    types::{
+        Axes, BrickColor, CFrame, Color3, Color3uint8, ColorSequence, ColorSequenceKeypoint,
+        CustomPhysicalProperties, Enum, Faces, Matrix3, NumberRange, NumberSequence,
+        NumberSequenceKeypoint, PhysicalProperties, Ray, Rect, SharedString, UDim, UDim2, Variant,
+        Vector2, Vector3, Vector3int16,
+    },
+    DomViewer, InstanceBuilder, WeakDom,
+};
+
+use crate::{
+    chunk::{ChunkBuilder, ChunkCompression, StoredCompression},
+    core::RbxWriteExt,
+    from_reader,
+    text_deserializer::{
+        BytecodeHeader, CompiledScript, DecodeError, DecodeWarning, DecodedCFrame, DecodedChunk,
+        DecodedColorSequence, DecodedModel, DecodedValues, FileOrigin, LazyChunk, LazyDecodedModel,
+        OriginConfidence, RobloxString,
+    },
+    to_writer,
+};
+
+use super::util::{
+    build_raw_model, end_chunk, huge_inst_chunk, huge_prnt_chunk, inst_chunk, name_prop_chunk,
+    prnt_chunk, recompress_with_zstd,
+};
+
+/// Models saved by Studio are classified as coming from Studio. The benchmark
+/// files were all saved by Studio.
+#[test]
+fn origin_studio() {
+    let files: [&[u8]; 3] = [
+        include_bytes!("../../bench-files/folders-100.rbxm"),
+        include_bytes!("../../bench-files/deep-folders-100.rbxm"),
+        include_bytes!("../../bench-files/modulescripts-100-lines-100.rbxm"),
+    ];
+
+    for contents in &files {
+        let decoded = DecodedModel::from_reader(*contents);
+        assert_eq!(
+            decoded.detect_origin(),
+            FileOrigin::Studio(OriginConfidence::High)
+        );
+    }
+}
+
+/// rbx_binary doesn't write a META chunk unless it's given metadata, and always
+/// writes chunks in the same order.
+#[test]
+fn origin_rbx_binary() {
+    let tree = WeakDom::new(InstanceBuilder::new("Folder").with_children(vec![
+        InstanceBuilder::new("StringValue"),
+        InstanceBuilder::new("BoolValue"),
+    ]));
+
+    let mut contents = Vec::new();
+    to_writer(&mut contents, &tree, &[tree.root_ref()]).unwrap();
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    assert_eq!(
+        decoded.detect_origin(),
+        FileOrigin::RbxBinary(OriginConfidence::Medium)
+    );
+}
+
+/// A hand-built file that interleaves INST and PROP chunks doesn't look like
+/// anything we know about.
+#[test]
+fn origin_unknown() {
+    let contents = build_raw_model(
+        2,
+        2,
+        vec![
+            inst_chunk(0, "Folder", &[0]),
+            name_prop_chunk(0, &["Folder"]),
+            inst_chunk(1, "Model", &[1]),
+            name_prop_chunk(1, &["Model"]),
+            prnt_chunk(&[(0, -1), (1, 0)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    assert_eq!(decoded.detect_origin(), FileOrigin::Unknown);
+}
+
+/// Malformed files should produce an error from `try_from_reader` instead
+/// of a panic.
+#[test]
+fn try_from_reader_errors() {
+    let tree = WeakDom::new(
+        InstanceBuilder::new("Folder")
+            .with_child(InstanceBuilder::new("StringValue").with_property("Value", "hello")),
+    );
+
+    let mut contents = Vec::new();
+    to_writer(&mut contents, &tree, &[tree.root_ref()]).unwrap();
+
+    assert!(DecodedModel::try_from_reader(contents.as_slice()).is_ok());
+
+    for len in &[0, 10, contents.len() / 2, contents.len() - 1] {
+        let result = DecodedModel::try_from_reader(&contents[..*len]);
+        assert!(result.is_err(), "truncating to {} bytes should fail", len);
+    }
+
+    let mut bad_magic = contents.clone();
+    bad_magic[0] = b'?';
+    assert!(matches!(
+        DecodedModel::try_from_reader(bad_magic.as_slice()),
+        Err(DecodeError::Header { .. })
+    ));
+
+    let mut prop = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    prop.write_le_u32(0).unwrap();
+    prop.write_string("BrickColor").unwrap();
+    prop.write_u8(0x0B).unwrap();
+    prop.write_interleaved_u32_array(&[999_999]).unwrap();
+
+    let bad_brick_color = build_raw_model(
+        1,
+        1,
+        vec![
+            inst_chunk(0, "Part", &[0]),
+            prop,
+            prnt_chunk(&[(0, -1)]),
+            end_chunk(),
+        ],
+    );
+    assert!(matches!(
+        DecodedModel::try_from_reader(bad_brick_color.as_slice()),
+        Err(DecodeError::InvalidBrickColor { value: 999_999 })
+    ));
+
+    let huge_inst = build_raw_model(1, 1, vec![huge_inst_chunk(), end_chunk()]);
+    assert!(matches!(
+        DecodedModel::try_from_reader(huge_inst.as_slice()),
+        Err(DecodeError::TooManyInstances {
+            chunk_name: "INST",
+            count: u32::MAX,
+            max: 0,
+        })
+    ));
+
+    let huge_prnt = build_raw_model(
+        1,
+        1,
+        vec![inst_chunk(0, "Part", &[0]), huge_prnt_chunk(), end_chunk()],
+    );
+    assert!(matches!(
+        DecodedModel::try_from_reader(huge_prnt.as_slice()),
+        Err(DecodeError::TooManyInstances {
+            chunk_name: "PRNT",
+            count: u32::MAX,
+            max: 0,
+        })
+    ));
+}
+
+/// Property names that aren't valid UTF-8 are decoded lossily instead of
+/// panicking.
+#[test]
+fn non_utf8_prop_name() {
+    let mut prop = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    prop.write_le_u32(0).unwrap();
+    prop.write_binary_string(b"Bad\xffName").unwrap();
+    prop.write_u8(0x03).unwrap();
+    prop.write_interleaved_i32_array([7].iter().copied())
+        .unwrap();
+
+    let contents = build_raw_model(
+        1,
+        1,
+        vec![
+            inst_chunk(0, "IntValue", &[0]),
+            prop,
+            prnt_chunk(&[(0, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    match &decoded.chunks[1] {
+        DecodedChunk::Prop { prop_name, .. } => assert_eq!(prop_name, "Bad\u{fffd}Name"),
+        other => panic!("expected a PROP chunk, got {:?}", other),
+    }
+}
+
+/// Every questionable thing about a file should end up in its list of
+/// warnings.
+#[test]
+fn warnings() {
+    let mut unknown_type = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    unknown_type.write_le_u32(0).unwrap();
+    unknown_type.write_string("Mystery").unwrap();
+    unknown_type.write_u8(0xFF).unwrap();
+
+    let contents = build_raw_model(
+        1,
+        3,
+        vec![
+            ChunkBuilder::new(b"WHAT", ChunkCompression::Uncompressed),
+            inst_chunk(0, "Folder", &[0]),
+            name_prop_chunk(0, &["Folder"]),
+            name_prop_chunk(5, &["Orphan"]),
+            unknown_type,
+            prnt_chunk(&[(0, -1), (1, 0)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    assert_eq!(
+        decoded.warnings,
+        vec![
+            DecodeWarning::UnknownChunk {
+                name: "WHAT".to_owned()
+            },
+            DecodeWarning::UndeclaredTypeId {
+                type_id: 5,
+                prop_name: "Name".to_owned()
+            },
+            DecodeWarning::UnknownPropType {
+                type_id: 0,
+                prop_name: "Mystery".to_owned(),
+                prop_type: 0xFF
+            },
+            DecodeWarning::InstanceCountMismatch {
+                header: 3,
+                actual: 1
+            },
+            DecodeWarning::DanglingReferent { referent: 1 },
+        ]
+    );
+}
+
+/// A PRNT chunk with a version we don't know shouldn't have its referent
+/// arrays guessed at. Its contents should be kept around untouched instead.
+#[test]
+fn unknown_prnt_version() {
+    let tail = [1, 0, 0, 0, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE];
+
+    let mut prnt = ChunkBuilder::new(b"PRNT", ChunkCompression::Compressed);
+    prnt.write_u8(2).unwrap();
+    prnt.write_all(&tail).unwrap();
+
+    let contents = build_raw_model(
+        1,
+        1,
+        vec![
+            inst_chunk(0, "Folder", &[0]),
+            name_prop_chunk(0, &["Folder"]),
+            prnt,
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    assert_eq!(
+        decoded.warnings,
+        vec![DecodeWarning::UnknownPrntVersion { version: 2 }]
+    );
+
+    match &decoded.chunks[2] {
+        DecodedChunk::Prnt {
+            version,
+            links,
+            remaining,
+            ..
+        } => {
+            assert_eq!(*version, 2);
+            assert!(links.is_empty());
+            assert_eq!(remaining.as_slice(), &tail[..]);
+        }
+        other => panic!("expected a PRNT chunk, got {:?}", other),
+    }
+}
+
+/// Vector3 values are stored as three interleaved arrays of floats, one for
+/// each axis.
+#[test]
+fn vector3_values() {
+    let values = [Vector3::new(1.0, -2.5, 0.0), Vector3::new(-0.125, 1e6, 3.0)];
+
+    let mut prop = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    prop.write_le_u32(0).unwrap();
+    prop.write_string("Size").unwrap();
+    prop.write_u8(0x0E).unwrap();
+    prop.write_interleaved_f32_array(values.iter().map(|value| value.x))
+        .unwrap();
+    prop.write_interleaved_f32_array(values.iter().map(|value| value.y))
+        .unwrap();
+    prop.write_interleaved_f32_array(values.iter().map(|value| value.z))
+        .unwrap();
+
+    let contents = build_raw_model(
+        1,
+        2,
+        vec![
+            inst_chunk(0, "Part", &[0, 1]),
+            prop,
+            prnt_chunk(&[(0, -1), (1, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    match &decoded.chunks[1] {
+        DecodedChunk::Prop {
+            values: Some(DecodedValues::Vector3(decoded_values)),
+            remaining,
+            ..
+        } => {
+            assert_eq!(decoded_values.as_slice(), &values[..]);
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected a Vector3 PROP chunk, got {:?}", other),
+    }
+}
+
+/// A PROP chunk for a type that was declared outside of the file can be
+/// decoded when the number of instances of that type is passed in.
+#[test]
+fn prop_chunk_with_external_counts() {
+    let contents = build_raw_model(0, 0, vec![name_prop_chunk(3, &["A", "B"]), end_chunk()]);
+
+    let without_counts = DecodedModel::from_reader(contents.as_slice());
+    assert!(matches!(
+        &without_counts.chunks[0],
+        DecodedChunk::Prop { values: None, .. }
+    ));
+    assert!(matches!(
+        without_counts.warnings.as_slice(),
+        [DecodeWarning::UndeclaredTypeId { type_id: 3, .. }]
+    ));
+
+    let counts = vec![(3, 2)].into_iter().collect();
+    let decoded = DecodedModel::from_reader_with_counts(contents.as_slice(), counts);
+    match &decoded.chunks[0] {
+        DecodedChunk::Prop {
+            values: Some(DecodedValues::String(names)),
+            remaining,
+            ..
+        } => {
+            let names: Vec<_> = names
+                .iter()
+                .map(|name| match name {
+                    RobloxString::String(name) => name.as_str(),
+                    RobloxString::BinaryString(_) => panic!("names should be UTF-8"),
+                })
+                .collect();
+            assert_eq!(names, ["A", "B"]);
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected a String PROP chunk, got {:?}", other),
+    }
+    assert!(decoded.warnings.is_empty());
+}
+
+/// Enums are stored as an interleaved array of u32s and are decoded as their
+/// raw numbers, without looking up their names.
+#[test]
+fn enum_values() {
+    let mut prop = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    prop.write_le_u32(0).unwrap();
+    prop.write_string("Material").unwrap();
+    prop.write_u8(0x12).unwrap();
+    prop.write_interleaved_u32_array(&[256, 1088, 12345])
+        .unwrap();
+
+    let contents = build_raw_model(
+        1,
+        3,
+        vec![
+            inst_chunk(0, "Part", &[0, 1, 2]),
+            prop,
+            prnt_chunk(&[(0, -1), (1, -1), (2, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    match &decoded.chunks[1] {
+        DecodedChunk::Prop {
+            values: Some(DecodedValues::Enum(values)),
+            remaining,
+            ..
+        } => {
+            let values: Vec<u32> = values.iter().map(|value| value.to_u32()).collect();
+            assert_eq!(values, vec![256, 1088, 12345]);
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected an Enum PROP chunk, got {:?}", other),
+    }
+}
+
+/// Color3uint8 values are stored as three arrays of bytes, in R, G, B order.
+/// They should be decoded as-is instead of being normalized to floats.
+#[test]
+fn color3uint8_values() {
+    let mut prop = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    prop.write_le_u32(0).unwrap();
+    prop.write_string("Color").unwrap();
+    prop.write_u8(0x1A).unwrap();
+    prop.write_all(&[0, 255, 1, 128, 2, 64]).unwrap();
+
+    let contents = build_raw_model(
+        1,
+        2,
+        vec![
+            inst_chunk(0, "Part", &[0, 1]),
+            prop,
+            prnt_chunk(&[(0, -1), (1, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    match &decoded.chunks[1] {
+        DecodedChunk::Prop {
+            values: Some(DecodedValues::Color3uint8(values)),
+            remaining,
+            ..
+        } => {
+            assert_eq!(
+                values,
+                &[Color3uint8::new(0, 1, 2), Color3uint8::new(255, 128, 64)]
+            );
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected a Color3uint8 PROP chunk, got {:?}", other),
+    }
+}
+
+/// NumberSequence and ColorSequence values aren't interleaved. Each sequence
+/// is written out whole, starting with its number of keypoints.
+#[test]
+fn sequence_values() {
+    let mut numbers = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    numbers.write_le_u32(0).unwrap();
+    numbers.write_string("Transparency").unwrap();
+    numbers.write_u8(0x15).unwrap();
+    numbers.write_le_u32(1).unwrap();
+    for value in &[0.0, 0.5, 0.25] {
+        numbers.write_le_f32(*value).unwrap();
+    }
+    numbers.write_le_u32(2).unwrap();
+    for value in &[0.0, 1.0, 0.0, 1.0, 0.0, 0.0] {
+        numbers.write_le_f32(*value).unwrap();
+    }
+
+    let mut colors = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    colors.write_le_u32(0).unwrap();
+    colors.write_string("Color").unwrap();
+    colors.write_u8(0x16).unwrap();
+    colors.write_le_u32(2).unwrap();
+    for value in &[0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0] {
+        colors.write_le_f32(*value).unwrap();
+    }
+    colors.write_le_u32(1).unwrap();
+    for value in &[0.0, 0.5, 0.5, 0.5, 0.0] {
+        colors.write_le_f32(*value).unwrap();
+    }
+
+    let contents = build_raw_model(
+        1,
+        2,
+        vec![
+            inst_chunk(0, "ParticleEmitter", &[0, 1]),
+            numbers,
+            colors,
+            prnt_chunk(&[(0, -1), (1, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    match &decoded.chunks[1] {
+        DecodedChunk::Prop {
+            values: Some(DecodedValues::NumberSequence(values)),
+            remaining,
+            ..
+        } => {
+            assert_eq!(
+                values,
+                &[
+                    NumberSequence {
+                        keypoints: vec![NumberSequenceKeypoint::new(0.0, 0.5, 0.25)]
+                    },
+                    NumberSequence {
+                        keypoints: vec![
+                            NumberSequenceKeypoint::new(0.0, 1.0, 0.0),
+                            NumberSequenceKeypoint::new(1.0, 0.0, 0.0),
+                        ]
+                    },
+                ]
+            );
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected a NumberSequence PROP chunk, got {:?}", other),
+    }
+    match &decoded.chunks[2] {
+        DecodedChunk::Prop {
+            values: Some(DecodedValues::ColorSequence(values)),
+            remaining,
+            ..
+        } => {
+            assert_eq!(
+                values,
+                &[
+                    DecodedColorSequence {
+                        sequence: ColorSequence {
+                            keypoints: vec![
+                                ColorSequenceKeypoint::new(0.0, Color3::new(1.0, 0.0, 0.0)),
+                                ColorSequenceKeypoint::new(1.0, Color3::new(0.0, 0.0, 1.0)),
+                            ]
+                        },
+                        envelopes: vec![0.0, 0.0],
+                    },
+                    DecodedColorSequence {
+                        sequence: ColorSequence {
+                            keypoints: vec![ColorSequenceKeypoint::new(
+                                0.0,
+                                Color3::new(0.5, 0.5, 0.5)
+                            )]
+                        },
+                        envelopes: vec![0.0],
+                    },
+                ]
+            );
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected a ColorSequence PROP chunk, got {:?}", other),
+    }
+}
+
+/// NumberRange values are stored as pairs of plain floats, while Rect values
+/// are stored as four interleaved arrays of floats.
+#[test]
+fn number_range_and_rect_values() {
+    let mut ranges = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    ranges.write_le_u32(0).unwrap();
+    ranges.write_string("Lifetime").unwrap();
+    ranges.write_u8(0x17).unwrap();
+    for value in &[1.0, 2.0, -3.0, 4.5] {
+        ranges.write_le_f32(*value).unwrap();
+    }
+
+    let rects = [
+        Rect::new(Vector2::new(0.0, 1.0), Vector2::new(2.0, 3.0)),
+        Rect::new(Vector2::new(-4.0, 5.0), Vector2::new(6.0, -7.0)),
+    ];
+
+    let mut rect_prop = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    rect_prop.write_le_u32(0).unwrap();
+    rect_prop.write_string("SliceCenter").unwrap();
+    rect_prop.write_u8(0x18).unwrap();
+    rect_prop
+        .write_interleaved_f32_array(rects.iter().map(|rect| rect.min.x))
+        .unwrap();
+    rect_prop
+        .write_interleaved_f32_array(rects.iter().map(|rect| rect.min.y))
+        .unwrap();
+    rect_prop
+        .write_interleaved_f32_array(rects.iter().map(|rect| rect.max.x))
+        .unwrap();
+    rect_prop
+        .write_interleaved_f32_array(rects.iter().map(|rect| rect.max.y))
+        .unwrap();
+
+    let contents = build_raw_model(
+        1,
+        2,
+        vec![
+            inst_chunk(0, "ImageLabel", &[0, 1]),
+            ranges,
+            rect_prop,
+            prnt_chunk(&[(0, -1), (1, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    match &decoded.chunks[1] {
+        DecodedChunk::Prop {
+            values: Some(DecodedValues::NumberRange(values)),
+            remaining,
+            ..
+        } => {
+            assert_eq!(
+                values,
+                &[NumberRange::new(1.0, 2.0), NumberRange::new(-3.0, 4.5)]
+            );
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected a NumberRange PROP chunk, got {:?}", other),
+    }
+    match &decoded.chunks[2] {
+        DecodedChunk::Prop {
+            values: Some(DecodedValues::Rect(values)),
+            remaining,
+            ..
+        } => {
+            assert_eq!(values.as_slice(), &rects[..]);
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected a Rect PROP chunk, got {:?}", other),
+    }
+}
+
+/// Each PhysicalProperties value starts with a byte that says whether custom
+/// properties follow, so custom and default values can be mixed freely.
+#[test]
+fn physical_properties_values() {
+    let custom = CustomPhysicalProperties {
+        density: 0.7,
+        friction: 0.3,
+        elasticity: 0.5,
+        friction_weight: 1.0,
+        elasticity_weight: 2.0,
+    };
+
+    let mut prop = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    prop.write_le_u32(0).unwrap();
+    prop.write_string("CustomPhysicalProperties").unwrap();
+    prop.write_u8(0x19).unwrap();
+    prop.write_u8(0).unwrap();
+    prop.write_u8(1).unwrap();
+    prop.write_le_f32(custom.density).unwrap();
+    prop.write_le_f32(custom.friction).unwrap();
+    prop.write_le_f32(custom.elasticity).unwrap();
+    prop.write_le_f32(custom.friction_weight).unwrap();
+    prop.write_le_f32(custom.elasticity_weight).unwrap();
+    prop.write_u8(0).unwrap();
+
+    let contents = build_raw_model(
+        1,
+        3,
+        vec![
+            inst_chunk(0, "Part", &[0, 1, 2]),
+            prop,
+            prnt_chunk(&[(0, -1), (1, -1), (2, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    match &decoded.chunks[1] {
+        DecodedChunk::Prop {
+            values: Some(DecodedValues::PhysicalProperties(values)),
+            remaining,
+            ..
+        } => {
+            assert_eq!(
+                values,
+                &[
+                    PhysicalProperties::Default,
+                    PhysicalProperties::Custom(custom),
+                    PhysicalProperties::Default,
+                ]
+            );
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected a PhysicalProperties PROP chunk, got {:?}", other),
+    }
+}
+
+/// SharedString properties are stored as indices into the SSTR chunk. Equal
+/// values should share one entry, no matter which class they're on.
+#[test]
+fn shared_string_values() {
+    let first = SharedString::new(b"first".to_vec());
+    let second = SharedString::new(b"second".to_vec());
+
+    let tree = WeakDom::new(
+        InstanceBuilder::new("Folder")
+            .with_child(
+                InstanceBuilder::new("UnionOperation").with_property("PhysicsData", first.clone()),
+            )
+            .with_child(
+                InstanceBuilder::new("UnionOperation").with_property("PhysicsData", second.clone()),
+            )
+            .with_child(
+                InstanceBuilder::new("MeshPart").with_property("PhysicsData", first.clone()),
+            ),
+    );
+
+    let mut contents = Vec::new();
+    to_writer(&mut contents, &tree, tree.root().children()).unwrap();
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+
+    let entries = decoded
+        .chunks
+        .iter()
+        .find_map(|chunk| match chunk {
+            DecodedChunk::Sstr { entries, .. } => Some(entries),
+            _ => None,
+        })
+        .expect("expected an SSTR chunk");
+    assert_eq!(entries.len(), 2);
+
+    let mut referenced = Vec::new();
+    for chunk in &decoded.chunks {
+        if let DecodedChunk::Prop {
+            values: Some(DecodedValues::SharedString(indices)),
+            ..
+        } = chunk
+        {
+            referenced.extend(indices.iter().map(|&index| entries[index as usize].data()));
+        }
+    }
+
+    referenced.sort();
+    assert_eq!(referenced, vec![&b"first"[..], b"first", b"second"]);
+}
+
+/// Ref values are stored as an interleaved array of zig-zag encoded deltas
+/// between consecutive referents. The bytes here are written out by hand so
+/// that a change to that scheme can't cancel itself out.
+#[test]
+fn ref_values() {
+    let mut prop = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    prop.write_le_u32(0).unwrap();
+    prop.write_string("Value").unwrap();
+    prop.write_u8(0x13).unwrap();
+    // Deltas of 7 and -4, which zig-zag encode to 14 and 7.
+    prop.write_all(&[0, 0, 0, 0, 0, 0, 14, 7]).unwrap();
+
+    let contents = build_raw_model(
+        1,
+        2,
+        vec![
+            inst_chunk(0, "ObjectValue", &[3, 7]),
+            name_prop_chunk(0, &["A", "B"]),
+            prop,
+            prnt_chunk(&[(3, -1), (7, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    match &decoded.chunks[2] {
+        DecodedChunk::Prop {
+            values: Some(DecodedValues::Ref(values)),
+            remaining,
+            ..
+        } => {
+            assert_eq!(values, &[7, 3]);
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected a Ref PROP chunk, got {:?}", other),
+    }
+
+    let dom = from_reader(contents.as_slice()).unwrap();
+    let children = dom.root().children();
+    let a = dom.get_by_ref(children[0]).unwrap();
+    let b = dom.get_by_ref(children[1]).unwrap();
+
+    assert_eq!(a.properties.get("Value"), Some(&Variant::Ref(b.referent())));
+    assert_eq!(b.properties.get("Value"), Some(&Variant::Ref(a.referent())));
+}
+
+/// CFrames in the same chunk can mix compact orientation IDs with full
+/// rotation matrices. Both forms should keep the ID they were stored with.
+#[test]
+fn cframe_mixed_orientations() {
+    let rotated = Matrix3::new(
+        Vector3::new(0.6, 0.8, 0.0),
+        Vector3::new(-0.8, 0.6, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+    );
+
+    let mut prop = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    prop.write_le_u32(0).unwrap();
+    prop.write_string("CFrame").unwrap();
+    prop.write_u8(0x10).unwrap();
+
+    prop.write_u8(0x02).unwrap();
+    prop.write_u8(0x00).unwrap();
+    for row in &[rotated.x, rotated.y, rotated.z] {
+        prop.write_le_f32(row.x).unwrap();
+        prop.write_le_f32(row.y).unwrap();
+        prop.write_le_f32(row.z).unwrap();
+    }
+    prop.write_u8(0x0E).unwrap();
+
+    prop.write_interleaved_f32_array([1.0, 4.0, 7.0].iter().copied())
+        .unwrap();
+    prop.write_interleaved_f32_array([2.0, 5.0, 8.0].iter().copied())
+        .unwrap();
+    prop.write_interleaved_f32_array([3.0, 6.0, 9.0].iter().copied())
+        .unwrap();
+
+    let contents = build_raw_model(
+        1,
+        3,
+        vec![
+            inst_chunk(0, "Part", &[0, 1, 2]),
+            prop,
+            prnt_chunk(&[(0, -1), (1, -1), (2, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    let values = match &decoded.chunks[1] {
+        DecodedChunk::Prop {
+            values: Some(DecodedValues::CFrame(values)),
+            remaining,
+            ..
+        } => {
+            assert!(remaining.is_empty());
+            values
+        }
+        other => panic!("expected a CFrame PROP chunk, got {:?}", other),
+    };
+
+    let ids: Vec<u8> = values.iter().map(|value| value.orientation_id).collect();
+    assert_eq!(ids, vec![0x02, 0x00, 0x0E]);
+
+    assert_eq!(values[0].cframe.orientation, Matrix3::identity());
+    assert_eq!(values[1].cframe.orientation, rotated);
+    assert_eq!(
+        values[2].cframe.orientation,
+        Matrix3::new(
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+        )
+    );
+    assert_eq!(values[2].cframe.position, Vector3::new(7.0, 8.0, 9.0));
+}
+
+/// An SSTR chunk should keep its version and stored hashes when it's
+/// re-encoded, and a file that is loaded and saved again should keep the
+/// same SSTR version.
+#[test]
+fn sstr_round_trip() {
+    let hash: Vec<u8> = (1..=16).collect();
+
+    let mut expected = Vec::new();
+    expected.write_le_u32(0).unwrap();
+    expected.write_le_u32(1).unwrap();
+    expected.write_all(&hash).unwrap();
+    expected.write_binary_string(b"hello").unwrap();
+
+    let mut sstr = ChunkBuilder::new(b"SSTR", ChunkCompression::Compressed);
+    sstr.write_all(&expected).unwrap();
+
+    let mut prop = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    prop.write_le_u32(0).unwrap();
+    prop.write_string("PhysicsData").unwrap();
+    prop.write_u8(0x1C).unwrap();
+    prop.write_interleaved_u32_array(&[0]).unwrap();
+
+    let contents = build_raw_model(
+        1,
+        1,
+        vec![
+            sstr,
+            inst_chunk(0, "UnionOperation", &[0]),
+            prop,
+            prnt_chunk(&[(0, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    assert_eq!(decoded.chunks[0].encode(), expected);
+
+    let dom = from_reader(contents.as_slice()).unwrap();
+    let mut reencoded = Vec::new();
+    to_writer(&mut reencoded, &dom, dom.root().children()).unwrap();
+
+    let redecoded = DecodedModel::from_reader(reencoded.as_slice());
+    let versions: Vec<u32> = redecoded
+        .chunks
+        .iter()
+        .filter_map(|chunk| match chunk {
+            DecodedChunk::Sstr { version, .. } => Some(*version),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(versions, vec![0]);
+}
+
+/// SIGN chunks are decoded into their signatures, keep their raw bytes, and
+/// mark the file as coming from Studio.
+#[test]
+fn sign_chunk() {
+    let mut expected = Vec::new();
+    expected.write_le_u32(1).unwrap();
+    expected.write_le_i32(1).unwrap();
+    expected.write_le_i64(-5_000_000_000).unwrap();
+    expected.write_binary_string(&[0xAB; 64]).unwrap();
+
+    let mut sign = ChunkBuilder::new(b"SIGN", ChunkCompression::Compressed);
+    sign.write_all(&expected).unwrap();
+
+    let contents = build_raw_model(0, 0, vec![sign, end_chunk()]);
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    match &decoded.chunks[0] {
+        DecodedChunk::Sign {
+            signatures,
+            remaining,
+            ..
+        } => {
+            assert_eq!(signatures.len(), 1);
+            assert_eq!(signatures[0].signature_type, 1);
+            assert_eq!(signatures[0].public_key_id, -5_000_000_000);
+            assert_eq!(signatures[0].value, vec![0xAB; 64]);
+            assert!(remaining.is_empty());
+        }
+        other => panic!("expected a SIGN chunk, got {:?}", other),
+    }
+
+    assert!(decoded.warnings.is_empty());
+    assert_eq!(
+        decoded.detect_origin(),
+        FileOrigin::Studio(OriginConfidence::High)
+    );
+    assert_eq!(decoded.chunks[0].encode(), expected);
+}
+
+/// META, SSTR, and SIGN chunks whose entry counts are far larger than their
+/// contents fail once they run out of data, without reserving room for every
+/// entry first.
+#[test]
+fn huge_entry_counts() {
+    for name in [b"META", b"SSTR", b"SIGN"] {
+        let mut chunk = ChunkBuilder::new(name, ChunkCompression::Compressed);
+        if name == b"SSTR" {
+            chunk.write_le_u32(0).unwrap();
+        }
+        chunk.write_le_u32(u32::MAX).unwrap();
+
+        let contents = build_raw_model(0, 0, vec![chunk, end_chunk()]);
+
+        match DecodedModel::try_from_reader(contents.as_slice()) {
+            Err(DecodeError::Io { source }) => {
+                assert_eq!(source.kind(), io::ErrorKind::UnexpectedEof)
+            }
+            other => panic!("expected an I/O error, got {:?}", other),
+        }
+    }
+}
+
+/// Bytes after the entries in a META chunk are kept when re-encoding it.
+#[test]
+fn meta_trailing_bytes_round_trip() {
+    let mut meta = ChunkBuilder::new(b"META", ChunkCompression::Compressed);
+    meta.write_le_u32(1).unwrap();
+    meta.write_string("ExplicitAutoJoints").unwrap();
+    meta.write_string("true").unwrap();
+    meta.write_all(&[0xDE, 0xAD, 0xBE, 0xEF]).unwrap();
+
+    let mut expected = Vec::new();
+    expected.write_le_u32(1).unwrap();
+    expected.write_string("ExplicitAutoJoints").unwrap();
+    expected.write_string("true").unwrap();
+    expected.write_all(&[0xDE, 0xAD, 0xBE, 0xEF]).unwrap();
+
+    let contents = build_raw_model(0, 0, vec![meta, end_chunk()]);
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    match &decoded.chunks[0] {
+        DecodedChunk::Meta { remaining, .. } => assert_eq!(remaining, &[0xDE, 0xAD, 0xBE, 0xEF]),
+        other => panic!("expected a META chunk, got {:?}", other),
+    }
+
+    assert_eq!(decoded.chunks[0].encode(), expected);
+}
+
+#[test]
+fn lazy_values() {
+    let mut dom = WeakDom::new(InstanceBuilder::new("Folder"));
+    let root = dom.root_ref();
+    for i in 0..3 {
+        dom.insert(
+            root,
+            InstanceBuilder::new("Part")
+                .with_name(format!("Part{}", i))
+                .with_property("Transparency", i as f32 / 2.0)
+                .with_property("Size", Vector3::new(i as f32, 1.0, 2.0)),
+        );
+    }
+
+    let mut contents = Vec::new();
+    to_writer(&mut contents, &dom, &[root]).unwrap();
+
+    let eager = DecodedModel::from_reader(contents.as_slice());
+    let lazy = LazyDecodedModel::from_reader(contents.as_slice());
+    assert_eq!(lazy.decoded_count(), 0);
+
+    // Asking for one property only decodes that property, and asking again
+    // reuses the values that were already decoded.
+    let transparency = lazy
+        .chunks
+        .iter()
+        .find_map(|chunk| match chunk {
+            LazyChunk::Prop(prop) if prop.prop_name() == "Transparency" => Some(prop),
+            _ => None,
+        })
+        .unwrap();
+    let first = transparency.values().unwrap().unwrap() as *const DecodedValues;
+    let second = transparency.values().unwrap().unwrap() as *const DecodedValues;
+    assert_eq!(first, second);
+    assert_eq!(lazy.decoded_count(), 1);
+
+    assert_lazy_matches_eager(&lazy, &eager);
+
+    let prop_count = lazy
+        .chunks
+        .iter()
+        .filter(|chunk| matches!(chunk, LazyChunk::Prop(_)))
+        .count();
+    assert_eq!(lazy.decoded_count(), prop_count);
+}
+
+/// Files with ZSTD compressed chunks decode the same way lazily as they do
+/// eagerly, including how each chunk was stored.
+#[test]
+fn lazy_zstd() {
+    let dom = WeakDom::new(
+        InstanceBuilder::new("Folder").with_child(
+            InstanceBuilder::new("Part")
+                .with_property("Transparency", 0.5f32)
+                .with_property("Size", Vector3::new(1.0, 2.0, 3.0)),
+        ),
+    );
+
+    let mut contents = Vec::new();
+    to_writer(&mut contents, &dom, &[dom.root_ref()]).unwrap();
+    let contents = recompress_with_zstd(&contents);
+
+    let eager = DecodedModel::from_reader(contents.as_slice());
+    let lazy = LazyDecodedModel::from_reader(contents.as_slice());
+    assert!(lazy.chunks.iter().any(|chunk| matches!(
+        chunk,
+        LazyChunk::Prop(prop) if prop.compression() == StoredCompression::Zstd
+    )));
+
+    assert_lazy_matches_eager(&lazy, &eager);
+}
+
+/// Checks that every chunk of a lazily decoded file matches the same chunk of
+/// the eagerly decoded file, decoding every lazy property along the way.
+fn assert_lazy_matches_eager(lazy: &LazyDecodedModel, eager: &DecodedModel) {
+    assert_eq!(lazy.num_types, eager.num_types);
+    assert_eq!(lazy.num_instances, eager.num_instances);
+    assert_eq!(lazy.warnings, eager.warnings);
+    assert_eq!(lazy.chunks.len(), eager.chunks.len());
+
+    for (lazy_chunk, eager_chunk) in lazy.chunks.iter().zip(&eager.chunks) {
+        assert_eq!(lazy_chunk.compression(), eager_chunk.compression());
+
+        match (lazy_chunk, eager_chunk) {
+            (
+                LazyChunk::Prop(prop),
+                DecodedChunk::Prop {
+                    type_id,
+                    prop_name,
+                    prop_type,
+                    values,
+                    remaining,
+                    ..
+                },
+            ) => {
+                assert_eq!(prop.type_id(), *type_id);
+                assert_eq!(prop.prop_name(), prop_name);
+                assert_eq!(
+                    format!("{:?}", prop.prop_type()),
+                    format!("{:?}", prop_type)
+                );
+                assert_eq!(
+                    format!("{:?}", prop.values().unwrap()),
+                    format!("{:?}", values.as_ref())
+                );
+                assert_eq!(prop.remaining().unwrap(), remaining.as_slice());
+            }
+            (LazyChunk::Other(lazy_chunk), eager_chunk) => {
+                assert_eq!(format!("{:?}", lazy_chunk), format!("{:?}", eager_chunk));
+            }
+            (lazy_chunk, eager_chunk) => {
+                panic!("chunk mismatch: {:?} vs {:?}", lazy_chunk, eager_chunk)
+            }
+        }
+    }
+}
+
+/// Encoding a decoded model should give back exactly the bytes it was decoded
+/// from, for every type of value that rbx_binary writes.
+#[test]
+fn to_writer_round_trip() {
+    let rotated = CFrame::new(
+        Vector3::new(1.0, 2.0, 3.0),
+        Matrix3::new(
+            Vector3::new(0.6, 0.0, 0.8),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(-0.8, 0.0, 0.6),
+        ),
+    );
+
+    let part = InstanceBuilder::new("Part")
+        .with_property("Anchored", true)
+        .with_property("Transparency", 0.25f32)
+        .with_property("Size", Vector3::new(4.0, 1.0, 2.0))
+        .with_property("CFrame", rotated)
+        .with_property("Color", Color3uint8::new(10, 20, 30))
+        .with_property("Material", Enum::from_u32(512))
+        .with_property(
+            "CustomPhysicalProperties",
+            PhysicalProperties::Custom(CustomPhysicalProperties {
+                density: 1.0,
+                friction: 0.5,
+                elasticity: 0.25,
+                friction_weight: 1.0,
+                elasticity_weight: 2.0,
+            }),
+        );
+    let part_ref = part.referent();
+
+    let dom = WeakDom::new(
+        InstanceBuilder::new("Folder")
+            .with_child(part)
+            .with_child(
+                InstanceBuilder::new("ParticleEmitter")
+                    .with_property(
+                        "Color",
+                        ColorSequence {
+                            keypoints: vec![
+                                ColorSequenceKeypoint::new(0.0, Color3::new(1.0, 0.0, 0.0)),
+                                ColorSequenceKeypoint::new(1.0, Color3::new(0.0, 0.0, 1.0)),
+                            ],
+                        },
+                    )
+                    .with_property(
+                        "Size",
+                        NumberSequence {
+                            keypoints: vec![
+                                NumberSequenceKeypoint::new(0.0, 1.0, 0.5),
+                                NumberSequenceKeypoint::new(1.0, 2.0, 0.0),
+                            ],
+                        },
+                    )
+                    .with_property("Lifetime", NumberRange::new(1.0, 5.0)),
+            )
+            .with_child(InstanceBuilder::new("ObjectValue").with_property("Value", part_ref))
+            .with_child(InstanceBuilder::new("IntValue").with_property("Value", 1i64 << 40))
+            .with_child(InstanceBuilder::new("NumberValue").with_property("Value", 0.1f64))
+            .with_child(
+                InstanceBuilder::new("BrickColorValue")
+                    .with_property("Value", BrickColor::BrightRed),
+            )
+            .with_child(
+                InstanceBuilder::new("Color3Value")
+                    .with_property("Value", Color3::new(0.25, 0.5, 0.75)),
+            )
+            .with_child(
+                InstanceBuilder::new("TerrainRegion")
+                    .with_property("ExtentsMax", Vector3int16::new(1, -2, 3)),
+            )
+            .with_child(InstanceBuilder::new("StringValue").with_property("Value", "hello"))
+            .with_child(InstanceBuilder::new("RayValue").with_property(
+                "Value",
+                Ray::new(Vector3::new(1.0, 2.0, 3.0), Vector3::new(4.0, 5.0, 6.0)),
+            ))
+            .with_child(
+                InstanceBuilder::new("Handles")
+                    .with_property("Faces", Faces::from_bits(0b1010).unwrap()),
+            )
+            .with_child(InstanceBuilder::new("ArcHandles").with_property("Axes", Axes::Y))
+            .with_child(
+                InstanceBuilder::new("Frame")
+                    .with_property("Size", UDim2::new(UDim::new(0.5, 10), UDim::new(1.0, -20)))
+                    .with_property("ZIndex", 3)
+                    .with_child(
+                        InstanceBuilder::new("ImageLabel")
+                            .with_property(
+                                "SliceCenter",
+                                Rect::new(Vector2::new(1.0, 2.0), Vector2::new(3.0, 4.0)),
+                            )
+                            .with_property("ImageRectSize", Vector2::new(5.0, 6.0)),
+                    )
+                    .with_child(
+                        InstanceBuilder::new("UIListLayout")
+                            .with_property("Padding", UDim::new(0.0, 8)),
+                    ),
+            )
+            .with_child(
+                InstanceBuilder::new("UnionOperation")
+                    .with_property("PhysicsData", SharedString::new(b"physics".to_vec())),
+            )
+            .with_child(
+                InstanceBuilder::new("Model")
+                    .with_property("WorldPivotData", Variant::OptionalCFrame(Some(rotated))),
+            )
+            .with_child(
+                InstanceBuilder::new("Model")
+                    .with_property("WorldPivotData", Variant::OptionalCFrame(None)),
+            ),
+    );
+
+    let mut contents = Vec::new();
+    to_writer(&mut contents, &dom, &[dom.root_ref()]).unwrap();
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    let mut encoded = Vec::new();
+    decoded.to_writer(&mut encoded).unwrap();
+
+    assert_eq!(encoded, contents);
+
+    let redecoded = DecodedModel::from_reader(encoded.as_slice());
+    assert_eq!(format!("{:?}", redecoded), format!("{:?}", decoded));
+}
+
+/// Rotations are written with the orientation ID they were read with, even
+/// when a full matrix was stored for a rotation that has a shorter ID, and
+/// ColorSequence envelopes are written back as they were read.
+#[test]
+fn to_writer_keeps_stored_encodings() {
+    let mut cframes = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    cframes.write_le_u32(0).unwrap();
+    cframes.write_string("CFrame").unwrap();
+    cframes.write_u8(0x10).unwrap();
+    cframes.write_u8(0).unwrap();
+    for value in &[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0] {
+        cframes.write_le_f32(*value).unwrap();
+    }
+    cframes
+        .write_interleaved_f32_array([1.0].iter().copied())
+        .unwrap();
+    cframes
+        .write_interleaved_f32_array([2.0].iter().copied())
+        .unwrap();
+    cframes
+        .write_interleaved_f32_array([3.0].iter().copied())
+        .unwrap();
+
+    let mut pivots = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    pivots.write_le_u32(1).unwrap();
+    pivots.write_string("WorldPivotData").unwrap();
+    pivots.write_u8(0x1E).unwrap();
+    pivots.write_u8(0x10).unwrap();
+    pivots.write_u8(0).unwrap();
+    for value in &[0.0, 0.0, 1.0, 0.0, 1.0, 0.0, -1.0, 0.0, 0.0] {
+        pivots.write_le_f32(*value).unwrap();
+    }
+    pivots
+        .write_interleaved_f32_array([4.0].iter().copied())
+        .unwrap();
+    pivots
+        .write_interleaved_f32_array([5.0].iter().copied())
+        .unwrap();
+    pivots
+        .write_interleaved_f32_array([6.0].iter().copied())
+        .unwrap();
+    pivots.write_u8(0x02).unwrap();
+    pivots.write_u8(1).unwrap();
+
+    let mut colors = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    colors.write_le_u32(2).unwrap();
+    colors.write_string("Color").unwrap();
+    colors.write_u8(0x16).unwrap();
+    colors.write_le_u32(1).unwrap();
+    for value in &[0.0, 1.0, 0.0, 0.0, 0.75] {
+        colors.write_le_f32(*value).unwrap();
+    }
+
+    let contents = build_raw_model(
+        3,
+        3,
+        vec![
+            inst_chunk(0, "Part", &[0]),
+            inst_chunk(1, "Model", &[1]),
+            inst_chunk(2, "ParticleEmitter", &[2]),
+            cframes,
+            pivots,
+            colors,
+            prnt_chunk(&[(0, -1), (1, -1), (2, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    assert!(decoded.warnings.is_empty(), "{:?}", decoded.warnings);
+
+    let values: Vec<_> = decoded
+        .chunks
+        .iter()
+        .filter_map(|chunk| match chunk {
+            DecodedChunk::Prop { values, .. } => values.as_ref(),
+            _ => None,
+        })
+        .collect();
+
+    match values[..] {
+        [DecodedValues::CFrame(cframes), DecodedValues::OptionalCFrame(_, pivots), DecodedValues::ColorSequence(colors)] =>
+        {
+            assert_eq!(cframes[0].orientation_id, 0);
+            assert_eq!(pivots[0].as_ref().unwrap().orientation_id, 0);
+            assert_eq!(colors[0].envelopes, [0.75]);
+        }
+        _ => panic!(
+            "expected CFrame, OptionalCFrame and ColorSequence values, got {:?}",
+            values
+        ),
+    }
+
+    let mut encoded = Vec::new();
+    decoded.to_writer(&mut encoded).unwrap();
+    assert_eq!(encoded, contents);
+}
+
+/// Chunks and values that the decoder doesn't understand are kept as bytes, so
+/// they survive being encoded again too.
+#[test]
+fn to_writer_keeps_unknown_bytes() {
+    let mut unknown = ChunkBuilder::new(b"WHAT", ChunkCompression::Compressed);
+    unknown.write_all(b"mystery").unwrap();
+
+    // 0x40 isn't a valid set of faces, so these values can't be decoded.
+    let mut bad_faces = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    bad_faces.write_le_u32(0).unwrap();
+    bad_faces.write_string("Faces").unwrap();
+    bad_faces.write_u8(0x09).unwrap();
+    bad_faces.write_u8(0x40).unwrap();
+
+    let contents = build_raw_model(
+        1,
+        1,
+        vec![
+            unknown,
+            inst_chunk(0, "Handles", &[0]),
+            name_prop_chunk(0, &["Handles"]),
+            bad_faces,
+            prnt_chunk(&[(0, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    match &decoded.chunks[3] {
+        DecodedChunk::Prop {
+            values, remaining, ..
+        } => {
+            assert!(values.is_none());
+            assert_eq!(remaining, &[0x40]);
+        }
+        other => panic!("expected a PROP chunk, got {:?}", other),
+    }
+
+    let mut encoded = Vec::new();
+    decoded.to_writer(&mut encoded).unwrap();
+    assert_eq!(encoded, contents);
+}
+
+/// Bytes that the decoder doesn't understand are shown in snapshots as
+/// space-separated hex, so that a diff points at exactly which byte changed.
+#[test]
+fn unknown_buffers_are_hex() {
+    let mut unknown = ChunkBuilder::new(b"WHAT", ChunkCompression::Compressed);
+    unknown.write_all(b"\x00\x01\xfe\xff").unwrap();
+
+    let contents = build_raw_model(
+        1,
+        1,
+        vec![
+            unknown,
+            inst_chunk(0, "Folder", &[0]),
+            prnt_chunk(&[(0, -1)]),
+            end_chunk(),
+        ],
+    );
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    insta::assert_yaml_snapshot!(decoded);
+}
+
+/// Files whose chunks are compressed with ZSTD decode to the same thing as
+/// the same file compressed with LZ4. How every chunk was stored is recorded
+/// so that snapshots notice when it changes.
+#[test]
+fn zstd_matches_lz4() {
+    let tree = WeakDom::new(
+        InstanceBuilder::new("Folder")
+            .with_child(
+                InstanceBuilder::new("Part")
+                    .with_property("Size", Vector3::new(4.0, 1.0, 2.0))
+                    .with_property("Anchored", true),
+            )
+            .with_child(InstanceBuilder::new("StringValue").with_property("Value", "Hello")),
+    );
+
+    let mut lz4 = Vec::new();
+    to_writer(&mut lz4, &tree, &[tree.root_ref()]).unwrap();
+    let zstd = recompress_with_zstd(&lz4);
+
+    let mut lz4_decoded = DecodedModel::from_reader(lz4.as_slice());
+    let zstd_decoded = DecodedModel::from_reader(zstd.as_slice());
+
+    assert!(lz4_decoded
+        .chunks
+        .iter()
+        .any(|chunk| chunk.compression() == StoredCompression::Lz4));
+
+    // Apart from how they were stored, the chunks are the same.
+    for (zstd_chunk, lz4_chunk) in zstd_decoded.chunks.iter().zip(&mut lz4_decoded.chunks) {
+        if lz4_chunk.compression() == StoredCompression::Lz4 {
+            lz4_chunk.set_compression(StoredCompression::Zstd);
+        }
+        assert_eq!(zstd_chunk.compression(), lz4_chunk.compression());
+    }
+    assert_eq!(
+        format!("{:?}", zstd_decoded.chunks),
+        format!("{:?}", lz4_decoded.chunks)
+    );
+    assert_eq!(zstd_decoded.warnings, lz4_decoded.warnings);
+
+    // ZSTD chunks are written back with LZ4.
+    let mut rewritten = Vec::new();
+    zstd_decoded.to_writer(&mut rewritten).unwrap();
+    let rewritten = DecodedModel::from_reader(rewritten.as_slice());
+    for (rewritten_chunk, zstd_chunk) in rewritten.chunks.iter().zip(&zstd_decoded.chunks) {
+        let expected = match zstd_chunk.compression() {
+            StoredCompression::Zstd => StoredCompression::Lz4,
+            other => other,
+        };
+        assert_eq!(rewritten_chunk.compression(), expected);
+    }
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            DomViewer::new().view_children(&from_reader(zstd.as_slice()).unwrap())
+        ),
+        format!(
+            "{:?}",
+            DomViewer::new().view_children(&from_reader(lz4.as_slice()).unwrap())
+        )
+    );
+}
+
+/// Scripts holding compiled bytecode are found and their framing is decoded,
+/// while scripts holding source code and other string properties are left
+/// alone.
+#[test]
+fn compiled_scripts() {
+    // Bytecode version 6 with types version 3, followed by an empty string
+    // table and the rest of a compiled chunk, which we don't look at.
+    let bytecode = "\u{6}\u{3}\u{0}\u{1}\u{0}\u{0}";
+    let compile_error = "\u{0}[string \"Broken\"]:1: Expected identifier";
+
+    let tree = WeakDom::new(InstanceBuilder::new("Folder").with_children(vec![
+        InstanceBuilder::new("Script").with_property("Source", bytecode),
+        InstanceBuilder::new("ModuleScript").with_property("Source", compile_error),
+        InstanceBuilder::new("LocalScript").with_property("Source", "print('hello')"),
+        InstanceBuilder::new("StringValue").with_property("Value", bytecode),
+    ]));
+
+    let mut buffer = Vec::new();
+    to_writer(&mut buffer, &tree, &[tree.root_ref()]).unwrap();
+
+    let decoded = DecodedModel::from_reader(buffer.as_slice());
+    let mut scripts = decoded.compiled_scripts();
+    scripts.sort_by(|a, b| a.class.cmp(&b.class));
+
+    let referent_of = |class: &str| {
+        decoded
+            .chunks
+            .iter()
+            .find_map(|chunk| match chunk {
+                DecodedChunk::Inst {
+                    type_name,
+                    referents,
+                    ..
+                } if type_name == class => Some(referents[0]),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    assert_eq!(
+        scripts,
+        [
+            CompiledScript {
+                referent: referent_of("ModuleScript"),
+                class: "ModuleScript".to_owned(),
+                header: BytecodeHeader {
+                    version: 0,
+                    types_version: None,
+                    len: compile_error.len(),
+                },
+            },
+            CompiledScript {
+                referent: referent_of("Script"),
+                class: "Script".to_owned(),
+                header: BytecodeHeader {
+                    version: 6,
+                    types_version: Some(3),
+                    len: bytecode.len(),
+                },
+            },
+        ]
+    );
+}
+
+#[test]
+fn bytecode_header() {
+    assert_eq!(
+        BytecodeHeader::parse(&[3, 0, 0]),
+        Some(BytecodeHeader {
+            version: 3,
+            types_version: None,
+            len: 3,
+        })
+    );
+
+    // Version 4 and up need a types version after the bytecode version.
+    assert_eq!(BytecodeHeader::parse(&[4]), None);
+    assert_eq!(BytecodeHeader::parse(&[]), None);
+    assert_eq!(BytecodeHeader::parse(&[0]), None);
+    assert_eq!(BytecodeHeader::parse(&[7, 1]), None);
+    assert_eq!(BytecodeHeader::parse(b"print()"), None);
+}
+
+/// Two files that both number their referents from 0 can be combined once the
+/// referents of one are moved past the other's.
+#[test]
+fn merge_with_remapped_referents() {
+    fn encode_file(name: &str) -> Vec<u8> {
+        let target = InstanceBuilder::new("Part").with_name(format!("{}Target", name));
+        let value = InstanceBuilder::new("ObjectValue")
+            .with_name(format!("{}Pointer", name))
+            .with_property("Value", target.referent());
+        let dom = WeakDom::new(
+            InstanceBuilder::new("Folder")
+                .with_name(name)
+                .with_child(target)
+                .with_child(value),
+        );
+
+        let mut encoded = Vec::new();
+        to_writer(&mut encoded, &dom, &[dom.root_ref()]).unwrap();
+        encoded
+    }
+
+    let mut first = DecodedModel::from_reader(encode_file("First").as_slice());
+    let mut second = DecodedModel::from_reader(encode_file("Second").as_slice());
+    assert_eq!(first.max_referent(), Some(2));
+    assert_eq!(second.max_referent(), Some(2));
+
+    second.offset_referents(first.max_referent().unwrap() + 1);
+    assert_eq!(second.max_referent(), Some(5));
+
+    // Type IDs clash the same way referents do.
+    for chunk in &mut second.chunks {
+        match chunk {
+            DecodedChunk::Inst { type_id, .. } | DecodedChunk::Prop { type_id, .. } => {
+                *type_id += first.num_types;
+            }
+            _ => {}
+        }
+    }
+
+    first
+        .chunks
+        .retain(|chunk| !matches!(chunk, DecodedChunk::End { .. }));
+    first.chunks.append(&mut second.chunks);
+    first.num_types += second.num_types;
+    first.num_instances += second.num_instances;
+
+    let mut merged = Vec::new();
+    first.to_writer(&mut merged).unwrap();
+    let dom = from_reader(merged.as_slice()).unwrap();
+
+    let roots = dom.root().children();
+    assert_eq!(roots.len(), 2);
+
+    for (&root, name) in roots.iter().zip(["First", "Second"]) {
+        let folder = dom.get_by_ref(root).unwrap();
+        assert_eq!(folder.name, name);
+        assert_eq!(folder.children().len(), 2);
+
+        let pointer = folder
+            .children()
+            .iter()
+            .map(|&child| dom.get_by_ref(child).unwrap())
+            .find(|child| child.class == "ObjectValue")
+            .unwrap();
+        let target = match pointer.properties.get("Value") {
+            Some(Variant::Ref(target)) => dom.get_by_ref(*target).unwrap(),
+            other => panic!("expected a Ref, got {:?}", other),
+        };
+        assert_eq!(target.name, format!("{}Target", name));
+        assert_eq!(target.parent(), root);
+    }
+}
+
+#[test]
+fn remap_referents_keeps_null() {
+    let dom = WeakDom::new(InstanceBuilder::new("ObjectValue"));
+    let mut encoded = Vec::new();
+    to_writer(&mut encoded, &dom, &[dom.root_ref()]).unwrap();
+
+    let mut model = DecodedModel::from_reader(encoded.as_slice());
+    model.remap_referents(|referent| referent + 10);
+
+    for chunk in &model.chunks {
+        match chunk {
+            DecodedChunk::Inst { referents, .. } => assert_eq!(referents, &[10]),
+            DecodedChunk::Prnt { links, .. } => assert_eq!(links, &[(10, -1)]),
+            DecodedChunk::Prop {
+                values: Some(DecodedValues::Ref(referents)),
+                ..
+            } => assert_eq!(referents, &[-1]),
+            _ => {}
+        }
+    }
+}
+
+/// OptionalCFrame chunks store a CFrame for every instance and then a separate
+/// array of presence bytes, which should show up in the decoded values.
+#[test]
+fn optional_cframe_presence() {
+    let pivot = CFrame::new(Vector3::new(1.0, 2.0, 3.0), Matrix3::identity());
+    let dom = WeakDom::new(InstanceBuilder::new("Folder").with_children(vec![
+            InstanceBuilder::new("Model")
+                .with_property("WorldPivotData", Variant::OptionalCFrame(None)),
+            InstanceBuilder::new("Model")
+                .with_property("WorldPivotData", Variant::OptionalCFrame(Some(pivot))),
+            InstanceBuilder::new("Model")
+                .with_property("WorldPivotData", Variant::OptionalCFrame(None)),
+        ]));
+
+    let mut contents = Vec::new();
+    to_writer(&mut contents, &dom, &[dom.root_ref()]).unwrap();
+
+    let decoded = DecodedModel::from_reader(contents.as_slice());
+    let (present, values) = decoded
+        .chunks
+        .iter()
+        .find_map(|chunk| match chunk {
+            DecodedChunk::Prop {
+                values: Some(DecodedValues::OptionalCFrame(present, values)),
+                ..
+            } => Some((present, values)),
+            _ => None,
+        })
+        .unwrap();
+
+    assert_eq!(present, &[false, true, false]);
+    assert_eq!(
+        values,
+        &[
+            None,
+            Some(DecodedCFrame {
+                orientation_id: 0x02,
+                cframe: pivot,
+            }),
+            None,
+        ]
+    );
+
+    insta::assert_yaml_snapshot!(decoded);
+}
@@ -1,8 +1,15 @@
-use std::{fs, path::Path};
+use std::{fs, io::Write, path::Path};
 
+use flate2::{write::GzEncoder, Compression};
 use rbx_dom_weak::DomViewer;
 
-use crate::{from_reader, text_deserializer::DecodedModel, to_writer};
+use crate::{
+    chunk::{Chunk, ChunkBuilder, ChunkCompression, StoredCompression},
+    core::{RbxWriteExt, FILE_MAGIC_HEADER, FILE_SIGNATURE, FILE_VERSION},
+    from_reader,
+    text_deserializer::DecodedModel,
+    to_writer, Deserializer,
+};
 
 /// Run a basic gauntlet of tests to verify that the serializer and deserializer
 /// can handle this model correctly.
@@ -32,6 +39,16 @@ pub fn run_model_base_suite(model_path: impl AsRef<Path>) {
     let decoded_viewed = DomViewer::new().view_children(&decoded);
     insta::assert_yaml_snapshot!(format!("{}__decoded", model_stem), decoded_viewed);
 
+    // Decoding properties in parallel should give exactly the same tree.
+    let parallel = Deserializer::new()
+        .deserialize_parallel(contents.as_slice())
+        .unwrap();
+    let parallel_viewed = DomViewer::new().view_children(&parallel);
+    assert_eq!(
+        format!("{:?}", parallel_viewed),
+        format!("{:?}", decoded_viewed)
+    );
+
     // Re-encode the model that we decoded. We can't snapshot this directly...
     let decoded_root = decoded.root();
     let top_level_ids = decoded_root.children();
@@ -53,3 +70,115 @@ pub fn run_model_base_suite(model_path: impl AsRef<Path>) {
     // the same as the original decoding of the test file.
     from_reader(encoded.as_slice()).unwrap();
 }
+
+/// Assembles a binary model file by hand out of the given chunks. This is
+/// useful for testing files that the serializer would never produce.
+pub fn build_raw_model(num_types: u32, num_instances: u32, chunks: Vec<ChunkBuilder>) -> Vec<u8> {
+    let mut output = Vec::new();
+
+    output.write_all(FILE_MAGIC_HEADER).unwrap();
+    output.write_all(FILE_SIGNATURE).unwrap();
+    output.write_le_u16(FILE_VERSION).unwrap();
+    output.write_le_u32(num_types).unwrap();
+    output.write_le_u32(num_instances).unwrap();
+    output.write_all(&[0; 8]).unwrap();
+
+    for chunk in chunks {
+        chunk.dump(&mut output).unwrap();
+    }
+
+    output
+}
+
+pub fn inst_chunk(type_id: u32, type_name: &str, referents: &[i32]) -> ChunkBuilder {
+    let mut chunk = ChunkBuilder::new(b"INST", ChunkCompression::Compressed);
+    chunk.write_le_u32(type_id).unwrap();
+    chunk.write_string(type_name).unwrap();
+    chunk.write_u8(0).unwrap();
+    chunk.write_le_u32(referents.len() as u32).unwrap();
+    chunk
+        .write_referent_array(referents.iter().copied())
+        .unwrap();
+    chunk
+}
+
+pub fn name_prop_chunk(type_id: u32, names: &[&str]) -> ChunkBuilder {
+    let mut chunk = ChunkBuilder::new(b"PROP", ChunkCompression::Compressed);
+    chunk.write_le_u32(type_id).unwrap();
+    chunk.write_string("Name").unwrap();
+    chunk.write_u8(0x01).unwrap();
+    for name in names {
+        chunk.write_string(name).unwrap();
+    }
+    chunk
+}
+
+pub fn prnt_chunk(links: &[(i32, i32)]) -> ChunkBuilder {
+    let mut chunk = ChunkBuilder::new(b"PRNT", ChunkCompression::Compressed);
+    chunk.write_u8(0).unwrap();
+    chunk.write_le_u32(links.len() as u32).unwrap();
+    chunk
+        .write_referent_array(links.iter().map(|(child, _)| *child))
+        .unwrap();
+    chunk
+        .write_referent_array(links.iter().map(|(_, parent)| *parent))
+        .unwrap();
+    chunk
+}
+
+/// An INST chunk that claims to hold far more instances than it has room for.
+pub fn huge_inst_chunk() -> ChunkBuilder {
+    let mut chunk = ChunkBuilder::new(b"INST", ChunkCompression::Compressed);
+    chunk.write_le_u32(0).unwrap();
+    chunk.write_string("Folder").unwrap();
+    chunk.write_u8(0).unwrap();
+    chunk.write_le_u32(u32::MAX).unwrap();
+    chunk
+}
+
+/// A PRNT chunk that claims to hold far more links than it has room for.
+pub fn huge_prnt_chunk() -> ChunkBuilder {
+    let mut chunk = ChunkBuilder::new(b"PRNT", ChunkCompression::Compressed);
+    chunk.write_u8(0).unwrap();
+    chunk.write_le_u32(u32::MAX).unwrap();
+    chunk
+}
+
+pub fn end_chunk() -> ChunkBuilder {
+    let mut chunk = ChunkBuilder::new(b"END\0", ChunkCompression::Uncompressed);
+    chunk.write_all(b"</roblox>").unwrap();
+    chunk
+}
+
+/// Compresses `data` as a gzip stream.
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Rewrites a file so that every LZ4 compressed chunk is compressed with ZSTD
+/// instead, the way newer versions of Studio save files.
+pub fn recompress_with_zstd(file: &[u8]) -> Vec<u8> {
+    let (header, mut chunks) = file.split_at(32);
+    let mut output = header.to_vec();
+
+    while !chunks.is_empty() {
+        let chunk = Chunk::decode(&mut chunks).unwrap();
+
+        if chunk.compression == StoredCompression::Lz4 {
+            let compressed = zstd::bulk::compress(&chunk.data, 0).unwrap();
+            output.write_all(&chunk.name).unwrap();
+            output.write_le_u32(compressed.len() as u32).unwrap();
+            output.write_le_u32(chunk.data.len() as u32).unwrap();
+            output.write_le_u32(0).unwrap();
+            output.write_all(&compressed).unwrap();
+        } else {
+            let mut builder = ChunkBuilder::new(&chunk.name, ChunkCompression::Uncompressed);
+            builder.write_all(&chunk.data).unwrap();
+            builder.dump(&mut output).unwrap();
+        }
+    }
+
+    output
+}
@@ -4,177 +4,1001 @@
 
 #![allow(missing_docs)]
 
-use std::{collections::HashMap, convert::TryInto, fmt::Write, io::Read};
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    fmt::Write,
+    io::{self, Read},
+};
 
 use rbx_dom_weak::types::{
     Axes, BrickColor, CFrame, Color3, Color3uint8, ColorSequence, ColorSequenceKeypoint,
     CustomPhysicalProperties, Enum, Faces, Matrix3, NumberRange, NumberSequence,
-    NumberSequenceKeypoint, PhysicalProperties, Ray, Rect, SharedString, UDim, UDim2, Vector2,
-    Vector3, Vector3int16,
+    NumberSequenceKeypoint, PhysicalProperties, Ray, Rect, SharedString, UDim, UDim2, UniqueId,
+    Vector2, Vector3, Vector3int16,
 };
 use serde::{ser::SerializeSeq, Serialize, Serializer};
-
-use crate::{cframe, chunk::Chunk, core::RbxReadExt, deserializer::FileHeader, types::Type};
+use thiserror::Error;
+
+use crate::{
+    cframe,
+    chunk::{Chunk, ChunkBuilder, ChunkCompression, StoredCompression},
+    core::{
+        transform_unique_id, untransform_unique_id, RbxReadExt, RbxWriteExt, FILE_MAGIC_HEADER,
+        FILE_SIGNATURE, FILE_VERSION,
+    },
+    deserializer::{self, FileHeader},
+    metadata::Metadata,
+    types::Type,
+    CompressionTemplate,
+};
 
 #[derive(Debug, Serialize)]
 pub struct DecodedModel {
     pub num_types: u32,
     pub num_instances: u32,
     pub chunks: Vec<DecodedChunk>,
+
+    /// Everything questionable we noticed about the file while decoding it.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DecodeWarning>,
 }
 
 impl DecodedModel {
-    pub fn from_reader<R: Read>(mut reader: R) -> Self {
-        let header = FileHeader::decode(&mut reader).expect("invalid file header");
+    /// Decodes a file, panicking if it is malformed. Use
+    /// [`DecodedModel::try_from_reader`] for files that might be corrupt.
+    pub fn from_reader<R: Read>(reader: R) -> Self {
+        Self::try_from_reader(reader).expect("could not decode file")
+    }
+
+    /// Decodes a file, returning an error if it is malformed badly enough that
+    /// decoding can't continue.
+    pub fn try_from_reader<R: Read>(reader: R) -> Result<Self, DecodeError> {
+        Self::try_from_reader_with_counts(reader, HashMap::new())
+    }
+
+    /// Decodes a file whose `PROP` chunks may refer to types declared
+    /// somewhere else, panicking if it is malformed. Use
+    /// [`DecodedModel::try_from_reader_with_counts`] for files that might be
+    /// corrupt.
+    pub fn from_reader_with_counts<R: Read>(reader: R, counts: HashMap<u32, usize>) -> Self {
+        Self::try_from_reader_with_counts(reader, counts).expect("could not decode file")
+    }
+
+    /// Decodes a file whose `PROP` chunks may refer to types declared
+    /// somewhere else, like a file that was split up into separate streams.
+    ///
+    /// `counts` maps type IDs to the number of instances of that type, which
+    /// is what an `INST` chunk would normally provide. `INST` chunks in the
+    /// file replace the count given for their type ID.
+    pub fn try_from_reader_with_counts<R: Read>(
+        mut reader: R,
+        counts: HashMap<u32, usize>,
+    ) -> Result<Self, DecodeError> {
+        let header = FileHeader::decode(&mut reader).map_err(deserializer::Error::from)?;
         let mut chunks = Vec::new();
+        let mut warnings = Vec::new();
 
         // The number of instance with a given type ID. Used to correctly decode
         // lists of properties from the PROP chunk.
-        let mut count_by_type_id = HashMap::new();
+        let mut count_by_type_id = counts;
 
         loop {
-            let chunk = Chunk::decode(&mut reader).expect("invalid chunk");
-
-            match &chunk.name {
-                b"META" => chunks.push(decode_meta_chunk(chunk.data.as_slice())),
-                b"SSTR" => chunks.push(decode_sstr_chunk(chunk.data.as_slice())),
-                b"INST" => chunks.push(decode_inst_chunk(
-                    chunk.data.as_slice(),
-                    &mut count_by_type_id,
-                )),
-                b"PROP" => chunks.push(decode_prop_chunk(
-                    chunk.data.as_slice(),
-                    &mut count_by_type_id,
-                )),
-                b"PRNT" => chunks.push(decode_prnt_chunk(chunk.data.as_slice())),
-                b"END\0" => {
-                    chunks.push(DecodedChunk::End);
-                    break;
-                }
-                _ => {
-                    chunks.push(DecodedChunk::Unknown {
-                        name: String::from_utf8_lossy(&chunk.name[..]).to_string(),
-                        contents: chunk.data,
-                    });
+            let chunk = Chunk::decode(&mut reader)?;
+            let decoded = decode_chunk(chunk, &mut count_by_type_id, &mut warnings)?;
+            let is_end = matches!(decoded, DecodedChunk::End { .. });
+
+            chunks.push(decoded);
+
+            if is_end {
+                break;
+            }
+        }
+
+        check_counts(&header, &chunks, &mut warnings);
+        check_parents(&chunks, &mut warnings);
+
+        Ok(DecodedModel {
+            num_types: header.num_types,
+            num_instances: header.num_instances,
+            chunks,
+            warnings,
+        })
+    }
+
+    /// Encodes this model back into the binary format. Decoding the output
+    /// gives back a model equal to this one, which makes this a way to check
+    /// that the decoder doesn't lose anything.
+    ///
+    /// Chunk contents are reconstructed byte for byte, except for details that
+    /// decoding throws away: the position and rotation of empty
+    /// `OptionalCFrame` values, and the contents of the `END` chunk. Chunks are compressed the way their `compression` says,
+    /// except for `END`, which is never compressed. Chunks that were ZSTD
+    /// compressed are written with LZ4, since the serializer can't write ZSTD.
+    pub fn to_writer<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(FILE_MAGIC_HEADER)?;
+        writer.write_all(FILE_SIGNATURE)?;
+        writer.write_le_u16(FILE_VERSION)?;
+        writer.write_le_u32(self.num_types)?;
+        writer.write_le_u32(self.num_instances)?;
+        writer.write_all(&[0; 8])?;
+
+        for chunk in &self.chunks {
+            let name = chunk.name()?;
+            let compression = match (chunk, chunk.compression()) {
+                (DecodedChunk::End { .. }, _) | (_, StoredCompression::Raw) => {
+                    ChunkCompression::Uncompressed
+                }
+                (_, StoredCompression::Lz4 | StoredCompression::Zstd) => {
+                    ChunkCompression::Compressed
+                }
+            };
+
+            let mut builder = ChunkBuilder::new(&name, compression);
+            io::Write::write_all(&mut builder, &chunk.encode())?;
+            builder.dump(&mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Builds a [`CompressionTemplate`] that makes the serializer compress the
+    /// same chunks that were compressed in this file.
+    pub fn compression_template(&self) -> CompressionTemplate {
+        let mut template = CompressionTemplate::new();
+        let mut type_names = HashMap::new();
+
+        for chunk in &self.chunks {
+            let compression = match chunk.compression() {
+                StoredCompression::Raw => ChunkCompression::Uncompressed,
+                StoredCompression::Lz4 | StoredCompression::Zstd => ChunkCompression::Compressed,
+            };
+
+            match chunk {
+                DecodedChunk::Sstr { .. } => template.set_shared_strings(compression),
+                DecodedChunk::Inst {
+                    type_id, type_name, ..
+                } => {
+                    type_names.insert(*type_id, type_name.as_str());
+                    template.set_instances(type_name.as_str(), compression);
+                }
+                DecodedChunk::Prop {
+                    type_id, prop_name, ..
+                } => {
+                    if let Some(&type_name) = type_names.get(type_id) {
+                        template.set_property(type_name, prop_name.as_str(), compression);
+                    }
+                }
+                DecodedChunk::Prnt { .. } => template.set_parents(compression),
+                DecodedChunk::Meta { .. } => template.set_metadata(compression),
+                _ => {}
+            }
+        }
+
+        template
+    }
+
+    /// The largest referent used by an instance in this file, if it has any
+    /// instances.
+    pub fn max_referent(&self) -> Option<i32> {
+        self.chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                DecodedChunk::Inst { referents, .. } => referents.iter().copied().max(),
+                _ => None,
+            })
+            .max()
+    }
+
+    /// Rewrites every referent in this file with `remap`, which is called
+    /// once for each referent that appears in an `INST` chunk, a `PRNT`
+    /// chunk, or a `Ref` property. Null referents are left alone.
+    ///
+    /// `remap` should give the same result each time it's called with the
+    /// same referent, or the file's instances will no longer line up.
+    pub fn remap_referents<F: FnMut(i32) -> i32>(&mut self, mut remap: F) {
+        let mut remap_non_null = |referent: &mut i32| {
+            if *referent != -1 {
+                *referent = remap(*referent);
+            }
+        };
+
+        for chunk in &mut self.chunks {
+            match chunk {
+                DecodedChunk::Inst { referents, .. } => {
+                    referents.iter_mut().for_each(&mut remap_non_null);
+                }
+                DecodedChunk::Prnt { links, .. } => {
+                    for (subject, parent) in links {
+                        remap_non_null(subject);
+                        remap_non_null(parent);
+                    }
+                }
+                DecodedChunk::Prop {
+                    values: Some(DecodedValues::Ref(referents)),
+                    ..
+                } => {
+                    referents.iter_mut().for_each(&mut remap_non_null);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Adds `offset` to every referent in this file. This is useful for
+    /// combining files, which usually all number their referents from 0: the
+    /// referents of one file can be moved past the
+    /// [`max_referent`](Self::max_referent) of another.
+    pub fn offset_referents(&mut self, offset: i32) {
+        self.remap_referents(|referent| referent + offset)
+    }
+
+    /// Makes an educated guess about which tool produced this file. This is
+    /// useful when triaging bug reports, but it is only a heuristic; any tool
+    /// can write a file that looks like it came from somewhere else.
+    ///
+    /// The signals used, from strongest to weakest, are:
+    ///
+    /// - A `META` chunk containing the `ExplicitAutoJoints` key, or a `SIGN`
+    ///   chunk. Studio writes these and we know of no other tool that does.
+    ///   This is classified as Studio with high confidence.
+    /// - Any other `META` chunk. rbx_binary only writes one when it's given
+    ///   metadata, so this is classified as Studio with medium confidence.
+    /// - No `META` chunk, with chunks laid out exactly as rbx_binary writes
+    ///   them (`SSTR`, then every `INST`, then every `PROP`, then `PRNT`
+    ///   version 0, then `END`). If the `INST` chunks are also sorted by
+    ///   class name, this is classified as rbx_binary with medium confidence,
+    ///   otherwise with low confidence.
+    ///
+    /// Anything else is classified as [`FileOrigin::Unknown`].
+    pub fn detect_origin(&self) -> FileOrigin {
+        let mut has_meta = false;
+
+        for chunk in &self.chunks {
+            match chunk {
+                DecodedChunk::Meta { entries, .. } => {
+                    if entries.get("ExplicitAutoJoints").is_some() {
+                        return FileOrigin::Studio(OriginConfidence::High);
+                    }
+
+                    has_meta = true;
+                }
+                DecodedChunk::Sign { .. } => {
+                    return FileOrigin::Studio(OriginConfidence::High);
+                }
+                _ => {}
+            }
+        }
+
+        if has_meta {
+            return FileOrigin::Studio(OriginConfidence::Medium);
+        }
+
+        match self.rbx_binary_layout() {
+            Some(true) => FileOrigin::RbxBinary(OriginConfidence::Medium),
+            Some(false) => FileOrigin::RbxBinary(OriginConfidence::Low),
+            None => FileOrigin::Unknown,
+        }
+    }
+
+    /// Finds the scripts in this file whose `Source` property holds compiled
+    /// Luau bytecode instead of source code, which is how scripts appear in
+    /// some files that were saved by the Roblox server. Only the framing of
+    /// the bytecode is decoded; the rest is left alone.
+    pub fn compiled_scripts(&self) -> Vec<CompiledScript> {
+        let mut instances: HashMap<u32, (&str, &[i32])> = HashMap::new();
+        let mut scripts = Vec::new();
+
+        for chunk in &self.chunks {
+            match chunk {
+                DecodedChunk::Inst {
+                    type_id,
+                    type_name,
+                    referents,
+                    ..
+                } => {
+                    instances.insert(*type_id, (type_name, referents));
+                }
+                DecodedChunk::Prop {
+                    type_id,
+                    prop_name,
+                    values: Some(DecodedValues::String(values)),
+                    ..
+                } if prop_name == "Source" => {
+                    let (class, referents) = match instances.get(type_id) {
+                        Some(&instances) => instances,
+                        None => continue,
+                    };
+
+                    for (&referent, value) in referents.iter().zip(values) {
+                        let bytes = match value {
+                            RobloxString::String(value) => value.as_bytes(),
+                            RobloxString::BinaryString(value) => value.as_slice(),
+                        };
+
+                        if let Some(header) = BytecodeHeader::parse(bytes) {
+                            scripts.push(CompiledScript {
+                                referent,
+                                class: class.to_owned(),
+                                header,
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        scripts
+    }
+
+    /// Tells whether the chunks in this file are laid out in the same order
+    /// that rbx_binary's serializer writes them. If they are, also tells
+    /// whether the `INST` chunks are sorted by class name.
+    fn rbx_binary_layout(&self) -> Option<bool> {
+        let mut last_rank = 0;
+        let mut last_type_name: Option<&str> = None;
+        let mut sorted = true;
+
+        for chunk in &self.chunks {
+            let rank = match chunk {
+                DecodedChunk::Sstr { .. } => 1,
+                DecodedChunk::Inst { type_name, .. } => {
+                    if let Some(last) = last_type_name {
+                        sorted &= last <= type_name.as_str();
+                    }
+
+                    last_type_name = Some(type_name);
+                    2
                 }
+                DecodedChunk::Prop { .. } => 3,
+                DecodedChunk::Prnt { version: 0, .. } => 4,
+                DecodedChunk::End { .. } => 5,
+                _ => return None,
+            };
+
+            if rank < last_rank {
+                return None;
+            }
+
+            last_rank = rank;
+        }
+
+        if last_rank == 5 {
+            Some(sorted)
+        } else {
+            None
+        }
+    }
+}
+
+/// A decoded file whose property values are only decoded when they're first
+/// asked for. Large files often have many properties that a caller never
+/// looks at, and decoding values is most of the work of decoding a file.
+///
+/// Everything except the values of `PROP` chunks is decoded up front, so
+/// warnings about the file are available immediately.
+#[derive(Debug)]
+pub struct LazyDecodedModel {
+    pub num_types: u32,
+    pub num_instances: u32,
+    pub chunks: Vec<LazyChunk>,
+
+    /// Everything questionable we noticed about the file while decoding it.
+    pub warnings: Vec<DecodeWarning>,
+}
+
+impl LazyDecodedModel {
+    /// Decodes a file, panicking if it is malformed. Use
+    /// [`LazyDecodedModel::try_from_reader`] for files that might be corrupt.
+    pub fn from_reader<R: Read>(reader: R) -> Self {
+        Self::try_from_reader(reader).expect("could not decode file")
+    }
+
+    /// Decodes a file, returning an error if it is malformed badly enough that
+    /// decoding can't continue. Errors in property values are only returned
+    /// once those values are decoded by [`LazyProp::values`].
+    pub fn try_from_reader<R: Read>(mut reader: R) -> Result<Self, DecodeError> {
+        let header = FileHeader::decode(&mut reader).map_err(deserializer::Error::from)?;
+        let mut chunks = Vec::new();
+        let mut warnings = Vec::new();
+        let mut count_by_type_id = HashMap::new();
+
+        loop {
+            let chunk = Chunk::decode(&mut reader)?;
+
+            if &chunk.name == b"PROP" {
+                let mut payload = chunk.data.as_slice();
+                let header = decode_prop_header(&mut payload, &count_by_type_id, &mut warnings)?;
+
+                chunks.push(LazyChunk::Prop(LazyProp {
+                    compression: chunk.compression,
+                    header,
+                    payload: payload.to_vec(),
+                    decoded: OnceCell::new(),
+                }));
+                continue;
+            }
+
+            let decoded = decode_chunk(chunk, &mut count_by_type_id, &mut warnings)?;
+            let is_end = matches!(decoded, DecodedChunk::End { .. });
+
+            chunks.push(LazyChunk::Other(decoded));
+
+            if is_end {
+                break;
             }
         }
 
-        DecodedModel {
+        let others = chunks.iter().filter_map(|chunk| match chunk {
+            LazyChunk::Other(chunk) => Some(chunk),
+            LazyChunk::Prop(_) => None,
+        });
+        check_counts(&header, others.clone(), &mut warnings);
+        check_parents(others, &mut warnings);
+
+        Ok(LazyDecodedModel {
             num_types: header.num_types,
             num_instances: header.num_instances,
             chunks,
+            warnings,
+        })
+    }
+
+    /// Returns how many `PROP` chunks have had their values decoded so far.
+    pub fn decoded_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| matches!(chunk, LazyChunk::Prop(prop) if prop.decoded.get().is_some()))
+            .count()
+    }
+}
+
+/// A chunk in a [`LazyDecodedModel`].
+#[derive(Debug)]
+pub enum LazyChunk {
+    Prop(LazyProp),
+    Other(DecodedChunk),
+}
+
+impl LazyChunk {
+    /// How this chunk was stored in the file it was decoded from.
+    pub fn compression(&self) -> StoredCompression {
+        match self {
+            LazyChunk::Prop(prop) => prop.compression,
+            LazyChunk::Other(chunk) => chunk.compression(),
+        }
+    }
+}
+
+/// A `PROP` chunk whose values haven't necessarily been decoded yet.
+#[derive(Debug)]
+pub struct LazyProp {
+    compression: StoredCompression,
+    header: PropHeader,
+    payload: Vec<u8>,
+    decoded: OnceCell<(Option<DecodedValues>, Vec<u8>)>,
+}
+
+impl LazyProp {
+    pub fn type_id(&self) -> u32 {
+        self.header.type_id
+    }
+
+    pub fn prop_name(&self) -> &str {
+        &self.header.prop_name
+    }
+
+    pub fn prop_type(&self) -> &DecodedPropType {
+        &self.header.prop_type
+    }
+
+    /// How this chunk was stored in the file it was decoded from.
+    pub fn compression(&self) -> StoredCompression {
+        self.compression
+    }
+
+    /// Decodes this chunk's values the first time it's called, and returns
+    /// the same values on every call after that. Returns `None` for the same
+    /// chunks that [`DecodedChunk::Prop`] would.
+    pub fn values(&self) -> Result<Option<&DecodedValues>, DecodeError> {
+        Ok(self.decode()?.0.as_ref())
+    }
+
+    /// Returns any bytes left over after this chunk's values, decoding the
+    /// values first if they haven't been already.
+    pub fn remaining(&self) -> Result<&[u8], DecodeError> {
+        Ok(&self.decode()?.1)
+    }
+
+    fn decode(&self) -> Result<&(Option<DecodedValues>, Vec<u8>), DecodeError> {
+        if let Some(decoded) = self.decoded.get() {
+            return Ok(decoded);
+        }
+
+        let decoded = decode_prop_values(self.payload.as_slice(), &self.header)?;
+        Ok(self.decoded.get_or_init(|| decoded))
+    }
+}
+
+/// An error that stopped [`DecodedModel::try_from_reader`] from decoding a
+/// file.
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error(transparent)]
+    Header {
+        #[from]
+        source: deserializer::Error,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+
+    #[error("Invalid BrickColor value {value}")]
+    InvalidBrickColor { value: u32 },
+
+    #[error("Invalid CFrame rotation ID {id:02x}")]
+    BadRotationId { id: u8 },
+
+    #[error("{chunk_name} chunk declared {count} instances, but only has room for {max}")]
+    TooManyInstances {
+        chunk_name: &'static str,
+        count: u32,
+        max: usize,
+    },
+}
+
+/// Something questionable about a file that didn't stop us from decoding it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum DecodeWarning {
+    /// A chunk had a name that we don't recognize.
+    UnknownChunk { name: String },
+
+    /// A PROP chunk's property name was not valid UTF-8. The name is stored
+    /// lossily converted.
+    InvalidPropName { type_id: u32, prop_name: String },
+
+    /// A PROP chunk referred to a type ID that no INST chunk declared, so its
+    /// values could not be decoded.
+    UndeclaredTypeId { type_id: u32, prop_name: String },
+
+    /// A PROP chunk had a value type that we don't know how to decode.
+    UnknownPropType {
+        type_id: u32,
+        prop_name: String,
+        prop_type: u8,
+    },
+
+    /// The number of types in the file header didn't match the number of INST
+    /// chunks in the file.
+    TypeCountMismatch { header: u32, actual: u32 },
+
+    /// The number of instances in the file header didn't match the number of
+    /// referents declared in INST chunks.
+    InstanceCountMismatch { header: u32, actual: u32 },
+
+    /// A PRNT chunk referred to a referent that no INST chunk declared.
+    DanglingReferent { referent: i32 },
+
+    /// A PRNT chunk had a version that we don't know how to decode. Its
+    /// contents are stored undecoded.
+    UnknownPrntVersion { version: u8 },
+}
+
+fn check_counts<'a, I>(header: &FileHeader, chunks: I, warnings: &mut Vec<DecodeWarning>)
+where
+    I: IntoIterator<Item = &'a DecodedChunk>,
+{
+    let mut num_types = 0;
+    let mut num_instances = 0;
+
+    for chunk in chunks {
+        if let DecodedChunk::Inst { referents, .. } = chunk {
+            num_types += 1;
+            num_instances += referents.len() as u32;
+        }
+    }
+
+    if num_types != header.num_types {
+        warnings.push(DecodeWarning::TypeCountMismatch {
+            header: header.num_types,
+            actual: num_types,
+        });
+    }
+
+    if num_instances != header.num_instances {
+        warnings.push(DecodeWarning::InstanceCountMismatch {
+            header: header.num_instances,
+            actual: num_instances,
+        });
+    }
+}
+
+fn check_parents<'a, I>(chunks: I, warnings: &mut Vec<DecodeWarning>)
+where
+    I: IntoIterator<Item = &'a DecodedChunk> + Clone,
+{
+    let mut declared = HashSet::new();
+
+    for chunk in chunks.clone() {
+        if let DecodedChunk::Inst { referents, .. } = chunk {
+            declared.extend(referents.iter().copied());
+        }
+    }
+
+    for chunk in chunks {
+        if let DecodedChunk::Prnt { links, .. } = chunk {
+            for &(subject, parent) in links {
+                if !declared.contains(&subject) {
+                    warnings.push(DecodeWarning::DanglingReferent { referent: subject });
+                }
+
+                if parent != -1 && !declared.contains(&parent) {
+                    warnings.push(DecodeWarning::DanglingReferent { referent: parent });
+                }
+            }
+        }
+    }
+}
+
+/// The tool that most likely produced a file, as guessed by
+/// [`DecodedModel::detect_origin`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileOrigin {
+    /// Saved by Roblox Studio.
+    Studio(OriginConfidence),
+
+    /// Written by rbx_binary, which is used by Rojo and most other tools in
+    /// the rbx-dom ecosystem.
+    RbxBinary(OriginConfidence),
+
+    /// None of the signals we know about matched.
+    Unknown,
+}
+
+/// How strongly the signals in a file point towards a given [`FileOrigin`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OriginConfidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// A script found by [`DecodedModel::compiled_scripts`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompiledScript {
+    /// The referent of the script within the file.
+    pub referent: i32,
+    pub class: String,
+    pub header: BytecodeHeader,
+}
+
+/// The framing at the start of a compiled Luau script.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BytecodeHeader {
+    /// The bytecode version. Version 0 means that the script failed to
+    /// compile, and the rest of the payload is the error message.
+    pub version: u8,
+
+    /// The version of the type information in the bytecode, which is only
+    /// present from bytecode version 4 onwards.
+    pub types_version: Option<u8>,
+
+    /// The length of the whole payload, including this header.
+    pub len: usize,
+}
+
+impl BytecodeHeader {
+    /// Reads the framing of the given payload, returning `None` if it doesn't
+    /// look like Luau bytecode. Source code always begins with a printable
+    /// character, so it can't be mistaken for bytecode.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let (&version, rest) = bytes.split_first()?;
+
+        // These are the bytecode versions that Luau has produced so far.
+        let types_version = match version {
+            0 if !rest.is_empty() => None,
+            3 => None,
+            4..=6 => Some(*rest.first()?),
+            _ => return None,
+        };
+
+        Some(BytecodeHeader {
+            version,
+            types_version,
+            len: bytes.len(),
+        })
+    }
+}
+
+/// Decodes a chunk of any kind, recording a warning if we don't recognize
+/// it.
+fn decode_chunk(
+    chunk: Chunk,
+    count_by_type_id: &mut HashMap<u32, usize>,
+    warnings: &mut Vec<DecodeWarning>,
+) -> Result<DecodedChunk, DecodeError> {
+    let data = chunk.data.as_slice();
+    let compression = chunk.compression;
+
+    match &chunk.name {
+        b"META" => decode_meta_chunk(data, compression),
+        b"SSTR" => decode_sstr_chunk(data, compression),
+        b"INST" => decode_inst_chunk(data, compression, count_by_type_id),
+        b"PROP" => decode_prop_chunk(data, compression, count_by_type_id, warnings),
+        b"PRNT" => decode_prnt_chunk(data, compression, warnings),
+        b"SIGN" => decode_sign_chunk(data, compression),
+        b"END\0" => Ok(DecodedChunk::End { compression }),
+        _ => {
+            let name = String::from_utf8_lossy(&chunk.name[..]).to_string();
+            warnings.push(DecodeWarning::UnknownChunk { name: name.clone() });
+
+            Ok(DecodedChunk::Unknown {
+                compression,
+                name,
+                contents: chunk.data,
+            })
         }
     }
 }
 
-fn decode_meta_chunk<R: Read>(mut reader: R) -> DecodedChunk {
-    let num_entries = reader.read_le_u32().unwrap();
-    let mut entries = Vec::with_capacity(num_entries as usize);
+fn decode_meta_chunk<R: Read>(
+    mut reader: R,
+    compression: StoredCompression,
+) -> Result<DecodedChunk, DecodeError> {
+    // Counts come straight from the file, so room is made for entries as
+    // they're read instead of up front.
+    let num_entries = reader.read_le_u32()?;
+    let mut entries = Vec::new();
 
     for _ in 0..num_entries {
-        let key = reader.read_string().unwrap();
-        let value = reader.read_string().unwrap();
+        let key = reader.read_string()?;
+        let value = reader.read_string()?;
         entries.push((key, value));
     }
 
+    let entries = entries.into_iter().collect();
+
     let mut remaining = Vec::new();
-    reader.read_to_end(&mut remaining).unwrap();
+    reader.read_to_end(&mut remaining)?;
 
-    DecodedChunk::Meta { entries, remaining }
+    Ok(DecodedChunk::Meta {
+        compression,
+        entries,
+        remaining,
+    })
 }
 
-fn decode_sstr_chunk<R: Read>(mut reader: R) -> DecodedChunk {
-    let version = reader.read_le_u32().unwrap();
-    let num_entries = reader.read_le_u32().unwrap();
-    let mut entries = Vec::with_capacity(num_entries as usize);
+fn decode_sstr_chunk<R: Read>(
+    mut reader: R,
+    compression: StoredCompression,
+) -> Result<DecodedChunk, DecodeError> {
+    let version = reader.read_le_u32()?;
+    let num_entries = reader.read_le_u32()?;
+    let mut entries = Vec::new();
+    let mut hashes = Vec::new();
 
     for _ in 0..num_entries {
         let mut hash = [0; 16];
-        reader.read_exact(&mut hash).unwrap();
-        let data = reader.read_binary_string().unwrap();
+        reader.read_exact(&mut hash)?;
+        hashes.push(hash);
+
+        let data = reader.read_binary_string()?;
         entries.push(SharedString::new(data));
     }
 
     let mut remaining = Vec::new();
-    reader.read_to_end(&mut remaining).unwrap();
+    reader.read_to_end(&mut remaining)?;
 
-    DecodedChunk::Sstr {
+    Ok(DecodedChunk::Sstr {
+        compression,
         version,
         entries,
+        hashes,
         remaining,
+    })
+}
+
+/// Checks that `count` instances, each taking up `bytes_per_instance` bytes,
+/// fit in what's left of `reader`, so that a bogus count fails before room is
+/// allocated for it.
+fn check_instance_count(
+    chunk_name: &'static str,
+    count: u32,
+    reader: &[u8],
+    bytes_per_instance: usize,
+) -> Result<(), DecodeError> {
+    let max = reader.len() / bytes_per_instance;
+    if count as usize > max {
+        return Err(DecodeError::TooManyInstances {
+            chunk_name,
+            count,
+            max,
+        });
     }
+
+    Ok(())
 }
 
-fn decode_inst_chunk<R: Read>(
-    mut reader: R,
+fn decode_inst_chunk(
+    mut reader: &[u8],
+    compression: StoredCompression,
     count_by_type_id: &mut HashMap<u32, usize>,
-) -> DecodedChunk {
-    let type_id = reader.read_le_u32().unwrap();
-    let type_name = reader.read_string().unwrap();
-    let object_format = reader.read_u8().unwrap();
-    let num_instances = reader.read_le_u32().unwrap();
+) -> Result<DecodedChunk, DecodeError> {
+    let type_id = reader.read_le_u32()?;
+    let type_name = reader.read_string()?;
+    let object_format = reader.read_u8()?;
+    let num_instances = reader.read_le_u32()?;
+
+    // Each referent is four bytes.
+    check_instance_count("INST", num_instances, reader, 4)?;
 
     count_by_type_id.insert(type_id, num_instances as usize);
 
     let mut referents = vec![0; num_instances as usize];
-    reader.read_referent_array(&mut referents).unwrap();
+    reader.read_referent_array(&mut referents)?;
 
     let mut remaining = Vec::new();
-    reader.read_to_end(&mut remaining).unwrap();
+    reader.read_to_end(&mut remaining)?;
 
-    DecodedChunk::Inst {
+    Ok(DecodedChunk::Inst {
+        compression,
         type_id,
         type_name,
         object_format,
         referents,
         remaining,
-    }
+    })
 }
 
 fn decode_prop_chunk<R: Read>(
     mut reader: R,
+    compression: StoredCompression,
     count_by_type_id: &mut HashMap<u32, usize>,
-) -> DecodedChunk {
-    let type_id = reader.read_le_u32().unwrap();
-    let prop_name = reader.read_string().unwrap();
-
-    let prop_type_value = reader.read_u8().unwrap();
-    let (prop_type, values) = match prop_type_value.try_into() {
-        Ok(prop_type) => {
-            // If this type ID is unknown, we'll default to assuming that type
-            // has no members and thus has no values of this property.
-            let values = count_by_type_id
-                .get(&type_id)
-                .map(|&prop_count| DecodedValues::decode(&mut reader, prop_count, prop_type))
-                .unwrap_or(None);
-
-            (DecodedPropType::Known(prop_type), values)
+    warnings: &mut Vec<DecodeWarning>,
+) -> Result<DecodedChunk, DecodeError> {
+    let header = decode_prop_header(&mut reader, count_by_type_id, warnings)?;
+    let (values, remaining) = decode_prop_values(reader, &header)?;
+
+    Ok(DecodedChunk::Prop {
+        compression,
+        type_id: header.type_id,
+        prop_name: header.prop_name,
+        prop_type: header.prop_type,
+        values,
+        remaining,
+    })
+}
+
+/// The part of a PROP chunk that comes before its values.
+#[derive(Debug)]
+struct PropHeader {
+    type_id: u32,
+    prop_name: String,
+    prop_type: DecodedPropType,
+
+    /// The number of values in the chunk, if its type ID was declared.
+    prop_count: Option<usize>,
+}
+
+fn decode_prop_header<R: Read>(
+    mut reader: R,
+    count_by_type_id: &HashMap<u32, usize>,
+    warnings: &mut Vec<DecodeWarning>,
+) -> Result<PropHeader, DecodeError> {
+    let type_id = reader.read_le_u32()?;
+
+    // Property names should always be UTF-8, but we'd rather show something
+    // for a corrupt file than refuse to decode it.
+    let prop_name = match String::from_utf8(reader.read_binary_string()?) {
+        Ok(prop_name) => prop_name,
+        Err(err) => {
+            let prop_name = String::from_utf8_lossy(err.as_bytes()).into_owned();
+            warnings.push(DecodeWarning::InvalidPropName {
+                type_id,
+                prop_name: prop_name.clone(),
+            });
+            prop_name
         }
-        Err(_) => (DecodedPropType::Unknown(prop_type_value), None),
     };
 
-    let mut remaining = Vec::new();
-    reader.read_to_end(&mut remaining).unwrap();
+    let prop_type_value = reader.read_u8()?;
+    let prop_type = match prop_type_value.try_into() {
+        Ok(prop_type) => DecodedPropType::Known(prop_type),
+        Err(_) => {
+            warnings.push(DecodeWarning::UnknownPropType {
+                type_id,
+                prop_name: prop_name.clone(),
+                prop_type: prop_type_value,
+            });
+
+            DecodedPropType::Unknown(prop_type_value)
+        }
+    };
+
+    // If this type ID is unknown, we'll default to assuming that type has no
+    // members and thus has no values of this property.
+    let prop_count = count_by_type_id.get(&type_id).copied();
+    if prop_count.is_none() && matches!(prop_type, DecodedPropType::Known(_)) {
+        warnings.push(DecodeWarning::UndeclaredTypeId {
+            type_id,
+            prop_name: prop_name.clone(),
+        });
+    }
 
-    DecodedChunk::Prop {
+    Ok(PropHeader {
         type_id,
         prop_name,
         prop_type,
-        values,
-        remaining,
-    }
+        prop_count,
+    })
 }
 
-fn decode_prnt_chunk<R: Read>(mut reader: R) -> DecodedChunk {
-    let version = reader.read_u8().unwrap();
-    let num_referents = reader.read_le_u32().unwrap();
+/// Decodes the values of a PROP chunk, along with any bytes left over after
+/// them.
+fn decode_prop_values<R: Read>(
+    mut reader: R,
+    header: &PropHeader,
+) -> Result<(Option<DecodedValues>, Vec<u8>), DecodeError> {
+    let mut payload = Vec::new();
+    reader.read_to_end(&mut payload)?;
+
+    let mut values_reader = payload.as_slice();
+    let values = match (&header.prop_type, header.prop_count) {
+        (DecodedPropType::Known(prop_type), Some(prop_count)) => {
+            DecodedValues::decode(&mut values_reader, prop_count, *prop_type)?
+        }
+        _ => None,
+    };
+
+    // Values we couldn't represent are kept as bytes along with everything
+    // after them, so that no part of the chunk is lost.
+    let remaining = match values {
+        Some(_) => values_reader.to_vec(),
+        None => payload,
+    };
+
+    Ok((values, remaining))
+}
+
+fn decode_prnt_chunk(
+    mut reader: &[u8],
+    compression: StoredCompression,
+    warnings: &mut Vec<DecodeWarning>,
+) -> Result<DecodedChunk, DecodeError> {
+    let version = reader.read_u8()?;
+
+    // Only version 0 is known to exist. Guessing at the layout of any other
+    // version would produce links that look plausible but are wrong, so we
+    // keep its contents as-is instead.
+    if version != 0 {
+        warnings.push(DecodeWarning::UnknownPrntVersion { version });
+
+        let mut remaining = Vec::new();
+        reader.read_to_end(&mut remaining)?;
+
+        return Ok(DecodedChunk::Prnt {
+            compression,
+            version,
+            links: Vec::new(),
+            remaining,
+        });
+    }
+
+    let num_referents = reader.read_le_u32()?;
+
+    // Each link is two referents, four bytes each.
+    check_instance_count("PRNT", num_referents, reader, 8)?;
 
     let mut subjects = vec![0; num_referents as usize];
     let mut parents = vec![0; num_referents as usize];
 
-    reader.read_referent_array(&mut subjects).unwrap();
-    reader.read_referent_array(&mut parents).unwrap();
+    reader.read_referent_array(&mut subjects)?;
+    reader.read_referent_array(&mut parents)?;
 
     let links = subjects
         .iter()
@@ -183,13 +1007,54 @@ fn decode_prnt_chunk<R: Read>(mut reader: R) -> DecodedChunk {
         .collect();
 
     let mut remaining = Vec::new();
-    reader.read_to_end(&mut remaining).unwrap();
+    reader.read_to_end(&mut remaining)?;
 
-    DecodedChunk::Prnt {
+    Ok(DecodedChunk::Prnt {
+        compression,
         version,
         links,
         remaining,
+    })
+}
+
+fn decode_sign_chunk<R: Read>(
+    mut reader: R,
+    compression: StoredCompression,
+) -> Result<DecodedChunk, DecodeError> {
+    let num_signatures = reader.read_le_u32()?;
+    let mut signatures = Vec::new();
+
+    for _ in 0..num_signatures {
+        let signature_type = reader.read_le_i32()?;
+        let public_key_id = reader.read_le_i64()?;
+        let value = reader.read_binary_string()?;
+
+        signatures.push(DecodedSignature {
+            signature_type,
+            public_key_id,
+            value,
+        });
     }
+
+    let mut remaining = Vec::new();
+    reader.read_to_end(&mut remaining)?;
+
+    Ok(DecodedChunk::Sign {
+        compression,
+        signatures,
+        remaining,
+    })
+}
+
+/// One of the signatures in a SIGN chunk. We don't verify these, but knowing
+/// their structure lets snapshots show when Roblox changes it.
+#[derive(Debug, Serialize)]
+pub struct DecodedSignature {
+    pub signature_type: i32,
+    pub public_key_id: i64,
+
+    #[serde(with = "unknown_buffer")]
+    pub value: Vec<u8>,
 }
 
 #[derive(Debug, Serialize)]
@@ -209,29 +1074,42 @@ pub enum DecodedValues {
     Color3(Vec<Color3>),
     Vector2(Vec<Vector2>),
     Vector3(Vec<Vector3>),
-    CFrame(Vec<CFrame>),
+    CFrame(Vec<DecodedCFrame>),
     Enum(Vec<Enum>),
     Ref(Vec<i32>),
     Vector3int16(Vec<Vector3int16>),
     NumberSequence(Vec<NumberSequence>),
-    ColorSequence(Vec<ColorSequence>),
+    ColorSequence(Vec<DecodedColorSequence>),
     NumberRange(Vec<NumberRange>),
     Rect(Vec<Rect>),
     PhysicalProperties(Vec<PhysicalProperties>),
     Color3uint8(Vec<Color3uint8>),
     Int64(Vec<i64>),
     SharedString(Vec<u32>), // For the text deserializer, we only show the index in the shared string array.
-    OptionalCFrame(Vec<Option<CFrame>>),
+    /// Whether each instance has a value, as stored in the chunk's presence
+    /// array, and the values themselves.
+    ///
+    /// The chunk stores a CFrame for every instance, including those with no
+    /// value, followed by an array of presence bytes. The CFrames stored for
+    /// instances with no value are thrown away.
+    OptionalCFrame(Vec<bool>, Vec<Option<DecodedCFrame>>),
+    UniqueId(Vec<UniqueId>),
 }
 
 impl DecodedValues {
-    fn decode<R: Read>(mut reader: R, prop_count: usize, prop_type: Type) -> Option<Self> {
-        match prop_type {
+    /// Decodes `prop_count` values of the given type. Returns `Ok(None)` if
+    /// the values can't be represented.
+    fn decode<R: Read>(
+        mut reader: R,
+        prop_count: usize,
+        prop_type: Type,
+    ) -> Result<Option<Self>, DecodeError> {
+        let values = match prop_type {
             Type::String => {
                 let mut values = Vec::with_capacity(prop_count);
 
                 for _ in 0..prop_count {
-                    values.push(reader.read_binary_string().unwrap().into());
+                    values.push(reader.read_binary_string()?.into());
                 }
 
                 Some(DecodedValues::String(values))
@@ -240,7 +1118,7 @@ impl DecodedValues {
                 let mut values = Vec::with_capacity(prop_count);
 
                 for _ in 0..prop_count {
-                    values.push(reader.read_bool().unwrap());
+                    values.push(reader.read_bool()?);
                 }
 
                 Some(DecodedValues::Bool(values))
@@ -248,14 +1126,14 @@ impl DecodedValues {
             Type::Int32 => {
                 let mut values = vec![0; prop_count];
 
-                reader.read_interleaved_i32_array(&mut values).unwrap();
+                reader.read_interleaved_i32_array(&mut values)?;
 
                 Some(DecodedValues::Int32(values))
             }
             Type::Float32 => {
                 let mut values = vec![0.0; prop_count];
 
-                reader.read_interleaved_f32_array(&mut values).unwrap();
+                reader.read_interleaved_f32_array(&mut values)?;
 
                 Some(DecodedValues::Float32(values))
             }
@@ -263,7 +1141,7 @@ impl DecodedValues {
                 let mut values = Vec::with_capacity(prop_count);
 
                 for _ in 0..prop_count {
-                    values.push(reader.read_le_f64().unwrap());
+                    values.push(reader.read_le_f64()?);
                 }
 
                 Some(DecodedValues::Float64(values))
@@ -272,8 +1150,8 @@ impl DecodedValues {
                 let mut scale = vec![0.0; prop_count];
                 let mut offset = vec![0; prop_count];
 
-                reader.read_interleaved_f32_array(&mut scale).unwrap();
-                reader.read_interleaved_i32_array(&mut offset).unwrap();
+                reader.read_interleaved_f32_array(&mut scale)?;
+                reader.read_interleaved_i32_array(&mut offset)?;
 
                 let values = scale
                     .into_iter()
@@ -289,10 +1167,10 @@ impl DecodedValues {
                 let mut offset_x = vec![0; prop_count];
                 let mut offset_y = vec![0; prop_count];
 
-                reader.read_interleaved_f32_array(&mut scale_x).unwrap();
-                reader.read_interleaved_f32_array(&mut scale_y).unwrap();
-                reader.read_interleaved_i32_array(&mut offset_x).unwrap();
-                reader.read_interleaved_i32_array(&mut offset_y).unwrap();
+                reader.read_interleaved_f32_array(&mut scale_x)?;
+                reader.read_interleaved_f32_array(&mut scale_y)?;
+                reader.read_interleaved_i32_array(&mut offset_x)?;
+                reader.read_interleaved_i32_array(&mut offset_y)?;
 
                 let x_values = scale_x
                     .into_iter()
@@ -314,12 +1192,12 @@ impl DecodedValues {
                 let mut values = Vec::with_capacity(prop_count);
 
                 for _ in 0..prop_count {
-                    let origin_x = reader.read_le_f32().unwrap();
-                    let origin_y = reader.read_le_f32().unwrap();
-                    let origin_z = reader.read_le_f32().unwrap();
-                    let direction_x = reader.read_le_f32().unwrap();
-                    let direction_y = reader.read_le_f32().unwrap();
-                    let direction_z = reader.read_le_f32().unwrap();
+                    let origin_x = reader.read_le_f32()?;
+                    let origin_y = reader.read_le_f32()?;
+                    let origin_z = reader.read_le_f32()?;
+                    let direction_x = reader.read_le_f32()?;
+                    let direction_y = reader.read_le_f32()?;
+                    let direction_z = reader.read_le_f32()?;
 
                     values.push(Ray::new(
                         Vector3::new(origin_x, origin_y, origin_z),
@@ -333,7 +1211,10 @@ impl DecodedValues {
                 let mut values = Vec::with_capacity(prop_count);
 
                 for _ in 0..prop_count {
-                    values.push(Faces::from_bits(reader.read_u8().unwrap())?)
+                    match Faces::from_bits(reader.read_u8()?) {
+                        Some(value) => values.push(value),
+                        None => return Ok(None),
+                    }
                 }
 
                 Some(DecodedValues::Faces(values))
@@ -342,47 +1223,63 @@ impl DecodedValues {
                 let mut values = Vec::with_capacity(prop_count);
 
                 for _ in 0..prop_count {
-                    values.push(Axes::from_bits(reader.read_u8().unwrap())?)
+                    match Axes::from_bits(reader.read_u8()?) {
+                        Some(value) => values.push(value),
+                        None => return Ok(None),
+                    }
                 }
 
                 Some(DecodedValues::Axes(values))
             }
             Type::BrickColor => {
                 let mut values = vec![0; prop_count];
-                reader.read_interleaved_u32_array(&mut values).unwrap();
+                reader.read_interleaved_u32_array(&mut values)?;
 
                 let values = values
                     .into_iter()
-                    .map(|value| BrickColor::from_number(value.try_into().unwrap()).unwrap())
-                    .collect();
+                    .map(|value| {
+                        value
+                            .try_into()
+                            .ok()
+                            .and_then(BrickColor::from_number)
+                            .ok_or(DecodeError::InvalidBrickColor { value })
+                    })
+                    .collect::<Result<_, _>>()?;
 
                 Some(DecodedValues::BrickColor(values))
             }
             Type::CFrame => {
+                let mut orientation_ids = vec![0; prop_count];
                 let mut rotations = vec![Matrix3::identity(); prop_count];
 
-                for rotation in rotations.iter_mut() {
-                    let id = reader.read_u8().unwrap();
+                // Each rotation is either a compact orientation ID, or a zero
+                // followed by a full rotation matrix. Instances in the same
+                // chunk can freely mix the two.
+                for (orientation_id, rotation) in orientation_ids.iter_mut().zip(&mut rotations) {
+                    let id = reader.read_u8()?;
+                    *orientation_id = id;
+
                     if id == 0 {
                         *rotation = Matrix3::new(
                             Vector3::new(
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
                             ),
                             Vector3::new(
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
                             ),
                             Vector3::new(
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
                             ),
                         );
                     } else {
-                        *rotation = cframe::from_basic_rotation_id(id).unwrap();
+                        *rotation = cframe::from_basic_rotation_id(id)
+                            .ok_or(DecodeError::BadRotationId { id })?;
                     }
                 }
 
@@ -390,23 +1287,27 @@ impl DecodedValues {
                 let mut y = vec![0.0; prop_count];
                 let mut z = vec![0.0; prop_count];
 
-                reader.read_interleaved_f32_array(&mut x).unwrap();
-                reader.read_interleaved_f32_array(&mut y).unwrap();
-                reader.read_interleaved_f32_array(&mut z).unwrap();
+                reader.read_interleaved_f32_array(&mut x)?;
+                reader.read_interleaved_f32_array(&mut y)?;
+                reader.read_interleaved_f32_array(&mut z)?;
 
                 let values = x
                     .into_iter()
                     .zip(y)
                     .zip(z)
                     .zip(rotations)
-                    .map(|(((x, y), z), rotation)| CFrame::new(Vector3::new(x, y, z), rotation))
+                    .zip(orientation_ids)
+                    .map(|((((x, y), z), rotation), orientation_id)| DecodedCFrame {
+                        orientation_id,
+                        cframe: CFrame::new(Vector3::new(x, y, z), rotation),
+                    })
                     .collect();
 
                 Some(DecodedValues::CFrame(values))
             }
             Type::Enum => {
                 let mut ints = vec![0; prop_count];
-                reader.read_interleaved_u32_array(&mut ints).unwrap();
+                reader.read_interleaved_u32_array(&mut ints)?;
 
                 let values = ints.into_iter().map(Enum::from_u32).collect();
 
@@ -414,7 +1315,7 @@ impl DecodedValues {
             }
             Type::Ref => {
                 let mut refs = vec![0; prop_count];
-                reader.read_referent_array(&mut refs).unwrap();
+                reader.read_referent_array(&mut refs)?;
 
                 Some(DecodedValues::Ref(refs))
             }
@@ -423,9 +1324,9 @@ impl DecodedValues {
                 let mut g = vec![0.0; prop_count];
                 let mut b = vec![0.0; prop_count];
 
-                reader.read_interleaved_f32_array(&mut r).unwrap();
-                reader.read_interleaved_f32_array(&mut g).unwrap();
-                reader.read_interleaved_f32_array(&mut b).unwrap();
+                reader.read_interleaved_f32_array(&mut r)?;
+                reader.read_interleaved_f32_array(&mut g)?;
+                reader.read_interleaved_f32_array(&mut b)?;
 
                 let values = r
                     .into_iter()
@@ -440,8 +1341,8 @@ impl DecodedValues {
                 let mut x = vec![0.0; prop_count];
                 let mut y = vec![0.0; prop_count];
 
-                reader.read_interleaved_f32_array(&mut x).unwrap();
-                reader.read_interleaved_f32_array(&mut y).unwrap();
+                reader.read_interleaved_f32_array(&mut x)?;
+                reader.read_interleaved_f32_array(&mut y)?;
 
                 let values = x
                     .into_iter()
@@ -456,9 +1357,9 @@ impl DecodedValues {
                 let mut y = vec![0.0; prop_count];
                 let mut z = vec![0.0; prop_count];
 
-                reader.read_interleaved_f32_array(&mut x).unwrap();
-                reader.read_interleaved_f32_array(&mut y).unwrap();
-                reader.read_interleaved_f32_array(&mut z).unwrap();
+                reader.read_interleaved_f32_array(&mut x)?;
+                reader.read_interleaved_f32_array(&mut y)?;
+                reader.read_interleaved_f32_array(&mut z)?;
 
                 let values = x
                     .into_iter()
@@ -473,24 +1374,29 @@ impl DecodedValues {
                 let mut values = Vec::with_capacity(prop_count);
 
                 for _ in 0..prop_count {
-                    let keypoint_count = reader.read_le_u32().unwrap() as usize;
+                    let keypoint_count = reader.read_le_u32()? as usize;
                     let mut keypoints = Vec::with_capacity(keypoint_count);
+                    let mut envelopes = Vec::with_capacity(keypoint_count);
 
                     for _ in 0..keypoint_count {
                         keypoints.push(ColorSequenceKeypoint::new(
-                            reader.read_le_f32().unwrap(),
+                            reader.read_le_f32()?,
                             Color3::new(
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
                             ),
                         ));
 
-                        // envelope is serialized but doesn't do anything; don't do anything with it
-                        reader.read_le_f32().unwrap();
+                        // The envelope doesn't do anything, but we keep it so
+                        // that the value can be encoded exactly as it was.
+                        envelopes.push(reader.read_le_f32()?);
                     }
 
-                    values.push(ColorSequence { keypoints })
+                    values.push(DecodedColorSequence {
+                        sequence: ColorSequence { keypoints },
+                        envelopes,
+                    })
                 }
 
                 Some(DecodedValues::ColorSequence(values))
@@ -500,9 +1406,9 @@ impl DecodedValues {
 
                 for _ in 0..prop_count {
                     values.push(Vector3int16::new(
-                        reader.read_le_i16().unwrap(),
-                        reader.read_le_i16().unwrap(),
-                        reader.read_le_i16().unwrap(),
+                        reader.read_le_i16()?,
+                        reader.read_le_i16()?,
+                        reader.read_le_i16()?,
                     ));
                 }
 
@@ -513,8 +1419,8 @@ impl DecodedValues {
 
                 for _ in 0..prop_count {
                     values.push(NumberRange::new(
-                        reader.read_le_f32().unwrap(),
-                        reader.read_le_f32().unwrap(),
+                        reader.read_le_f32()?,
+                        reader.read_le_f32()?,
                     ));
                 }
 
@@ -524,14 +1430,14 @@ impl DecodedValues {
                 let mut values = Vec::with_capacity(prop_count);
 
                 for _ in 0..prop_count {
-                    let keypoint_count = reader.read_le_u32().unwrap();
+                    let keypoint_count = reader.read_le_u32()?;
                     let mut keypoints = Vec::with_capacity(keypoint_count as usize);
 
                     for _ in 0..keypoint_count {
                         keypoints.push(NumberSequenceKeypoint::new(
-                            reader.read_le_f32().unwrap(),
-                            reader.read_le_f32().unwrap(),
-                            reader.read_le_f32().unwrap(),
+                            reader.read_le_f32()?,
+                            reader.read_le_f32()?,
+                            reader.read_le_f32()?,
                         ))
                     }
 
@@ -546,10 +1452,10 @@ impl DecodedValues {
                 let mut x_max = vec![0.0; prop_count];
                 let mut y_max = vec![0.0; prop_count];
 
-                reader.read_interleaved_f32_array(&mut x_min).unwrap();
-                reader.read_interleaved_f32_array(&mut y_min).unwrap();
-                reader.read_interleaved_f32_array(&mut x_max).unwrap();
-                reader.read_interleaved_f32_array(&mut y_max).unwrap();
+                reader.read_interleaved_f32_array(&mut x_min)?;
+                reader.read_interleaved_f32_array(&mut y_min)?;
+                reader.read_interleaved_f32_array(&mut x_max)?;
+                reader.read_interleaved_f32_array(&mut y_max)?;
 
                 let values = x_min
                     .into_iter()
@@ -567,13 +1473,13 @@ impl DecodedValues {
                 let mut values = Vec::with_capacity(prop_count);
 
                 for _ in 0..prop_count {
-                    if reader.read_u8().unwrap() == 1 {
+                    if reader.read_u8()? == 1 {
                         values.push(PhysicalProperties::Custom(CustomPhysicalProperties {
-                            density: reader.read_le_f32().unwrap(),
-                            friction: reader.read_le_f32().unwrap(),
-                            elasticity: reader.read_le_f32().unwrap(),
-                            friction_weight: reader.read_le_f32().unwrap(),
-                            elasticity_weight: reader.read_le_f32().unwrap(),
+                            density: reader.read_le_f32()?,
+                            friction: reader.read_le_f32()?,
+                            elasticity: reader.read_le_f32()?,
+                            friction_weight: reader.read_le_f32()?,
+                            elasticity_weight: reader.read_le_f32()?,
                         }))
                     } else {
                         values.push(PhysicalProperties::Default)
@@ -587,9 +1493,9 @@ impl DecodedValues {
                 let mut g = vec![0; prop_count];
                 let mut b = vec![0; prop_count];
 
-                reader.read_exact(r.as_mut_slice()).unwrap();
-                reader.read_exact(g.as_mut_slice()).unwrap();
-                reader.read_exact(b.as_mut_slice()).unwrap();
+                reader.read_exact(r.as_mut_slice())?;
+                reader.read_exact(g.as_mut_slice())?;
+                reader.read_exact(b.as_mut_slice())?;
 
                 let values = r
                     .into_iter()
@@ -603,44 +1509,47 @@ impl DecodedValues {
             Type::Int64 => {
                 let mut values = vec![0; prop_count];
 
-                reader.read_interleaved_i64_array(&mut values).unwrap();
+                reader.read_interleaved_i64_array(&mut values)?;
 
                 Some(DecodedValues::Int64(values))
             }
             Type::SharedString => {
                 let mut values = vec![0; prop_count];
 
-                reader.read_interleaved_u32_array(&mut values).unwrap();
+                reader.read_interleaved_u32_array(&mut values)?;
 
                 Some(DecodedValues::SharedString(values))
             }
             Type::OptionalCFrame => {
                 let mut rotations = vec![Matrix3::identity(); prop_count];
+                let mut orientation_ids = vec![0; prop_count];
 
-                reader.read_u8().unwrap();
+                reader.read_u8()?;
 
-                for rotation in rotations.iter_mut() {
-                    let id = reader.read_u8().unwrap();
+                for (rotation, orientation_id) in rotations.iter_mut().zip(&mut orientation_ids) {
+                    let id = reader.read_u8()?;
+                    *orientation_id = id;
                     if id == 0 {
                         *rotation = Matrix3::new(
                             Vector3::new(
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
                             ),
                             Vector3::new(
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
                             ),
                             Vector3::new(
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
-                                reader.read_le_f32().unwrap(),
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
+                                reader.read_le_f32()?,
                             ),
                         );
                     } else {
-                        *rotation = cframe::from_basic_rotation_id(id).unwrap();
+                        *rotation = cframe::from_basic_rotation_id(id)
+                            .ok_or(DecodeError::BadRotationId { id })?;
                     }
                 }
 
@@ -648,30 +1557,299 @@ impl DecodedValues {
                 let mut y = vec![0.0; prop_count];
                 let mut z = vec![0.0; prop_count];
 
-                reader.read_interleaved_f32_array(&mut x).unwrap();
-                reader.read_interleaved_f32_array(&mut y).unwrap();
-                reader.read_interleaved_f32_array(&mut z).unwrap();
+                reader.read_interleaved_f32_array(&mut x)?;
+                reader.read_interleaved_f32_array(&mut y)?;
+                reader.read_interleaved_f32_array(&mut z)?;
 
-                reader.read_u8().unwrap();
+                reader.read_u8()?;
+
+                let mut present = Vec::with_capacity(prop_count);
+                for _ in 0..prop_count {
+                    present.push(reader.read_u8()? != 0);
+                }
 
                 let values = x
                     .into_iter()
                     .zip(y)
                     .zip(z)
                     .zip(rotations)
-                    .map(|(((x, y), z), rotation)| {
-                        if reader.read_u8().unwrap() == 0 {
-                            None
+                    .zip(orientation_ids)
+                    .zip(&present)
+                    .map(|(((((x, y), z), rotation), orientation_id), &present)| {
+                        if present {
+                            Some(DecodedCFrame {
+                                orientation_id,
+                                cframe: CFrame::new(Vector3::new(x, y, z), rotation),
+                            })
                         } else {
-                            Some(CFrame::new(Vector3::new(x, y, z), rotation))
+                            None
                         }
                     })
                     .collect();
 
-                Some(DecodedValues::OptionalCFrame(values))
+                Some(DecodedValues::OptionalCFrame(present, values))
+            }
+            Type::UniqueId => {
+                let mut values = vec![[0; 16]; prop_count];
+
+                reader.read_interleaved_bytes(&mut values)?;
+
+                Some(DecodedValues::UniqueId(
+                    values.into_iter().map(untransform_unique_id).collect(),
+                ))
+            }
+        };
+
+        Ok(values)
+    }
+
+    /// Encodes these values the same way that [`DecodedValues::decode`]
+    /// expects to read them.
+    fn encode<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        match self {
+            DecodedValues::String(values) => {
+                for value in values {
+                    match value {
+                        RobloxString::String(value) => writer.write_string(value)?,
+                        RobloxString::BinaryString(value) => writer.write_binary_string(value)?,
+                    }
+                }
+            }
+            DecodedValues::Bool(values) => {
+                for &value in values {
+                    writer.write_bool(value)?;
+                }
+            }
+            DecodedValues::Int32(values) => {
+                writer.write_interleaved_i32_array(values.iter().copied())?;
+            }
+            DecodedValues::Float32(values) => {
+                writer.write_interleaved_f32_array(values.iter().copied())?;
+            }
+            DecodedValues::Float64(values) => {
+                for &value in values {
+                    writer.write_le_f64(value)?;
+                }
+            }
+            DecodedValues::UDim(values) => {
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.scale))?;
+                writer.write_interleaved_i32_array(values.iter().map(|value| value.offset))?;
+            }
+            DecodedValues::UDim2(values) => {
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.x.scale))?;
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.y.scale))?;
+                writer.write_interleaved_i32_array(values.iter().map(|value| value.x.offset))?;
+                writer.write_interleaved_i32_array(values.iter().map(|value| value.y.offset))?;
+            }
+            DecodedValues::Ray(values) => {
+                for value in values {
+                    writer.write_le_f32(value.origin.x)?;
+                    writer.write_le_f32(value.origin.y)?;
+                    writer.write_le_f32(value.origin.z)?;
+                    writer.write_le_f32(value.direction.x)?;
+                    writer.write_le_f32(value.direction.y)?;
+                    writer.write_le_f32(value.direction.z)?;
+                }
+            }
+            DecodedValues::Faces(values) => {
+                for value in values {
+                    writer.write_u8(value.bits())?;
+                }
+            }
+            DecodedValues::Axes(values) => {
+                for value in values {
+                    writer.write_u8(value.bits())?;
+                }
+            }
+            DecodedValues::BrickColor(values) => {
+                let numbers: Vec<u32> = values.iter().map(|&value| value as u32).collect();
+                writer.write_interleaved_u32_array(&numbers)?;
+            }
+            DecodedValues::Color3(values) => {
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.r))?;
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.g))?;
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.b))?;
+            }
+            DecodedValues::Vector2(values) => {
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.x))?;
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.y))?;
+            }
+            DecodedValues::Vector3(values) => {
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.x))?;
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.y))?;
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.z))?;
+            }
+            DecodedValues::CFrame(values) => {
+                for value in values {
+                    writer.write_u8(value.orientation_id)?;
+
+                    if value.orientation_id == 0 {
+                        write_matrix3(&mut writer, &value.cframe.orientation)?;
+                    }
+                }
+
+                let positions = values.iter().map(|value| value.cframe.position);
+                writer.write_interleaved_f32_array(positions.clone().map(|position| position.x))?;
+                writer.write_interleaved_f32_array(positions.clone().map(|position| position.y))?;
+                writer.write_interleaved_f32_array(positions.map(|position| position.z))?;
+            }
+            DecodedValues::Enum(values) => {
+                let numbers: Vec<u32> = values.iter().map(|value| value.to_u32()).collect();
+                writer.write_interleaved_u32_array(&numbers)?;
+            }
+            DecodedValues::Ref(values) => {
+                writer.write_referent_array(values.iter().copied())?;
+            }
+            DecodedValues::Vector3int16(values) => {
+                for value in values {
+                    writer.write_le_i16(value.x)?;
+                    writer.write_le_i16(value.y)?;
+                    writer.write_le_i16(value.z)?;
+                }
+            }
+            DecodedValues::NumberSequence(values) => {
+                for value in values {
+                    writer.write_le_u32(value.keypoints.len() as u32)?;
+
+                    for keypoint in &value.keypoints {
+                        writer.write_le_f32(keypoint.time)?;
+                        writer.write_le_f32(keypoint.value)?;
+                        writer.write_le_f32(keypoint.envelope)?;
+                    }
+                }
+            }
+            DecodedValues::ColorSequence(values) => {
+                for value in values {
+                    writer.write_le_u32(value.sequence.keypoints.len() as u32)?;
+
+                    for (keypoint, &envelope) in
+                        value.sequence.keypoints.iter().zip(&value.envelopes)
+                    {
+                        writer.write_le_f32(keypoint.time)?;
+                        writer.write_le_f32(keypoint.color.r)?;
+                        writer.write_le_f32(keypoint.color.g)?;
+                        writer.write_le_f32(keypoint.color.b)?;
+                        writer.write_le_f32(envelope)?;
+                    }
+                }
+            }
+            DecodedValues::NumberRange(values) => {
+                for value in values {
+                    writer.write_le_f32(value.min)?;
+                    writer.write_le_f32(value.max)?;
+                }
+            }
+            DecodedValues::Rect(values) => {
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.min.x))?;
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.min.y))?;
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.max.x))?;
+                writer.write_interleaved_f32_array(values.iter().map(|value| value.max.y))?;
+            }
+            DecodedValues::PhysicalProperties(values) => {
+                for value in values {
+                    match value {
+                        PhysicalProperties::Custom(custom) => {
+                            writer.write_u8(1)?;
+                            writer.write_le_f32(custom.density)?;
+                            writer.write_le_f32(custom.friction)?;
+                            writer.write_le_f32(custom.elasticity)?;
+                            writer.write_le_f32(custom.friction_weight)?;
+                            writer.write_le_f32(custom.elasticity_weight)?;
+                        }
+                        PhysicalProperties::Default => writer.write_u8(0)?,
+                    }
+                }
+            }
+            DecodedValues::Color3uint8(values) => {
+                let r: Vec<u8> = values.iter().map(|value| value.r).collect();
+                let g: Vec<u8> = values.iter().map(|value| value.g).collect();
+                let b: Vec<u8> = values.iter().map(|value| value.b).collect();
+
+                writer.write_all(&r)?;
+                writer.write_all(&g)?;
+                writer.write_all(&b)?;
+            }
+            DecodedValues::Int64(values) => {
+                writer.write_interleaved_i64_array(values.iter().copied())?;
+            }
+            DecodedValues::SharedString(values) => {
+                writer.write_interleaved_u32_array(values)?;
+            }
+            DecodedValues::OptionalCFrame(present, values) => {
+                // Empty values don't keep the position and rotation they were
+                // stored with, so we write the identity CFrame in their place.
+                let identity = DecodedCFrame {
+                    orientation_id: cframe::to_basic_rotation_id(Matrix3::identity()).unwrap(),
+                    cframe: CFrame::new(Vector3::new(0.0, 0.0, 0.0), Matrix3::identity()),
+                };
+                let values: Vec<&DecodedCFrame> = values
+                    .iter()
+                    .map(|value| value.as_ref().unwrap_or(&identity))
+                    .collect();
+
+                writer.write_u8(Type::CFrame as u8)?;
+
+                for value in &values {
+                    writer.write_u8(value.orientation_id)?;
+
+                    if value.orientation_id == 0 {
+                        write_matrix3(&mut writer, &value.cframe.orientation)?;
+                    }
+                }
+
+                let cframes: Vec<CFrame> = values.iter().map(|value| value.cframe).collect();
+
+                writer.write_interleaved_f32_array(cframes.iter().map(|c| c.position.x))?;
+                writer.write_interleaved_f32_array(cframes.iter().map(|c| c.position.y))?;
+                writer.write_interleaved_f32_array(cframes.iter().map(|c| c.position.z))?;
+
+                writer.write_u8(Type::Bool as u8)?;
+
+                for &present in present {
+                    writer.write_bool(present)?;
+                }
+            }
+            DecodedValues::UniqueId(values) => {
+                let values: Vec<[u8; 16]> =
+                    values.iter().copied().map(transform_unique_id).collect();
+
+                writer.write_interleaved_bytes(&values)?;
             }
         }
+
+        Ok(())
+    }
+}
+
+fn write_matrix3<W: io::Write>(mut writer: W, matrix: &Matrix3) -> io::Result<()> {
+    for row in &[matrix.x, matrix.y, matrix.z] {
+        writer.write_le_f32(row.x)?;
+        writer.write_le_f32(row.y)?;
+        writer.write_le_f32(row.z)?;
     }
+
+    Ok(())
+}
+
+/// A CFrame along with the orientation ID that its rotation was stored with.
+/// An ID of zero means that the full rotation matrix was stored.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DecodedCFrame {
+    pub orientation_id: u8,
+
+    #[serde(flatten)]
+    pub cframe: CFrame,
+}
+
+/// A ColorSequence along with the envelope stored for each of its keypoints.
+/// Roblox doesn't use the envelopes, but they're kept so that the value can be
+/// encoded again exactly.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DecodedColorSequence {
+    #[serde(flatten)]
+    pub sequence: ColorSequence,
+
+    pub envelopes: Vec<f32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -699,25 +1877,35 @@ impl From<Vec<u8>> for RobloxString {
     }
 }
 
+/// One chunk of a file. Every chunk records how it was stored in the file in
+/// its `compression` field.
 #[derive(Debug, Serialize)]
 pub enum DecodedChunk {
     Meta {
-        entries: Vec<(String, String)>,
+        compression: StoredCompression,
+        entries: Metadata,
 
         #[serde(with = "unknown_buffer", skip_serializing_if = "Vec::is_empty")]
         remaining: Vec<u8>,
     },
 
     Sstr {
+        compression: StoredCompression,
         version: u32,
         #[serde(serialize_with = "shared_string_serializer")]
         entries: Vec<SharedString>,
 
+        /// The hash stored in the file next to each entry. rbx_binary writes
+        /// zeroes here, so these are kept out of snapshots.
+        #[serde(skip)]
+        hashes: Vec<[u8; 16]>,
+
         #[serde(with = "unknown_buffer", skip_serializing_if = "Vec::is_empty")]
         remaining: Vec<u8>,
     },
 
     Inst {
+        compression: StoredCompression,
         type_id: u32,
         type_name: String,
         object_format: u8,
@@ -728,6 +1916,7 @@ pub enum DecodedChunk {
     },
 
     Prop {
+        compression: StoredCompression,
         type_id: u32,
         prop_name: String,
         prop_type: DecodedPropType,
@@ -740,6 +1929,7 @@ pub enum DecodedChunk {
     },
 
     Prnt {
+        compression: StoredCompression,
         version: u8,
         links: Vec<(i32, i32)>,
 
@@ -747,9 +1937,20 @@ pub enum DecodedChunk {
         remaining: Vec<u8>,
     },
 
-    End,
+    Sign {
+        compression: StoredCompression,
+        signatures: Vec<DecodedSignature>,
+
+        #[serde(with = "unknown_buffer", skip_serializing_if = "Vec::is_empty")]
+        remaining: Vec<u8>,
+    },
+
+    End {
+        compression: StoredCompression,
+    },
 
     Unknown {
+        compression: StoredCompression,
         name: String,
 
         #[serde(with = "unknown_buffer")]
@@ -757,6 +1958,178 @@ pub enum DecodedChunk {
     },
 }
 
+impl DecodedChunk {
+    /// How this chunk was stored in the file it was decoded from.
+    pub fn compression(&self) -> StoredCompression {
+        match self {
+            DecodedChunk::Meta { compression, .. }
+            | DecodedChunk::Sstr { compression, .. }
+            | DecodedChunk::Inst { compression, .. }
+            | DecodedChunk::Prop { compression, .. }
+            | DecodedChunk::Prnt { compression, .. }
+            | DecodedChunk::Sign { compression, .. }
+            | DecodedChunk::End { compression }
+            | DecodedChunk::Unknown { compression, .. } => *compression,
+        }
+    }
+
+    /// Changes how this chunk will be stored when it's written with
+    /// [`DecodedModel::to_writer`].
+    pub fn set_compression(&mut self, new_compression: StoredCompression) {
+        match self {
+            DecodedChunk::Meta { compression, .. }
+            | DecodedChunk::Sstr { compression, .. }
+            | DecodedChunk::Inst { compression, .. }
+            | DecodedChunk::Prop { compression, .. }
+            | DecodedChunk::Prnt { compression, .. }
+            | DecodedChunk::Sign { compression, .. }
+            | DecodedChunk::End { compression }
+            | DecodedChunk::Unknown { compression, .. } => *compression = new_compression,
+        }
+    }
+
+    /// The four byte name that this chunk is stored under.
+    fn name(&self) -> io::Result<[u8; 4]> {
+        let name: &[u8] = match self {
+            DecodedChunk::Meta { .. } => b"META",
+            DecodedChunk::Sstr { .. } => b"SSTR",
+            DecodedChunk::Inst { .. } => b"INST",
+            DecodedChunk::Prop { .. } => b"PROP",
+            DecodedChunk::Prnt { .. } => b"PRNT",
+            DecodedChunk::Sign { .. } => b"SIGN",
+            DecodedChunk::End { .. } => b"END\0",
+            DecodedChunk::Unknown { name, .. } => name.as_bytes(),
+        };
+
+        name.try_into().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Chunk name {:?} is not four bytes long",
+                    String::from_utf8_lossy(name)
+                ),
+            )
+        })
+    }
+
+    /// Encodes this chunk's contents back into the binary format, including
+    /// any trailing bytes we didn't understand when decoding it.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+
+        match self {
+            DecodedChunk::Meta {
+                entries, remaining, ..
+            } => {
+                output.write_le_u32(entries.len() as u32).unwrap();
+                for (key, value) in entries.iter() {
+                    output.write_string(key).unwrap();
+                    output.write_string(value).unwrap();
+                }
+
+                output.extend_from_slice(remaining);
+            }
+            DecodedChunk::Sstr {
+                version,
+                entries,
+                hashes,
+                remaining,
+                ..
+            } => {
+                output.write_le_u32(*version).unwrap();
+                output.write_le_u32(entries.len() as u32).unwrap();
+                for (entry, hash) in entries.iter().zip(hashes) {
+                    output.extend_from_slice(hash);
+                    output.write_binary_string(entry.data()).unwrap();
+                }
+
+                output.extend_from_slice(remaining);
+            }
+            DecodedChunk::Inst {
+                type_id,
+                type_name,
+                object_format,
+                referents,
+                remaining,
+                ..
+            } => {
+                output.write_le_u32(*type_id).unwrap();
+                output.write_string(type_name).unwrap();
+                output.write_u8(*object_format).unwrap();
+                output.write_le_u32(referents.len() as u32).unwrap();
+                output
+                    .write_referent_array(referents.iter().copied())
+                    .unwrap();
+
+                output.extend_from_slice(remaining);
+            }
+            DecodedChunk::Prop {
+                type_id,
+                prop_name,
+                prop_type,
+                values,
+                remaining,
+                ..
+            } => {
+                output.write_le_u32(*type_id).unwrap();
+                output.write_string(prop_name).unwrap();
+                output
+                    .write_u8(match prop_type {
+                        DecodedPropType::Known(prop_type) => *prop_type as u8,
+                        DecodedPropType::Unknown(prop_type) => *prop_type,
+                    })
+                    .unwrap();
+
+                if let Some(values) = values {
+                    values.encode(&mut output).unwrap();
+                }
+
+                output.extend_from_slice(remaining);
+            }
+            DecodedChunk::Prnt {
+                version,
+                links,
+                remaining,
+                ..
+            } => {
+                output.write_u8(*version).unwrap();
+
+                // Chunks with versions we don't know have no links; all of
+                // their contents are in `remaining`.
+                if *version == 0 {
+                    output.write_le_u32(links.len() as u32).unwrap();
+                    output
+                        .write_referent_array(links.iter().map(|&(subject, _)| subject))
+                        .unwrap();
+                    output
+                        .write_referent_array(links.iter().map(|&(_, parent)| parent))
+                        .unwrap();
+                }
+
+                output.extend_from_slice(remaining);
+            }
+            DecodedChunk::Sign {
+                signatures,
+                remaining,
+                ..
+            } => {
+                output.write_le_u32(signatures.len() as u32).unwrap();
+                for signature in signatures {
+                    output.write_le_i32(signature.signature_type).unwrap();
+                    output.write_le_i64(signature.public_key_id).unwrap();
+                    output.write_binary_string(&signature.value).unwrap();
+                }
+
+                output.extend_from_slice(remaining);
+            }
+            DecodedChunk::End { .. } => output.extend_from_slice(b"</roblox>"),
+            DecodedChunk::Unknown { contents, .. } => output.extend_from_slice(contents),
+        }
+
+        output
+    }
+}
+
 #[derive(Serialize)]
 struct SerializedSharedString<'a> {
     len: usize,
@@ -39,6 +39,7 @@ pub enum Type {
     Int64 = 0x1B,
     SharedString = 0x1C,
     OptionalCFrame = 0x1E,
+    UniqueId = 0x1F,
 }
 
 impl Type {
@@ -48,6 +49,7 @@ impl Type {
             VariantType::String => Type::String,
             VariantType::BinaryString => Type::String,
             VariantType::Content => Type::String,
+            VariantType::ProtectedString => Type::String,
             VariantType::Tags => Type::String,
 
             VariantType::Bool => Type::Bool,
@@ -76,6 +78,7 @@ impl Type {
             VariantType::Int64 => Type::Int64,
             VariantType::SharedString => Type::SharedString,
             VariantType::OptionalCFrame => Type::OptionalCFrame,
+            VariantType::UniqueId => Type::UniqueId,
             _ => return None,
         })
     }
@@ -111,6 +114,7 @@ impl Type {
             Type::Int64 => VariantType::Int64,
             Type::SharedString => VariantType::SharedString,
             Type::OptionalCFrame => VariantType::OptionalCFrame,
+            Type::UniqueId => VariantType::UniqueId,
         })
     }
 }
@@ -149,6 +153,7 @@ impl TryFrom<u8> for Type {
             0x1B => Int64,
             0x1C => SharedString,
             0x1E => OptionalCFrame,
+            0x1F => UniqueId,
             _ => return Err(InvalidTypeError(value)),
         })
     }
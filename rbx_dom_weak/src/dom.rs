@@ -1,8 +1,15 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 
-use rbx_types::Ref;
+use rbx_reflection::ReflectionDatabase;
+use rbx_types::{AssetId, CFrame, Ref, UniqueId, Variant, VariantKey};
 
-use crate::instance::{Instance, InstanceBuilder};
+use crate::{
+    dump::{self, DumpOptions},
+    instance::{Instance, InstanceBuilder},
+};
+
+#[cfg(feature = "json")]
+use crate::json::{self, JsonOptions};
 
 /// Represents a DOM containing one or more Roblox instances.
 ///
@@ -215,7 +222,7 @@ impl WeakDom {
             panic!("cannot transfer the root instance of WeakDom");
         }
 
-        let mut instance = self
+        let instance = self
             .instances
             .get_mut(&referent)
             .unwrap_or_else(|| panic!("cannot move an instance that does not exist"));
@@ -235,12 +242,322 @@ impl WeakDom {
             .unwrap_or_else(|| panic!("cannot move into an instance that does not exist"));
         dest_parent.children.push(referent);
     }
+
+    /// Returns the name of every class used by an instance in the `WeakDom`,
+    /// including the root instance. Each class name appears once.
+    pub fn used_classes(&self) -> BTreeSet<String> {
+        self.instances
+            .values()
+            .map(|instance| instance.class.clone())
+            .collect()
+    }
+
+    /// Counts how many instances of the given class have each value of the
+    /// given property. Instances that don't have the property set are not
+    /// counted.
+    ///
+    /// Only instances whose class is exactly `class` are considered; the
+    /// `WeakDom` has no knowledge of class inheritance.
+    pub fn property_histogram(&self, class: &str, property: &str) -> HashMap<VariantKey, usize> {
+        let mut histogram = HashMap::new();
+
+        for instance in self.instances.values() {
+            if instance.class != class {
+                continue;
+            }
+
+            if let Some(value) = instance.properties.get(property) {
+                *histogram.entry(VariantKey::new(value.clone())).or_insert(0) += 1;
+            }
+        }
+
+        histogram
+    }
+
+    /// Renders the `WeakDom` as indented text, with one line per instance
+    /// showing its name and class. Useful for debugging.
+    pub fn dump_tree(&self, options: DumpOptions) -> String {
+        dump::dump_tree(self, &options)
+    }
+
+    /// Converts the `WeakDom` into a nested JSON tree for use by tools outside
+    /// of Rust.
+    ///
+    /// Each instance becomes an object with `class`, `name`, `properties`, and
+    /// `children` fields. Properties are listed under their canonical names
+    /// and use simple JSON values where possible: vectors become arrays of
+    /// numbers, colors become `"#rrggbb"` strings, and enums become the names
+    /// of their items. Other values use their serde representation.
+    ///
+    /// Requires the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn to_json(
+        &self,
+        database: &ReflectionDatabase,
+        options: JsonOptions,
+    ) -> serde_json::Value {
+        json::to_json(self, database, &options)
+    }
+
+    /// Finds every instance whose class isn't allowed to be parented to its
+    /// parent's class, according to [`ReflectionDatabase::can_parent`].
+    ///
+    /// Each entry holds the child's referent, the child's class, and the
+    /// parent's class. Instances are listed in breadth-first order starting
+    /// from the root.
+    pub fn invalid_parentings(&self, database: &ReflectionDatabase) -> Vec<(Ref, String, String)> {
+        let mut invalid = Vec::new();
+        let mut to_visit = VecDeque::new();
+        to_visit.push_back(self.root_ref);
+
+        while let Some(referent) = to_visit.pop_front() {
+            let instance = &self.instances[&referent];
+
+            for &child_ref in &instance.children {
+                let child = &self.instances[&child_ref];
+
+                if !database.can_parent(&child.class, &instance.class) {
+                    invalid.push((child_ref, child.class.clone(), instance.class.clone()));
+                }
+
+                to_visit.push_back(child_ref);
+            }
+        }
+
+        invalid
+    }
+
+    /// Finds every property whose value differs from its default value in
+    /// `database`, which is what a model actually customizes.
+    ///
+    /// Values are compared exactly, matching how Studio decides which
+    /// properties to omit, so a float that is only nearly equal to its default
+    /// is reported. Properties that have no known default value are always
+    /// reported.
+    ///
+    /// Instances are listed in breadth-first order starting from the root, and
+    /// each instance's properties are listed in order of their names.
+    pub fn non_default_properties<'a>(
+        &'a self,
+        database: &ReflectionDatabase,
+    ) -> Vec<(Ref, &'a str, &'a Variant)> {
+        let mut found = Vec::new();
+        let mut to_visit = VecDeque::new();
+        to_visit.push_back(self.root_ref);
+
+        while let Some(referent) = to_visit.pop_front() {
+            let instance = &self.instances[&referent];
+
+            let mut properties: Vec<_> = instance
+                .properties
+                .iter()
+                .filter(|(name, value)| {
+                    database.find_default_property(&instance.class, name) != Some(*value)
+                })
+                .map(|(name, value)| (referent, name.as_str(), value))
+                .collect();
+            properties.sort_by_key(|&(_, name, _)| name);
+
+            found.extend(properties);
+            to_visit.extend(instance.children.iter().copied());
+        }
+
+        found
+    }
+
+    /// Finds every property that is set on an instance but is deprecated
+    /// according to `database`, like `BasePart.brickColor` or
+    /// `BasePart.Velocity`. Properties that aren't in the database are
+    /// ignored.
+    ///
+    /// Instances are listed in breadth-first order starting from the root, and
+    /// each instance's properties are listed in order of their names.
+    pub fn deprecated_properties_used<'a>(
+        &'a self,
+        database: &ReflectionDatabase,
+    ) -> Vec<(Ref, &'a str)> {
+        let mut found = Vec::new();
+        let mut to_visit = VecDeque::new();
+        to_visit.push_back(self.root_ref);
+
+        while let Some(referent) = to_visit.pop_front() {
+            let instance = &self.instances[&referent];
+
+            let mut properties: Vec<_> = instance
+                .properties
+                .keys()
+                .filter(|name| {
+                    matches!(
+                        database.find_property(&instance.class, name),
+                        Some(descriptor) if descriptor.is_deprecated()
+                    )
+                })
+                .map(|name| (referent, name.as_str()))
+                .collect();
+            properties.sort_by_key(|&(_, name)| name);
+
+            found.extend(properties);
+            to_visit.extend(instance.children.iter().copied());
+        }
+
+        found
+    }
+
+    /// Returns the IDs of assets that are referenced by `Content` properties on
+    /// the given instances or their descendants, but nowhere else in the
+    /// `WeakDom`. These are the assets that would no longer be used if those
+    /// instances were destroyed.
+    ///
+    /// Referents that don't refer to an instance in the `WeakDom` are ignored.
+    pub fn assets_uniquely_referenced_by(&self, refs: &[Ref]) -> HashSet<AssetId> {
+        let mut removed = HashSet::new();
+        let mut to_visit: VecDeque<Ref> = refs.iter().copied().collect();
+
+        while let Some(referent) = to_visit.pop_front() {
+            if let Some(instance) = self.instances.get(&referent) {
+                if removed.insert(referent) {
+                    to_visit.extend(instance.children.iter().copied());
+                }
+            }
+        }
+
+        let mut assets = HashSet::new();
+        let mut kept_assets = HashSet::new();
+
+        for (referent, instance) in &self.instances {
+            let set = if removed.contains(referent) {
+                &mut assets
+            } else {
+                &mut kept_assets
+            };
+
+            for value in instance.properties.values() {
+                if let Variant::Content(content) = value {
+                    set.extend(content.asset_id());
+                }
+            }
+        }
+
+        assets.retain(|asset| !kept_assets.contains(asset));
+        assets
+    }
+
+    /// Finds `UniqueId` values that are shared by more than one instance in
+    /// the `WeakDom`. Roblox gives every instance its own `UniqueId`, so a
+    /// shared one usually means that the place file has been corrupted or
+    /// that instances were copied without care.
+    ///
+    /// The nil `UniqueId` is never reported. The referents for each
+    /// `UniqueId` are in no particular order.
+    pub fn duplicate_unique_ids(&self) -> HashMap<UniqueId, Vec<Ref>> {
+        let mut by_id: HashMap<UniqueId, Vec<Ref>> = HashMap::new();
+
+        for (referent, instance) in &self.instances {
+            if let Some(Variant::UniqueId(id)) = instance.properties.get("UniqueId") {
+                if !id.is_nil() {
+                    by_id.entry(*id).or_default().push(*referent);
+                }
+            }
+        }
+
+        by_id.retain(|_, referents| referents.len() > 1);
+        by_id
+    }
+
+    /// Renames every instance whose name matches `predicate` to the name
+    /// returned by `rename`, which is given the instance's current name.
+    ///
+    /// Returns how many instances had their name changed. Instances that
+    /// match but are given back the same name aren't counted.
+    pub fn rename_matching<P, F>(&mut self, predicate: P, rename: F) -> usize
+    where
+        P: Fn(&str) -> bool,
+        F: Fn(&str) -> String,
+    {
+        let mut renamed = 0;
+
+        for instance in self.instances.values_mut() {
+            if predicate(&instance.name) {
+                let new_name = rename(&instance.name);
+
+                if new_name != instance.name {
+                    instance.name = new_name;
+                    renamed += 1;
+                }
+            }
+        }
+
+        renamed
+    }
+
+    /// Removes every `Model` instance in the `WeakDom`, moving each one's
+    /// children into the Model's parent at the position the Model occupied.
+    /// Returns the number of Models that were removed.
+    ///
+    /// The root instance is never removed, even if it is a Model.
+    ///
+    /// `CFrame` properties are already in world space, so flattening never
+    /// moves anything. If `keep_pivot` is true, each child with a `CFrame`
+    /// property also has its `PivotOffset` set so that its pivot lands where
+    /// the Model's `WorldPivotData` was. Models without a pivot leave their
+    /// children's pivots alone.
+    pub fn flatten_models(&mut self, keep_pivot: bool) -> usize {
+        let mut models = Vec::new();
+        let mut to_visit = VecDeque::new();
+        to_visit.push_back(self.root_ref);
+
+        // Visiting Models from the top down means that nested Models are
+        // moved before they themselves are flattened.
+        while let Some(referent) = to_visit.pop_front() {
+            let instance = &self.instances[&referent];
+            to_visit.extend(instance.children.iter().copied());
+
+            if referent != self.root_ref && instance.class == "Model" {
+                models.push(referent);
+            }
+        }
+
+        for &model_ref in &models {
+            let model = self.instances.remove(&model_ref).unwrap();
+
+            let pivot = match model.properties.get("WorldPivotData") {
+                Some(Variant::OptionalCFrame(Some(pivot))) if keep_pivot => Some(*pivot),
+                _ => None,
+            };
+
+            for child_ref in &model.children {
+                let child = self.instances.get_mut(child_ref).unwrap();
+                child.parent = model.parent;
+
+                if let (Some(pivot), Some(Variant::CFrame(cframe))) =
+                    (pivot, child.properties.get("CFrame"))
+                {
+                    let offset: CFrame = cframe.inverse() * pivot;
+                    child
+                        .properties
+                        .insert("PivotOffset".to_owned(), offset.into());
+                }
+            }
+
+            let parent = self.instances.get_mut(&model.parent).unwrap();
+            let index = parent
+                .children
+                .iter()
+                .position(|&child| child == model_ref)
+                .unwrap();
+            parent.children.splice(index..=index, model.children);
+        }
+
+        models.len()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    use rbx_types::{BrickColor, Content, Enum, Matrix3, Vector3};
+
     use crate::DomViewer;
 
     #[test]
@@ -299,4 +616,332 @@ mod test {
         // This snapshot should have Root and SpawnLocation contained in Dest.
         insta::assert_yaml_snapshot!(viewer.view_children(&dom));
     }
+
+    #[test]
+    fn used_classes() {
+        let dom = WeakDom::new(
+            InstanceBuilder::new("DataModel")
+                .with_child(
+                    InstanceBuilder::new("Folder")
+                        .with_child(InstanceBuilder::new("Part"))
+                        .with_child(
+                            InstanceBuilder::new("Model")
+                                .with_child(InstanceBuilder::new("Part"))
+                                .with_child(InstanceBuilder::new("Folder")),
+                        ),
+                )
+                .with_child(InstanceBuilder::new("StringValue")),
+        );
+
+        let expected: BTreeSet<String> = ["DataModel", "Folder", "Model", "Part", "StringValue"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        assert_eq!(dom.used_classes(), expected);
+    }
+
+    #[test]
+    fn property_histogram() {
+        let plastic = Variant::Enum(Enum::from_u32(256));
+        let metal = Variant::Enum(Enum::from_u32(1088));
+
+        let dom = WeakDom::new(
+            InstanceBuilder::new("Folder")
+                .with_child(InstanceBuilder::new("Part").with_property("Material", plastic.clone()))
+                .with_child(InstanceBuilder::new("Part").with_property("Material", metal.clone()))
+                .with_child(InstanceBuilder::new("Part").with_property("Material", plastic.clone()))
+                .with_child(InstanceBuilder::new("Folder").with_child(
+                    InstanceBuilder::new("Part").with_property("Material", plastic.clone()),
+                ))
+                .with_child(InstanceBuilder::new("Part"))
+                .with_child(
+                    InstanceBuilder::new("WedgePart").with_property("Material", metal.clone()),
+                ),
+        );
+
+        let histogram = dom.property_histogram("Part", "Material");
+
+        assert_eq!(histogram.len(), 2);
+        assert_eq!(histogram[&VariantKey::new(plastic)], 3);
+        assert_eq!(histogram[&VariantKey::new(metal)], 1);
+    }
+
+    #[test]
+    fn flatten_models() {
+        let rotated = Matrix3::new(
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(-1.0, 0.0, 0.0),
+        );
+        let pivot = CFrame::new(Vector3::new(10.0, 0.0, 0.0), Matrix3::identity());
+        let first_cframe = CFrame::new(Vector3::new(1.0, 2.0, 3.0), Matrix3::identity());
+        let second_cframe = CFrame::new(Vector3::new(-4.0, 5.0, 6.0), rotated);
+
+        let first = InstanceBuilder::new("Part").with_property("CFrame", first_cframe);
+        let second = InstanceBuilder::new("Part").with_property("CFrame", second_cframe);
+        let after = InstanceBuilder::new("Folder");
+        let (first_ref, second_ref, after_ref) = (first.referent, second.referent, after.referent);
+
+        let mut dom = WeakDom::new(
+            InstanceBuilder::new("Folder")
+                .with_child(
+                    InstanceBuilder::new("Model")
+                        .with_property("WorldPivotData", Some(pivot))
+                        .with_child(first)
+                        .with_child(second),
+                )
+                .with_child(after),
+        );
+
+        assert_eq!(dom.flatten_models(true), 1);
+        assert_eq!(dom.root().children(), &[first_ref, second_ref, after_ref]);
+
+        for (referent, cframe) in &[(first_ref, first_cframe), (second_ref, second_cframe)] {
+            let part = dom.get_by_ref(*referent).unwrap();
+            assert_eq!(part.parent(), dom.root_ref());
+            assert_eq!(part.properties["CFrame"], Variant::CFrame(*cframe));
+
+            match part.properties["PivotOffset"] {
+                Variant::CFrame(offset) => assert_eq!(*cframe * offset, pivot),
+                ref other => panic!("expected PivotOffset to be a CFrame, got {:?}", other),
+            }
+        }
+
+        assert_eq!(dom.flatten_models(true), 0);
+    }
+
+    #[test]
+    fn assets_uniquely_referenced_by() {
+        let mesh =
+            |id: &str| InstanceBuilder::new("MeshPart").with_property("MeshId", Content::from(id));
+
+        let doomed = InstanceBuilder::new("Model")
+            .with_child(mesh("rbxassetid://1"))
+            .with_child(mesh("rbxassetid://2"));
+        let doomed_ref = doomed.referent;
+
+        let dom = WeakDom::new(
+            InstanceBuilder::new("Folder")
+                .with_child(doomed)
+                .with_child(mesh("http://www.roblox.com/asset/?id=2"))
+                .with_child(mesh("rbxassetid://3")),
+        );
+
+        let expected: HashSet<_> = vec![AssetId(1)].into_iter().collect();
+        assert_eq!(dom.assets_uniquely_referenced_by(&[doomed_ref]), expected);
+        assert!(dom.assets_uniquely_referenced_by(&[]).is_empty());
+    }
+
+    #[test]
+    fn dump_tree() {
+        let dom = WeakDom::new(
+            InstanceBuilder::new("Folder")
+                .with_name("Root")
+                .with_child(
+                    InstanceBuilder::new("Model")
+                        .with_name("Car")
+                        .with_child(
+                            InstanceBuilder::new("Part")
+                                .with_name("Wheel")
+                                .with_property("Anchored", true),
+                        )
+                        .with_child(InstanceBuilder::new("Folder").with_child(
+                            InstanceBuilder::new("StringValue").with_property("Value", "deep"),
+                        )),
+                )
+                .with_child(InstanceBuilder::new("StringValue").with_property("Value", "hi")),
+        );
+
+        let options = DumpOptions::new()
+            .with_max_depth(2)
+            .with_property("Anchored")
+            .with_property("Value");
+
+        assert_eq!(
+            dom.dump_tree(options),
+            concat!(
+                "Root (Folder)\n",
+                "  Car (Model)\n",
+                "    Wheel (Part)\n",
+                "      .Anchored = Bool(true)\n",
+                "    Folder (Folder)\n",
+                "      ... (1 children)\n",
+                "  StringValue (StringValue)\n",
+                "    .Value = String(\"hi\")\n",
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_parentings() {
+        let misplaced = InstanceBuilder::new("Attachment");
+        let misplaced_ref = misplaced.referent;
+
+        let dom = WeakDom::new(
+            InstanceBuilder::new("DataModel")
+                .with_child(
+                    InstanceBuilder::new("Workspace")
+                        .with_child(
+                            InstanceBuilder::new("Part")
+                                .with_child(InstanceBuilder::new("Attachment")),
+                        )
+                        .with_child(InstanceBuilder::new("Folder").with_child(misplaced)),
+                )
+                .with_child(InstanceBuilder::new("ReplicatedStorage")),
+        );
+
+        assert_eq!(
+            dom.invalid_parentings(rbx_reflection_database::get()),
+            vec![(misplaced_ref, "Attachment".to_owned(), "Folder".to_owned())]
+        );
+    }
+
+    #[test]
+    fn non_default_properties() {
+        let database = rbx_reflection_database::get();
+        let part_defaults = &database.classes["Part"].default_properties;
+
+        let part = InstanceBuilder::new("Part")
+            .with_property("Anchored", true)
+            .with_property("Transparency", part_defaults["Transparency"].clone())
+            .with_property("Size", part_defaults["Size"].clone())
+            .with_property("Locked", part_defaults["Locked"].clone());
+        let part_ref = part.referent;
+
+        let dom = WeakDom::new(InstanceBuilder::new("Folder").with_child(part));
+
+        assert_eq!(
+            dom.non_default_properties(database),
+            vec![(part_ref, "Anchored", &Variant::Bool(true))]
+        );
+    }
+
+    #[test]
+    fn deprecated_properties_used() {
+        let database = rbx_reflection_database::get();
+
+        let part = InstanceBuilder::new("Part")
+            .with_property("brickColor", BrickColor::BrightRed)
+            .with_property("Velocity", Vector3::new(0.0, 10.0, 0.0))
+            .with_property("Anchored", true)
+            .with_property("NotARealProperty", true);
+        let part_ref = part.referent;
+
+        let dom = WeakDom::new(InstanceBuilder::new("Folder").with_child(part));
+
+        assert_eq!(
+            dom.deprecated_properties_used(database),
+            vec![(part_ref, "Velocity"), (part_ref, "brickColor")]
+        );
+    }
+
+    #[test]
+    fn rename_matching() {
+        let mut dom = WeakDom::new(
+            InstanceBuilder::new("Model")
+                .with_name("Part")
+                .with_child(InstanceBuilder::new("Part").with_name("Part"))
+                .with_child(InstanceBuilder::new("Part").with_name("Parts"))
+                .with_child(
+                    InstanceBuilder::new("Folder")
+                        .with_name("Folder")
+                        .with_child(InstanceBuilder::new("Part").with_name("Part")),
+                ),
+        );
+
+        let renamed = dom.rename_matching(|name| name == "Part", |_| "Block".to_owned());
+        assert_eq!(renamed, 3);
+
+        // Names that don't change aren't counted.
+        let renamed = dom.rename_matching(|_| true, |name| name.trim().to_owned());
+        assert_eq!(renamed, 0);
+
+        let (_, instances) = dom.into_raw();
+        let mut names: Vec<String> = instances
+            .into_values()
+            .map(|instance| instance.name)
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, ["Block", "Block", "Block", "Folder", "Parts"]);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn to_json() {
+        use rbx_types::Color3uint8;
+        use serde_json::json;
+
+        let database = rbx_reflection_database::get();
+
+        let dom = WeakDom::new(
+            InstanceBuilder::new("Model").with_name("Car").with_child(
+                InstanceBuilder::new("Part")
+                    .with_name("Wheel")
+                    .with_property("Anchored", true)
+                    .with_property("Locked", false)
+                    .with_property("size", Vector3::new(2.0, 1.5, 2.0))
+                    .with_property("Color", Color3uint8::new(255, 0, 128))
+                    .with_property("Shape", Enum::from_u32(2))
+                    .with_property("Material", Enum::from_u32(288)),
+            ),
+        );
+
+        assert_eq!(
+            dom.to_json(database, JsonOptions::new()),
+            json!({
+                "class": "Model",
+                "name": "Car",
+                "properties": {},
+                "children": [{
+                    "class": "Part",
+                    "name": "Wheel",
+                    "properties": {
+                        "Anchored": true,
+                        "Color": "#ff0080",
+                        "Material": "Neon",
+                        "Shape": "Cylinder",
+                        "Size": [2.0, 1.5, 2.0],
+                    },
+                    "children": [],
+                }],
+            })
+        );
+
+        let with_defaults = dom.to_json(database, JsonOptions::new().with_defaults(true));
+        let wheel = &with_defaults["children"][0]["properties"];
+        assert_eq!(wheel["Locked"], json!(false));
+        assert_eq!(wheel["TopSurface"], json!("Studs"));
+        assert_eq!(wheel["Shape"], json!("Cylinder"));
+    }
+
+    #[test]
+    fn duplicate_unique_ids() {
+        let shared = UniqueId::new(1, 2, 3);
+
+        let first = InstanceBuilder::new("Part").with_property("UniqueId", shared);
+        let first_ref = first.referent;
+        let second = InstanceBuilder::new("Part").with_property("UniqueId", shared);
+        let second_ref = second.referent;
+
+        let dom = WeakDom::new(
+            InstanceBuilder::new("Folder")
+                .with_child(first)
+                .with_child(second)
+                .with_child(
+                    InstanceBuilder::new("Part").with_property("UniqueId", UniqueId::new(1, 2, 4)),
+                )
+                .with_child(InstanceBuilder::new("Part").with_property("UniqueId", UniqueId::nil()))
+                .with_child(
+                    InstanceBuilder::new("Part").with_property("UniqueId", UniqueId::nil()),
+                ),
+        );
+
+        let duplicates = dom.duplicate_unique_ids();
+        assert_eq!(duplicates.len(), 1);
+
+        let mut referents = duplicates[&shared].clone();
+        referents.sort_by_key(|referent| *referent != first_ref);
+        assert_eq!(referents, vec![first_ref, second_ref]);
+    }
 }
@@ -0,0 +1,68 @@
+use std::fmt::Write;
+
+use crate::{types::Ref, WeakDom};
+
+/// Options that control what [`WeakDom::dump_tree`] includes.
+#[derive(Debug, Clone, Default)]
+pub struct DumpOptions {
+    max_depth: Option<usize>,
+    properties: Vec<String>,
+}
+
+impl DumpOptions {
+    /// Creates a new `DumpOptions` that dumps the whole tree without any
+    /// properties.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops dumping below the given depth, where the root instance is at a
+    /// depth of zero. Instances whose children are cut off have the number of
+    /// hidden children noted instead.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Includes the given property under every instance that has it set.
+    /// Properties are listed in the order they were added.
+    pub fn with_property<S: Into<String>>(mut self, name: S) -> Self {
+        self.properties.push(name.into());
+        self
+    }
+}
+
+pub(crate) fn dump_tree(dom: &WeakDom, options: &DumpOptions) -> String {
+    let mut output = String::new();
+
+    // Walking the tree with our own stack instead of recursing means that a
+    // very deep tree can't overflow the call stack.
+    let mut stack: Vec<(Ref, usize)> = vec![(dom.root_ref(), 0)];
+
+    while let Some((referent, depth)) = stack.pop() {
+        let instance = dom.get_by_ref(referent).unwrap();
+        let indent = "  ".repeat(depth);
+
+        writeln!(output, "{}{} ({})", indent, instance.name, instance.class).unwrap();
+
+        for name in &options.properties {
+            if let Some(value) = instance.properties.get(name) {
+                writeln!(output, "{}  .{} = {:?}", indent, name, value).unwrap();
+            }
+        }
+
+        let children = instance.children();
+        if children.is_empty() {
+            continue;
+        }
+
+        if matches!(options.max_depth, Some(max_depth) if depth >= max_depth) {
+            writeln!(output, "{}  ... ({} children)", indent, children.len()).unwrap();
+            continue;
+        }
+
+        stack.extend(children.iter().rev().map(|&child| (child, depth + 1)));
+    }
+
+    output
+}
@@ -151,7 +151,7 @@ impl InstanceBuilder {
     where
         I: IntoIterator<Item = InstanceBuilder>,
     {
-        self.children.extend(children.into_iter());
+        self.children.extend(children);
         self
     }
 
@@ -162,7 +162,7 @@ impl InstanceBuilder {
     where
         I: IntoIterator<Item = InstanceBuilder>,
     {
-        self.children.extend(children.into_iter());
+        self.children.extend(children);
     }
 }
 
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use rbx_reflection::{DataType, PropertyDescriptor, PropertyKind, ReflectionDatabase};
+use rbx_types::{Color3uint8, Ref, Variant};
+use serde_json::{Map, Value};
+
+use crate::WeakDom;
+
+/// Options that control what [`WeakDom::to_json`] includes.
+#[derive(Debug, Clone, Default)]
+pub struct JsonOptions {
+    include_defaults: bool,
+}
+
+impl JsonOptions {
+    /// Creates a new `JsonOptions` that only includes properties whose values
+    /// differ from their defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also includes properties that are set to their default value, and
+    /// fills in the default value of every property with a known default that
+    /// isn't set on an instance.
+    pub fn with_defaults(mut self, include_defaults: bool) -> Self {
+        self.include_defaults = include_defaults;
+        self
+    }
+}
+
+pub(crate) fn to_json(
+    dom: &WeakDom,
+    database: &ReflectionDatabase,
+    options: &JsonOptions,
+) -> Value {
+    // Children have to be converted before their parents so that they can be
+    // moved into them. Walking the tree with our own stack instead of
+    // recursing means that a very deep tree can't overflow the call stack.
+    let mut order = Vec::new();
+    let mut stack = vec![dom.root_ref()];
+
+    while let Some(referent) = stack.pop() {
+        order.push(referent);
+        stack.extend(dom.get_by_ref(referent).unwrap().children());
+    }
+
+    let mut converted: HashMap<Ref, Value> = HashMap::with_capacity(order.len());
+
+    for &referent in order.iter().rev() {
+        let instance = dom.get_by_ref(referent).unwrap();
+
+        let mut properties = Map::new();
+
+        for (name, value) in &instance.properties {
+            let descriptor = database.find_property(&instance.class, name);
+            let canonical_name = match descriptor.map(|descriptor| &descriptor.kind) {
+                Some(PropertyKind::Alias { alias_for }) => alias_for.as_ref(),
+                _ => name.as_str(),
+            };
+
+            if !options.include_defaults
+                && database.find_default_property(&instance.class, canonical_name) == Some(value)
+            {
+                continue;
+            }
+
+            let descriptor = database.find_property(&instance.class, canonical_name);
+            properties.insert(
+                canonical_name.to_owned(),
+                variant_to_json(database, descriptor, value),
+            );
+        }
+
+        if options.include_defaults {
+            let mut current = database.classes.get(instance.class.as_str());
+
+            while let Some(class) = current {
+                for (name, value) in &class.default_properties {
+                    if !properties.contains_key(name.as_ref()) {
+                        let descriptor = database.find_property(&instance.class, name);
+                        properties.insert(
+                            name.to_string(),
+                            variant_to_json(database, descriptor, value),
+                        );
+                    }
+                }
+
+                current = class
+                    .superclass
+                    .as_deref()
+                    .and_then(|superclass| database.classes.get(superclass));
+            }
+        }
+
+        let children = instance
+            .children()
+            .iter()
+            .map(|child| converted.remove(child).unwrap())
+            .collect();
+
+        let mut object = Map::new();
+        object.insert("class".to_owned(), Value::String(instance.class.clone()));
+        object.insert("name".to_owned(), Value::String(instance.name.clone()));
+        object.insert("properties".to_owned(), Value::Object(properties));
+        object.insert("children".to_owned(), Value::Array(children));
+
+        converted.insert(referent, Value::Object(object));
+    }
+
+    converted.remove(&dom.root_ref()).unwrap()
+}
+
+fn variant_to_json(
+    database: &ReflectionDatabase,
+    descriptor: Option<&PropertyDescriptor>,
+    value: &Variant,
+) -> Value {
+    match value {
+        Variant::Bool(value) => Value::Bool(*value),
+        Variant::String(value) => Value::String(value.clone()),
+        Variant::Int32(value) => Value::from(*value),
+        Variant::Int64(value) => Value::from(*value),
+        Variant::Float32(value) => Value::from(*value),
+        Variant::Float64(value) => Value::from(*value),
+        Variant::Vector2(value) => Value::from(vec![value.x, value.y]),
+        Variant::Vector3(value) => Value::from(vec![value.x, value.y, value.z]),
+        Variant::Color3(value) => color_to_json((*value).into()),
+        Variant::Color3uint8(value) => color_to_json(*value),
+        Variant::Ref(value) if value.is_none() => Value::Null,
+        Variant::Ref(value) => Value::String(value.to_string()),
+        Variant::Enum(value) => {
+            let item_name = descriptor
+                .and_then(|descriptor| match &descriptor.data_type {
+                    DataType::Enum(enum_name) => database.enums.get(enum_name),
+                    _ => None,
+                })
+                .and_then(|descriptor| {
+                    // Some enums have several names for one value, so pick
+                    // one consistently.
+                    descriptor
+                        .items
+                        .iter()
+                        .filter(|(_, &item)| item == value.to_u32())
+                        .map(|(name, _)| name)
+                        .min()
+                });
+
+            match item_name {
+                Some(name) => Value::String(name.to_string()),
+                None => Value::from(value.to_u32()),
+            }
+        }
+
+        // Everything else uses its serde representation without the tag
+        // naming its type.
+        other => match serde_json::to_value(other) {
+            Ok(Value::Object(mut tagged)) if tagged.len() == 1 => {
+                let (_, inner) = tagged.iter_mut().next().unwrap();
+                inner.take()
+            }
+            Ok(value) => value,
+            Err(_) => Value::Null,
+        },
+    }
+}
+
+fn color_to_json(color: Color3uint8) -> Value {
+    Value::String(format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b))
+}
@@ -42,13 +42,20 @@
 #![deny(missing_docs)]
 
 mod dom;
+mod dump;
 mod instance;
+#[cfg(feature = "json")]
+mod json;
 mod viewer;
 
 pub use rbx_types as types;
 
 pub use crate::{
     dom::WeakDom,
+    dump::DumpOptions,
     instance::{Instance, InstanceBuilder},
     viewer::{DomViewer, ViewedInstance},
 };
+
+#[cfg(feature = "json")]
+pub use crate::json::JsonOptions;
@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 
@@ -20,6 +20,14 @@ pub enum ClassTag {
 #[derive(Debug)]
 pub struct ClassTagFromStrError(String);
 
+impl fmt::Display for ClassTagFromStrError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "unknown class tag \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for ClassTagFromStrError {}
+
 impl FromStr for ClassTag {
     type Err = ClassTagFromStrError;
 
@@ -39,6 +39,175 @@ impl<'a> ReflectionDatabase<'a> {
             enums: HashMap::new(),
         }
     }
+
+    /// Tells whether `value` is a defined item of the enum used by the given
+    /// property. Properties inherited from superclasses are checked too.
+    ///
+    /// Returns `None` if the property can't be found, if it isn't an enum, or
+    /// if the enum it refers to isn't in the database.
+    pub fn is_valid_enum_value(&self, class: &str, prop: &str, value: u32) -> Option<bool> {
+        let enum_name = match &self.find_property(class, prop)?.data_type {
+            DataType::Enum(name) => name,
+            DataType::Value(_) => return None,
+        };
+
+        let enum_descriptor = self.enums.get(enum_name)?;
+        Some(enum_descriptor.items.values().any(|&item| item == value))
+    }
+
+    /// Finds the name of the item with the given value in the given enum.
+    ///
+    /// If several items share the value, the name that sorts first is
+    /// returned so that the result doesn't depend on `HashMap` ordering.
+    /// Returns `None` if the enum isn't in the database or has no item with
+    /// the value.
+    pub fn enum_value_name(&self, enum_name: &str, value: u32) -> Option<&str> {
+        self.enums
+            .get(enum_name)?
+            .items
+            .iter()
+            .filter(|(_, &item)| item == value)
+            .map(|(name, _)| name.as_ref())
+            .min()
+    }
+
+    /// Finds the value of the item with the given name in the given enum. This
+    /// is the inverse of [`ReflectionDatabase::enum_value_name`].
+    ///
+    /// Returns `None` if the enum isn't in the database or has no item with
+    /// the name.
+    pub fn enum_value(&self, enum_name: &str, name: &str) -> Option<u32> {
+        self.enums.get(enum_name)?.items.get(name).copied()
+    }
+
+    /// Tells whether an instance of class `child` may be parented to an
+    /// instance of class `parent`.
+    ///
+    /// The API dump doesn't describe parenting rules, so this only knows about
+    /// a handful of rules that Roblox enforces:
+    ///
+    /// - Services can only be parented to the `DataModel`.
+    /// - The `DataModel` can't be parented to anything.
+    /// - Attachments must be parented to a `BasePart`. Bones, which are
+    ///   Attachments, may also be parented to other Bones.
+    ///
+    /// Anything not covered by these rules, including classes that aren't in
+    /// the database, is allowed.
+    pub fn can_parent(&self, child: &str, parent: &str) -> bool {
+        if child == "DataModel" {
+            return false;
+        }
+
+        if let Some(descriptor) = self.classes.get(child) {
+            if descriptor.tags.contains(&ClassTag::Service) {
+                return parent == "DataModel";
+            }
+        }
+
+        if self.class_is_a(child, "Attachment") {
+            return self.class_is_a(parent, "BasePart")
+                || (self.class_is_a(child, "Bone") && self.class_is_a(parent, "Bone"));
+        }
+
+        true
+    }
+
+    /// Finds the default value of a property on the given class, looking
+    /// through superclasses if the class doesn't list one itself.
+    ///
+    /// Returns `None` if the class isn't in the database or if no default
+    /// value is known for the property.
+    pub fn find_default_property(&self, class: &str, prop: &str) -> Option<&Variant> {
+        let mut current = self.classes.get(class);
+
+        while let Some(descriptor) = current {
+            if let Some(value) = descriptor.default_properties.get(prop) {
+                return Some(value);
+            }
+
+            current = descriptor
+                .superclass
+                .as_deref()
+                .and_then(|superclass| self.classes.get(superclass));
+        }
+
+        None
+    }
+
+    /// Finds the descriptor of a property on the given class, looking through
+    /// superclasses if the class doesn't define it itself.
+    ///
+    /// Returns `None` if the class isn't in the database or if neither it nor
+    /// its superclasses have the property.
+    pub fn find_property(&self, class: &str, prop: &str) -> Option<&PropertyDescriptor<'a>> {
+        let mut current = self.classes.get(class);
+
+        while let Some(descriptor) = current {
+            if let Some(property) = descriptor.properties.get(prop) {
+                return Some(property);
+            }
+
+            current = descriptor
+                .superclass
+                .as_deref()
+                .and_then(|superclass| self.classes.get(superclass));
+        }
+
+        None
+    }
+
+    /// Returns the properties that `class` declares itself, leaving out the
+    /// ones it inherits from its superclasses, sorted by name.
+    ///
+    /// Returns an empty list if the class isn't in the database.
+    pub fn own_properties(&self, class: &str) -> Vec<&PropertyDescriptor<'a>> {
+        let mut properties: Vec<_> = match self.classes.get(class) {
+            Some(descriptor) => descriptor.properties.values().collect(),
+            None => Vec::new(),
+        };
+
+        properties.sort_by(|a, b| a.name.cmp(&b.name));
+        properties
+    }
+
+    /// Returns every class that inherits from `class`, directly or through
+    /// other classes. `class` itself isn't included. The classes are returned
+    /// in no particular order.
+    ///
+    /// The subclasses aren't cached, because the database's fields can be
+    /// changed at any time. Instead, every class walks its own superclass
+    /// chain, so a full iteration takes time proportional to the number of
+    /// classes multiplied by the depth of the class hierarchy.
+    pub fn subclasses<'db>(
+        &'db self,
+        class: &'db str,
+    ) -> impl Iterator<Item = &'db ClassDescriptor<'a>> + 'db {
+        self.classes.values().filter(move |descriptor| {
+            descriptor.name != class && self.class_is_a(&descriptor.name, class)
+        })
+    }
+
+    /// Tells whether `class` is `ancestor` or one of its subclasses, like
+    /// `Instance:IsA` does in Roblox.
+    ///
+    /// Returns `false` if `class` isn't in the database, even when `class` and
+    /// `ancestor` are the same.
+    pub fn class_is_a(&self, class: &str, ancestor: &str) -> bool {
+        let mut current = self.classes.get(class);
+
+        while let Some(descriptor) = current {
+            if descriptor.name == ancestor {
+                return true;
+            }
+
+            current = descriptor
+                .superclass
+                .as_deref()
+                .and_then(|superclass| self.classes.get(superclass));
+        }
+
+        false
+    }
 }
 
 /// Describes a class of Instance, its properties, and its relation to other
@@ -117,6 +286,11 @@ impl<'a> PropertyDescriptor<'a> {
             },
         }
     }
+
+    /// Tells whether Roblox has deprecated this property.
+    pub fn is_deprecated(&self) -> bool {
+        self.tags.contains(&PropertyTag::Deprecated)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 
@@ -18,6 +18,14 @@ pub enum PropertyTag {
 #[derive(Debug)]
 pub struct PropertyTagFromStrError(String);
 
+impl fmt::Display for PropertyTagFromStrError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "unknown property tag \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for PropertyTagFromStrError {}
+
 impl FromStr for PropertyTag {
     type Err = PropertyTagFromStrError;
 
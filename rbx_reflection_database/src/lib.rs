@@ -14,10 +14,139 @@ pub fn get() -> &'static ReflectionDatabase<'static> {
 
 #[cfg(test)]
 mod test {
+    use rbx_types::Material;
+
     use super::*;
 
     #[test]
     fn smoke_test() {
         let _database = get();
     }
+
+    #[test]
+    fn material_enum_values() {
+        let database = get();
+
+        // Enum.Material.Plastic
+        assert_eq!(
+            database.is_valid_enum_value("Part", "Material", 256),
+            Some(true)
+        );
+        assert_eq!(
+            database.is_valid_enum_value("Part", "Material", 12345),
+            Some(false)
+        );
+
+        assert_eq!(database.is_valid_enum_value("Part", "Name", 0), None);
+        assert_eq!(
+            database.is_valid_enum_value("Part", "NotAProperty", 0),
+            None
+        );
+    }
+
+    #[test]
+    fn enum_names() {
+        let database = get();
+
+        assert_eq!(database.enum_value_name("Material", 256), Some("Plastic"));
+        assert_eq!(database.enum_value("Material", "Plastic"), Some(256));
+
+        assert_eq!(database.enum_value_name("Material", 12345), None);
+        assert_eq!(database.enum_value("Material", "Calzone"), None);
+        assert_eq!(database.enum_value_name("NotAnEnum", 0), None);
+    }
+
+    #[test]
+    fn class_is_a() {
+        let database = get();
+
+        assert!(database.class_is_a("Part", "Part"));
+        assert!(database.class_is_a("Part", "BasePart"));
+        assert!(database.class_is_a("Part", "Instance"));
+        assert!(!database.class_is_a("BasePart", "Part"));
+        assert!(!database.class_is_a("Folder", "BasePart"));
+
+        assert!(!database.class_is_a("NotAClass", "NotAClass"));
+        assert!(!database.class_is_a("NotAClass", "Instance"));
+        assert!(!database.class_is_a("Part", "NotAClass"));
+    }
+
+    #[test]
+    fn subclasses() {
+        let database = get();
+
+        let gui_objects: Vec<_> = database
+            .subclasses("GuiObject")
+            .map(|descriptor| descriptor.name.as_ref())
+            .collect();
+
+        assert!(gui_objects.contains(&"Frame"));
+        assert!(gui_objects.contains(&"TextButton"));
+        assert!(gui_objects.contains(&"GuiButton"));
+        assert!(!gui_objects.contains(&"GuiObject"));
+        assert!(!gui_objects.contains(&"Part"));
+
+        assert_eq!(database.subclasses("Folder").count(), 0);
+        assert_eq!(database.subclasses("NotAClass").count(), 0);
+    }
+
+    #[test]
+    fn parenting_rules() {
+        let database = get();
+
+        assert!(database.can_parent("Workspace", "DataModel"));
+        assert!(!database.can_parent("Workspace", "Folder"));
+        assert!(!database.can_parent("DataModel", "Folder"));
+
+        assert!(database.can_parent("Attachment", "Part"));
+        assert!(database.can_parent("Attachment", "MeshPart"));
+        assert!(!database.can_parent("Attachment", "Folder"));
+        assert!(database.can_parent("Bone", "Bone"));
+        assert!(!database.can_parent("Attachment", "Bone"));
+
+        assert!(database.can_parent("Folder", "Folder"));
+        assert!(database.can_parent("NotAClass", "Folder"));
+    }
+
+    #[test]
+    fn own_properties() {
+        let database = get();
+
+        let part: Vec<_> = database
+            .own_properties("Part")
+            .into_iter()
+            .map(|property| property.name.as_ref())
+            .collect();
+
+        assert!(part.contains(&"shape"));
+        assert!(!part.contains(&"Anchored"));
+        assert!(!part.contains(&"Name"));
+
+        let base_part: Vec<_> = database
+            .own_properties("BasePart")
+            .into_iter()
+            .map(|property| property.name.as_ref())
+            .collect();
+
+        assert!(base_part.contains(&"Anchored"));
+        assert!(database.own_properties("NotAClass").is_empty());
+    }
+
+    /// rbx_types keeps its own table of materials, which should agree with
+    /// the `Material` enum in the database.
+    #[test]
+    fn materials_match_types() {
+        let database = get();
+        let material_enum = database.enums.get("Material").unwrap();
+
+        assert!(!material_enum.items.is_empty());
+
+        for (name, &value) in &material_enum.items {
+            let material = Material::from_u32(value).unwrap_or_else(|| {
+                panic!("Material.{} ({}) is missing from rbx_types", name, value)
+            });
+
+            assert_eq!(format!("{:?}", material), *name);
+        }
+    }
 }
@@ -0,0 +1,213 @@
+use crate::{
+    Attributes, CFrame, Color3, ColorSequence, ColorSequenceKeypoint, CustomPhysicalProperties,
+    Matrix3, NumberRange, NumberSequence, NumberSequenceKeypoint, PhysicalProperties, Ray, Rect,
+    Region3, UDim, UDim2, Variant, Vector2, Vector3,
+};
+
+/// Compares values that contain floats, allowing each float to differ by up
+/// to `epsilon` and treating NaN as equal to NaN.
+pub(crate) trait ApproxEq {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool;
+}
+
+impl ApproxEq for f32 {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        if self.is_nan() || other.is_nan() {
+            return self.is_nan() && other.is_nan();
+        }
+
+        // Infinities of the same sign are equal, but their difference is NaN.
+        self == other || (self - other).abs() <= epsilon
+    }
+}
+
+impl ApproxEq for f64 {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        if self.is_nan() || other.is_nan() {
+            return self.is_nan() && other.is_nan();
+        }
+
+        self == other || (self - other).abs() <= f64::from(epsilon)
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for Option<T> {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.approx_eq(b, epsilon),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for [T] {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.approx_eq(b, epsilon))
+    }
+}
+
+/// Implements `ApproxEq` for a struct by comparing each of the listed fields.
+macro_rules! approx_eq_fields {
+    ($($ty:ty { $($field:ident),* },)*) => {
+        $(
+            impl ApproxEq for $ty {
+                fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+                    $( self.$field.approx_eq(&other.$field, epsilon) )&&*
+                }
+            }
+        )*
+    };
+}
+
+approx_eq_fields! {
+    Vector2 { x, y },
+    Vector3 { x, y, z },
+    Matrix3 { x, y, z },
+    CFrame { position, orientation },
+    Color3 { r, g, b },
+    Ray { origin, direction },
+    Region3 { min, max },
+    Rect { min, max },
+    UDim2 { x, y },
+    NumberRange { min, max },
+    ColorSequenceKeypoint { time, color },
+    NumberSequenceKeypoint { time, value, envelope },
+    CustomPhysicalProperties {
+        density,
+        friction,
+        elasticity,
+        friction_weight,
+        elasticity_weight
+    },
+}
+
+impl ApproxEq for UDim {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.scale.approx_eq(&other.scale, epsilon) && self.offset == other.offset
+    }
+}
+
+impl ApproxEq for ColorSequence {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.keypoints.approx_eq(&other.keypoints, epsilon)
+    }
+}
+
+impl ApproxEq for NumberSequence {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.keypoints.approx_eq(&other.keypoints, epsilon)
+    }
+}
+
+impl ApproxEq for PhysicalProperties {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        match (self, other) {
+            (PhysicalProperties::Custom(a), PhysicalProperties::Custom(b)) => {
+                a.approx_eq(b, epsilon)
+            }
+            _ => self == other,
+        }
+    }
+}
+
+impl ApproxEq for Attributes {
+    fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.iter().count() == other.iter().count()
+            && self.iter().all(|(key, value)| {
+                other
+                    .get(key.as_str())
+                    .is_some_and(|other_value| value.approx_eq(other_value, epsilon))
+            })
+    }
+}
+
+impl Variant {
+    /// Tells whether two values are equal, allowing every float they contain
+    /// to differ by up to `epsilon` and treating NaN as equal to NaN.
+    ///
+    /// Floats are compared inside composite types too, like `CFrame`,
+    /// `ColorSequence`, and `Attributes`. Values without floats, or values of
+    /// different types, are compared with `==`.
+    ///
+    /// This is useful for comparing values that have been through an encode
+    /// and decode cycle, which can change the last bits of a float.
+    pub fn approx_eq(&self, other: &Variant, epsilon: f32) -> bool {
+        match (self, other) {
+            (Variant::CFrame(a), Variant::CFrame(b)) => a.approx_eq(b, epsilon),
+            (Variant::Color3(a), Variant::Color3(b)) => a.approx_eq(b, epsilon),
+            (Variant::ColorSequence(a), Variant::ColorSequence(b)) => a.approx_eq(b, epsilon),
+            (Variant::Float32(a), Variant::Float32(b)) => a.approx_eq(b, epsilon),
+            (Variant::Float64(a), Variant::Float64(b)) => a.approx_eq(b, epsilon),
+            (Variant::NumberRange(a), Variant::NumberRange(b)) => a.approx_eq(b, epsilon),
+            (Variant::NumberSequence(a), Variant::NumberSequence(b)) => a.approx_eq(b, epsilon),
+            (Variant::PhysicalProperties(a), Variant::PhysicalProperties(b)) => {
+                a.approx_eq(b, epsilon)
+            }
+            (Variant::Ray(a), Variant::Ray(b)) => a.approx_eq(b, epsilon),
+            (Variant::Rect(a), Variant::Rect(b)) => a.approx_eq(b, epsilon),
+            (Variant::Region3(a), Variant::Region3(b)) => a.approx_eq(b, epsilon),
+            (Variant::UDim(a), Variant::UDim(b)) => a.approx_eq(b, epsilon),
+            (Variant::UDim2(a), Variant::UDim2(b)) => a.approx_eq(b, epsilon),
+            (Variant::Vector2(a), Variant::Vector2(b)) => a.approx_eq(b, epsilon),
+            (Variant::Vector3(a), Variant::Vector3(b)) => a.approx_eq(b, epsilon),
+            (Variant::OptionalCFrame(a), Variant::OptionalCFrame(b)) => a.approx_eq(b, epsilon),
+            (Variant::Attributes(a), Variant::Attributes(b)) => a.approx_eq(b, epsilon),
+            _ => self == other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn floats() {
+        assert!(Variant::Float32(1.0).approx_eq(&Variant::Float32(1.0 + 1e-7), 1e-5));
+        assert!(!Variant::Float32(1.0).approx_eq(&Variant::Float32(1.1), 1e-5));
+        assert!(Variant::Float64(f64::NAN).approx_eq(&Variant::Float64(f64::NAN), 0.0));
+        assert!(!Variant::Float32(f32::NAN).approx_eq(&Variant::Float32(0.0), 1.0));
+        assert!(Variant::Float32(f32::INFINITY).approx_eq(&Variant::Float32(f32::INFINITY), 0.0));
+        assert!(
+            !Variant::Float32(f32::INFINITY).approx_eq(&Variant::Float32(f32::NEG_INFINITY), 1.0)
+        );
+    }
+
+    #[test]
+    fn composites() {
+        let nan_cframe = CFrame::new(Vector3::new(f32::NAN, 1.0, 2.0), Matrix3::identity());
+        assert!(Variant::CFrame(nan_cframe).approx_eq(&Variant::CFrame(nan_cframe), 0.0));
+
+        let a = ColorSequence {
+            keypoints: vec![
+                ColorSequenceKeypoint::new(0.0, Color3::new(1.0, 0.5, 0.0)),
+                ColorSequenceKeypoint::new(1.0, Color3::new(0.0, 0.0, 0.0)),
+            ],
+        };
+        let mut b = a.clone();
+        b.keypoints[0].color.g += 1e-6;
+        assert!(Variant::from(a.clone()).approx_eq(&Variant::from(b.clone()), 1e-5));
+        assert!(!Variant::from(a.clone()).approx_eq(&Variant::from(b), 1e-7));
+
+        let mut shorter = a.clone();
+        shorter.keypoints.pop();
+        assert!(!Variant::from(a).approx_eq(&Variant::from(shorter), 1.0));
+
+        let attributes = Attributes::new().with("Speed", 1.0f32);
+        let close = Attributes::new().with("Speed", 1.0f32 + 1e-6);
+        let extra = close.clone().with("Other", true);
+        assert!(Variant::from(attributes.clone()).approx_eq(&Variant::from(close), 1e-5));
+        assert!(!Variant::from(attributes).approx_eq(&Variant::from(extra), 1e-5));
+    }
+
+    #[test]
+    fn other_types() {
+        assert!(Variant::Int32(5).approx_eq(&Variant::Int32(5), 0.0));
+        assert!(!Variant::Int32(5).approx_eq(&Variant::Int32(6), 10.0));
+        assert!(!Variant::Float32(1.0).approx_eq(&Variant::Float64(1.0), 1.0));
+        assert!(
+            !Variant::UDim(UDim::new(0.5, 1)).approx_eq(&Variant::UDim(UDim::new(0.5, 2)), 10.0)
+        );
+    }
+}
@@ -15,11 +15,23 @@ pub(crate) enum AttributeError {
     #[error("attribute key contained invalid UTF-8")]
     KeyBadUnicode(#[source] FromUtf8Error),
 
-    #[error("missing attribute value type")]
-    NoValueType,
+    #[error("missing value type for attribute {key:?}")]
+    NoValueType { key: String },
 
-    #[error("invalid value type: {0}")]
-    InvalidValueType(u8),
+    #[error("invalid value type for attribute {key:?}: {type_id}")]
+    InvalidValueType { key: String, type_id: u8 },
+
+    #[error("invalid value for attribute {key:?}")]
+    InvalidValue {
+        key: String,
+        source: Box<AttributeError>,
+    },
+
+    #[error("attribute {0:?} appears more than once")]
+    DuplicateKey(String),
+
+    #[error("invalid attribute name {key:?}: {reason}")]
+    InvalidKey { key: String, reason: &'static str },
 
     #[error("{0:?} values are not supported in attributes")]
     UnsupportedVariantType(VariantType),
@@ -27,6 +39,12 @@ pub(crate) enum AttributeError {
     #[error("invalid BrickColor value: {0}")]
     InvalidBrickColor(u32),
 
+    #[error("invalid Font weight: {0}")]
+    InvalidFontWeight(u16),
+
+    #[error("invalid Font style: {0}")]
+    InvalidFontStyle(u8),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
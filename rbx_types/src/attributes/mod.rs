@@ -15,13 +15,41 @@ use std::{
     iter::FromIterator,
 };
 
-use crate::{Error, Variant};
+use crate::{variant_key::bits_eq, Error, Variant};
 
 use self::reader::read_attributes;
-use self::writer::write_attributes;
+use self::writer::{attributes_len, write_attributes};
 
 pub(crate) use self::error::AttributeError;
 
+/// Options that control how [`Attributes::from_reader_with_options`] handles
+/// values it doesn't understand.
+#[derive(Debug, Clone, Default)]
+pub struct AttributeDeserializeOptions {
+    lenient: bool,
+}
+
+impl AttributeDeserializeOptions {
+    /// Creates a new `AttributeDeserializeOptions` that fails on any value it
+    /// doesn't understand, like [`Attributes::from_reader`] does.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Skips values that can't be understood instead of failing, which is
+    /// useful for files saved by a newer version of Roblox Studio.
+    ///
+    /// Values with a known type but an unknown value, like a `BrickColor`
+    /// with an unknown number, are skipped individually. Values of an unknown
+    /// type are skipped along with every attribute after them, because their
+    /// length can't be known. When a key appears more than once, the last
+    /// value wins. Data that is truncated still causes an error.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(
     feature = "serde",
@@ -40,16 +68,43 @@ impl Attributes {
 
     /// Reads from a serialized attributes string, and produces a new `Attributes` from it.
     pub fn from_reader<R: Read>(reader: R) -> Result<Self, Error> {
-        Ok(Attributes {
-            data: read_attributes(reader)?,
-        })
+        let (attributes, _) =
+            Self::from_reader_with_options(reader, &AttributeDeserializeOptions::new())?;
+
+        Ok(attributes)
+    }
+
+    /// Reads from a serialized attributes string using the given options.
+    /// Along with the attributes, returns the keys of any attributes that
+    /// were skipped, which is always empty unless the options are lenient.
+    pub fn from_reader_with_options<R: Read>(
+        reader: R,
+        options: &AttributeDeserializeOptions,
+    ) -> Result<(Self, Vec<String>), Error> {
+        let (data, skipped) = read_attributes(reader, options)?;
+
+        Ok((Attributes { data }, skipped))
     }
 
     /// Writes the attributes as a serialized string to the writer.
+    ///
+    /// Attributes are always written sorted by key (comparing the keys'
+    /// UTF-8 bytes), regardless of their types or the order they were
+    /// inserted in. Writing the same set of attributes will always produce
+    /// byte-identical output.
     pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<(), Error> {
         write_attributes(&self.data, &mut writer).map_err(Into::into)
     }
 
+    /// Returns the number of bytes that [`Attributes::to_writer`] would write,
+    /// without writing anything.
+    ///
+    /// Attributes whose types can't be written aren't counted, since
+    /// `to_writer` fails when it sees one.
+    pub fn serialized_len(&self) -> usize {
+        attributes_len(&self.data)
+    }
+
     /// Get the attribute with the following key.
     pub fn get<K: Borrow<str>>(&self, key: K) -> Option<&Variant> {
         self.data.get(key.borrow())
@@ -61,6 +116,19 @@ impl Attributes {
         self.data.insert(key, value)
     }
 
+    /// Inserts an attribute with the given key and value, first checking that
+    /// Roblox will accept the key. Will return the attribute that used to be
+    /// there if one existed.
+    ///
+    /// Keys must be between 1 and 100 characters long, can only contain
+    /// letters, digits, and underscores, and can't begin with `RBX`, which
+    /// Roblox reserves for itself.
+    pub fn try_insert(&mut self, key: String, value: Variant) -> Result<Option<Variant>, Error> {
+        validate_key(&key)?;
+
+        Ok(self.data.insert(key, value))
+    }
+
     /// Inserts an attribute with the given key and value.
     /// Will overwrite the attribute that used to be there if one existed.
     pub fn with<K: Into<String>, V: Into<Variant>>(mut self, key: K, value: V) -> Self {
@@ -74,10 +142,81 @@ impl Attributes {
         self.data.remove(key.borrow())
     }
 
-    /// Returns an iterator of borrowed attributes.
+    /// Returns an iterator of borrowed attributes, sorted by key.
     pub fn iter(&self) -> impl Iterator<Item = (&String, &Variant)> {
         self.data.iter()
     }
+
+    /// Returns an iterator of borrowed attributes, sorted by key (comparing
+    /// the keys' UTF-8 bytes). This is the same order that
+    /// [`Attributes::to_writer`] writes attributes in.
+    ///
+    /// `Attributes` always keeps its entries sorted, so this is exactly as
+    /// fast as [`Attributes::iter`], but spells out the order for callers that
+    /// depend on it.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (&str, &Variant)> {
+        self.data.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Compares these attributes with `other`, reporting the attributes that
+    /// `other` adds, removes, or changes.
+    ///
+    /// Values are compared by their bit patterns, like [`VariantKey`][crate::VariantKey]
+    /// does, so a `NaN` that's unchanged isn't reported as a change.
+    pub fn diff(&self, other: &Attributes) -> AttributeDiff {
+        let mut diff = AttributeDiff::default();
+
+        for (key, old) in &self.data {
+            match other.data.get(key) {
+                Some(new) if bits_eq(old, new) => {}
+                Some(new) => {
+                    diff.changed.insert(
+                        key.clone(),
+                        AttributeChange {
+                            old: old.clone(),
+                            new: new.clone(),
+                        },
+                    );
+                }
+                None => {
+                    diff.removed.insert(key.clone(), old.clone());
+                }
+            }
+        }
+
+        for (key, new) in &other.data {
+            if !self.data.contains_key(key) {
+                diff.added.insert(key.clone(), new.clone());
+            }
+        }
+
+        diff
+    }
+}
+
+/// The longest attribute name that Roblox accepts.
+const MAX_KEY_LEN: usize = 100;
+
+fn validate_key(key: &str) -> Result<(), AttributeError> {
+    let reason = if key.is_empty() {
+        "names can't be empty"
+    } else if key.len() > MAX_KEY_LEN {
+        "names can't be longer than 100 characters"
+    } else if key.starts_with("RBX") {
+        "names beginning with RBX are reserved"
+    } else if !key
+        .chars()
+        .all(|char| char.is_ascii_alphanumeric() || char == '_')
+    {
+        "names can only contain letters, digits, and underscores"
+    } else {
+        return Ok(());
+    };
+
+    Err(AttributeError::InvalidKey {
+        key: key.to_owned(),
+        reason,
+    })
 }
 
 impl IntoIterator for Attributes {
@@ -113,9 +252,46 @@ impl Iterator for AttributesIntoIter {
     }
 }
 
+/// The differences between two sets of attributes, created by
+/// [`Attributes::diff`]. Every map is sorted by key.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttributeDiff {
+    /// Attributes that only exist in the new set.
+    pub added: BTreeMap<String, Variant>,
+
+    /// Attributes that only exist in the old set, with their old values.
+    pub removed: BTreeMap<String, Variant>,
+
+    /// Attributes that exist in both sets but have different values.
+    pub changed: BTreeMap<String, AttributeChange>,
+}
+
+impl AttributeDiff {
+    /// Returns whether the two sets of attributes were the same.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// The old and new value of an attribute that changed.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttributeChange {
+    pub old: Variant,
+    pub new: Variant,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::convert::TryInto;
+
+    use crate::{
+        BinaryString, BrickColor, Color3, ColorSequence, ColorSequenceKeypoint, Content, Font,
+        FontStyle, FontWeight, NumberRange, NumberSequence, NumberSequenceKeypoint, Rect, UDim,
+        UDim2, Vector2, Vector3,
+    };
 
     // This is taken from rbx-test-files/models/attributes/xml.rbxmx, but with
     // the NaN and Infinity removed. This is pasted raw as to not create a
@@ -179,10 +355,449 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_stable_write_order() {
+        let first = Attributes::new()
+            .with("zeta", 1.0f64)
+            .with("Alpha", true)
+            .with("middle", "text");
+
+        let mut second = Attributes::new();
+        second.insert("middle".to_owned(), Variant::String("text".to_owned()));
+        second.insert("Alpha".to_owned(), Variant::Bool(true));
+        second.insert("zeta".to_owned(), Variant::Float64(1.0));
+
+        let mut first_bytes = Vec::new();
+        first.to_writer(&mut first_bytes).unwrap();
+
+        let mut first_bytes_again = Vec::new();
+        first.to_writer(&mut first_bytes_again).unwrap();
+
+        let mut second_bytes = Vec::new();
+        second.to_writer(&mut second_bytes).unwrap();
+
+        assert_eq!(first_bytes, first_bytes_again);
+        assert_eq!(first_bytes, second_bytes);
+
+        // The first entry comes directly after the entry count and must be the
+        // lowest key, even though it was neither inserted first nor last.
+        assert_eq!(&first_bytes[4..8], &5u32.to_le_bytes());
+        assert_eq!(&first_bytes[8..13], b"Alpha");
+    }
+
+    #[test]
+    fn test_vector3_layout() {
+        let attributes = Attributes::new().with("spawn", Vector3::new(1.0, -2.5, 1024.0));
+
+        let mut bytes = Vec::new();
+        attributes.to_writer(&mut bytes).unwrap();
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&1u32.to_le_bytes());
+        expected.extend_from_slice(&5u32.to_le_bytes());
+        expected.extend_from_slice(b"spawn");
+        expected.push(0x11);
+        expected.extend_from_slice(&1.0f32.to_le_bytes());
+        expected.extend_from_slice(&(-2.5f32).to_le_bytes());
+        expected.extend_from_slice(&1024.0f32.to_le_bytes());
+        assert_eq!(bytes, expected);
+
+        let decoded = Attributes::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(decoded, attributes);
+    }
+
+    #[test]
+    fn test_vector2_from_bytes() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&6u32.to_le_bytes());
+        bytes.extend_from_slice(b"anchor");
+        bytes.push(0x10);
+        bytes.extend_from_slice(&0.5f32.to_le_bytes());
+        bytes.extend_from_slice(&(-1.0f32).to_le_bytes());
+
+        let attributes = Attributes::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(
+            attributes.get("anchor"),
+            Some(&Variant::Vector2(Vector2::new(0.5, -1.0)))
+        );
+
+        let mut written = Vec::new();
+        attributes.to_writer(&mut written).unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn test_rect_round_trip() {
+        let rect = Rect::new(Vector2::new(1.0, 2.0), Vector2::new(3.0, 4.0));
+        let attributes = Attributes::new().with("slice", rect);
+
+        let mut bytes = Vec::new();
+        attributes.to_writer(&mut bytes).unwrap();
+
+        // The value follows the entry count, the key, and the type id, and is
+        // the minimum corner followed by the maximum corner.
+        let value: Vec<f32> = bytes[14..]
+            .chunks(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(bytes[13], 0x1C);
+        assert_eq!(value, [1.0, 2.0, 3.0, 4.0]);
+
+        let decoded = Attributes::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.get("slice"), Some(&Variant::Rect(rect)));
+    }
+
+    #[test]
+    fn test_content_as_string() {
+        let attributes = Attributes::new().with("Icon", Content::from("rbxassetid://12345"));
+
+        let mut bytes = Vec::new();
+        attributes.to_writer(&mut bytes).unwrap();
+
+        let mut expected = Vec::new();
+        Attributes::new()
+            .with("Icon", "rbxassetid://12345")
+            .to_writer(&mut expected)
+            .unwrap();
+        assert_eq!(bytes, expected);
+        assert_eq!(attributes.serialized_len(), bytes.len());
+    }
+
+    #[test]
+    fn test_font_round_trip() {
+        let font = Font {
+            family: "rbxasset://fonts/families/Arial.json".to_owned(),
+            weight: FontWeight::Bold,
+            style: FontStyle::Italic,
+            cached_face_id: None,
+        };
+        let attributes = Attributes::new().with("title", font.clone());
+
+        let mut bytes = Vec::new();
+        attributes.to_writer(&mut bytes).unwrap();
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&1u32.to_le_bytes());
+        expected.extend_from_slice(&5u32.to_le_bytes());
+        expected.extend_from_slice(b"title");
+        expected.push(0x21);
+        expected.extend_from_slice(&700u16.to_le_bytes());
+        expected.push(1);
+        expected.extend_from_slice(&(font.family.len() as u32).to_le_bytes());
+        expected.extend_from_slice(font.family.as_bytes());
+        expected.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(bytes, expected);
+
+        let decoded = Attributes::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.get("title"), Some(&Variant::Font(font.clone())));
+
+        let cached = Font {
+            cached_face_id: Some("rbxasset://fonts/Arial-BoldItalic.ttf".to_owned()),
+            ..font
+        };
+        let attributes = Attributes::new().with("title", cached);
+
+        let mut bytes = Vec::new();
+        attributes.to_writer(&mut bytes).unwrap();
+        assert_eq!(
+            Attributes::from_reader(bytes.as_slice()).unwrap(),
+            attributes
+        );
+    }
+
+    /// Encodes a single attribute entry by hand.
+    fn entry(key: &str, type_id: u8, value: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(key.as_bytes());
+        bytes.push(type_id);
+        bytes.extend_from_slice(value);
+        bytes
+    }
+
+    /// Encodes a list of attribute entries made by `entry`.
+    fn entries(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = (entries.len() as u32).to_le_bytes().to_vec();
+        for entry in entries {
+            bytes.extend_from_slice(entry);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_sequences_match_docs() {
+        fn hex(bytes: &[u8]) -> String {
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+            hex.join(" ")
+        }
+
+        // These are the examples given in docs/attributes.md.
+        let numbers = NumberSequence {
+            keypoints: vec![
+                NumberSequenceKeypoint::new(0.0, 0.0, 0.0),
+                NumberSequenceKeypoint::new(0.5, 1.0, 0.0),
+                NumberSequenceKeypoint::new(1.0, 1.0, 0.5),
+            ],
+        };
+        let colors = ColorSequence {
+            keypoints: vec![
+                ColorSequenceKeypoint::new(0.0, Color3::new(1.0, 0.0, 0.0)),
+                ColorSequenceKeypoint::new(0.5, Color3::new(0.0, 1.0, 0.0)),
+                ColorSequenceKeypoint::new(1.0, Color3::new(0.0, 0.0, 1.0)),
+            ],
+        };
+
+        let attributes = Attributes::new().with("a", numbers).with("b", colors);
+
+        let mut bytes = Vec::new();
+        attributes.to_writer(&mut bytes).unwrap();
+
+        // Each entry starts with the key and type ID, followed by the number of
+        // keypoints.
+        let numbers_start = 4 + 4 + 1 + 1;
+        let numbers_end = numbers_start + 4 + 3 * 12;
+        let colors_start = numbers_end + 4 + 1 + 1;
+
+        assert_eq!(bytes[numbers_start - 1], 0x17);
+        assert_eq!(
+            hex(&bytes[numbers_start..numbers_end]),
+            "03 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 3f 00 00 80 3f 00 00 00 3f 00 00 80 3f 00 00 80 3f"
+        );
+
+        assert_eq!(bytes[colors_start - 1], 0x19);
+        assert_eq!(
+            hex(&bytes[colors_start..]),
+            "03 00 00 00 00 00 00 00 00 00 00 00 00 00 80 3f 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 3f 00 00 00 00 00 00 80 3f 00 00 00 00 00 00 00 00 00 00 80 3f 00 00 00 00 00 00 00 00 00 00 80 3f"
+        );
+
+        assert_eq!(
+            Attributes::from_reader(bytes.as_slice()).unwrap(),
+            attributes
+        );
+    }
+
+    #[test]
+    fn test_errors_name_key() {
+        fn error_message(list: &[Vec<u8>]) -> String {
+            Attributes::from_reader(entries(list).as_slice())
+                .unwrap_err()
+                .to_string()
+        }
+
+        let good = entry("good", 0x03, &[1]);
+
+        assert_eq!(
+            error_message(&[good.clone(), entry("speed", 0xFF, &[])]),
+            "invalid value type for attribute \"speed\": 255"
+        );
+
+        // The reason the value couldn't be read is the error's source, so
+        // that reporters which print the whole chain only print it once.
+        let error = Attributes::from_reader(
+            entries(&[good.clone(), entry("spawn", 0x11, &[0; 8])]).as_slice(),
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "invalid value for attribute \"spawn\"");
+        assert_eq!(
+            std::error::Error::source(&error).unwrap().to_string(),
+            "couldn't read bytes to deserialize Vector3 Z"
+        );
+
+        let mut missing_type = entry("flag", 0x03, &[]);
+        missing_type.pop();
+        assert_eq!(
+            error_message(&[good, missing_type]),
+            "missing value type for attribute \"flag\""
+        );
+    }
+
+    #[test]
+    fn test_lenient() {
+        let lenient = AttributeDeserializeOptions::new().lenient(true);
+
+        // An unknown BrickColor number can be skipped over on its own.
+        let bytes = entries(&[
+            entry("a", 0x03, &[1]),
+            entry("b", 0x0E, &5000u32.to_le_bytes()),
+            entry("c", 0x03, &[0]),
+        ]);
+        assert!(Attributes::from_reader(bytes.as_slice()).is_err());
+
+        let (attributes, skipped) =
+            Attributes::from_reader_with_options(bytes.as_slice(), &lenient).unwrap();
+        assert_eq!(
+            attributes,
+            Attributes::new().with("a", true).with("c", false)
+        );
+        assert_eq!(skipped, ["b"]);
+
+        // Nothing after a value of an unknown type can be read.
+        let bytes = entries(&[
+            entry("a", 0x03, &[1]),
+            entry("b", 0xFF, &[1, 2, 3]),
+            entry("c", 0x03, &[0]),
+        ]);
+        assert!(Attributes::from_reader(bytes.as_slice()).is_err());
+
+        let (attributes, skipped) =
+            Attributes::from_reader_with_options(bytes.as_slice(), &lenient).unwrap();
+        assert_eq!(attributes, Attributes::new().with("a", true));
+        assert_eq!(skipped, ["b"]);
+
+        // Truncated data is still an error.
+        let bytes = entries(&[entry("a", 0x03, &[1]), entry("b", 0x11, &[0; 4])]);
+        assert!(Attributes::from_reader_with_options(bytes.as_slice(), &lenient).is_err());
+    }
+
     #[test]
     fn test_attribute_removal() {
         let mut attributes = Attributes::new();
         attributes.insert("key".to_owned(), Variant::Bool(true));
         assert_eq!(attributes.remove("key"), Some(Variant::Bool(true)));
     }
+
+    #[test]
+    fn test_diff() {
+        let old = Attributes::new()
+            .with("Kept", 1.0f64)
+            .with("Changed", "before")
+            .with("Removed", true)
+            .with("NotANumber", f64::NAN);
+
+        let new = Attributes::new()
+            .with("Kept", 1.0f64)
+            .with("Changed", "after")
+            .with("Added", Vector3::new(1.0, 2.0, 3.0))
+            .with("NotANumber", f64::NAN);
+
+        let diff = old.diff(&new);
+
+        assert_eq!(
+            diff.added.into_iter().collect::<Vec<_>>(),
+            [("Added".to_owned(), Vector3::new(1.0, 2.0, 3.0).into())]
+        );
+        assert_eq!(
+            diff.removed.into_iter().collect::<Vec<_>>(),
+            [("Removed".to_owned(), Variant::Bool(true))]
+        );
+        assert_eq!(
+            diff.changed.into_iter().collect::<Vec<_>>(),
+            [(
+                "Changed".to_owned(),
+                AttributeChange {
+                    old: "before".into(),
+                    new: "after".into(),
+                }
+            )]
+        );
+
+        assert!(new.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn test_serialized_len() {
+        assert_eq!(Attributes::new().serialized_len(), 0);
+
+        let attributes = Attributes::new()
+            .with("Bool", true)
+            .with("BrickColor", BrickColor::ReallyRed)
+            .with("Color3", Color3::new(1.0, 0.5, 0.0))
+            .with(
+                "ColorSequence",
+                ColorSequence {
+                    keypoints: vec![
+                        ColorSequenceKeypoint::new(0.0, Color3::new(0.0, 0.0, 0.0)),
+                        ColorSequenceKeypoint::new(1.0, Color3::new(1.0, 1.0, 1.0)),
+                    ],
+                },
+            )
+            .with("Float32", 1.0f32)
+            .with("Float64", 1.0f64)
+            .with("Font", Font::default())
+            .with("NumberRange", NumberRange::new(1.0, 2.0))
+            .with(
+                "NumberSequence",
+                NumberSequence {
+                    keypoints: vec![NumberSequenceKeypoint::new(0.0, 1.0, 0.0)],
+                },
+            )
+            .with(
+                "Rect",
+                Rect::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0)),
+            )
+            .with("BinaryString", BinaryString::from(vec![1, 2, 3]))
+            .with("String", "héllo")
+            .with("UDim", UDim::new(1.0, 2))
+            .with("UDim2", UDim2::new(UDim::new(1.0, 2), UDim::new(3.0, 4)))
+            .with("Vector2", Vector2::new(1.0, 2.0))
+            .with("Vector3", Vector3::new(1.0, 2.0, 3.0));
+
+        let mut buffer = Vec::new();
+        attributes.to_writer(&mut buffer).unwrap();
+
+        assert_eq!(attributes.serialized_len(), buffer.len());
+    }
+
+    #[test]
+    fn test_iter_sorted() {
+        let mut attributes = Attributes::new();
+        attributes.insert("b".to_owned(), Variant::Bool(true));
+        attributes.insert("B".to_owned(), Variant::Bool(true));
+        attributes.insert("é".to_owned(), Variant::Bool(true));
+        attributes.insert("a".to_owned(), Variant::Bool(false));
+
+        let keys: Vec<&str> = attributes.iter_sorted().map(|(key, _)| key).collect();
+        assert_eq!(keys, ["B", "a", "b", "é"]);
+    }
+
+    #[test]
+    fn test_try_insert() {
+        let mut attributes = Attributes::new();
+
+        assert!(attributes
+            .try_insert("Health_2".to_owned(), Variant::Float64(100.0))
+            .is_ok());
+        assert!(attributes
+            .try_insert("a".repeat(100), Variant::Bool(true))
+            .is_ok());
+
+        for key in [
+            String::new(),
+            "a".repeat(101),
+            "RBXHealth".to_owned(),
+            "Max Health".to_owned(),
+            "Héalth".to_owned(),
+        ] {
+            let err = attributes
+                .try_insert(key.clone(), Variant::Bool(true))
+                .unwrap_err();
+
+            assert!(err.to_string().contains(&format!("{:?}", key)));
+            assert_eq!(attributes.get(key.as_str()), None);
+        }
+
+        assert_eq!(attributes.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let bytes = entries(&[
+            entry("a", 0x03, &[1]),
+            entry("b", 0x03, &[1]),
+            entry("a", 0x03, &[0]),
+        ]);
+
+        let err = Attributes::from_reader(bytes.as_slice()).unwrap_err();
+        assert_eq!(err.to_string(), "attribute \"a\" appears more than once");
+
+        let lenient = AttributeDeserializeOptions::new().lenient(true);
+        let (attributes, skipped) =
+            Attributes::from_reader_with_options(bytes.as_slice(), &lenient).unwrap();
+        assert_eq!(
+            attributes,
+            Attributes::new().with("a", false).with("b", true)
+        );
+        assert!(skipped.is_empty());
+    }
 }
@@ -4,22 +4,25 @@ use std::{
 };
 
 use crate::{
-    BinaryString, BrickColor, Color3, ColorSequence, ColorSequenceKeypoint, NumberRange,
-    NumberSequence, NumberSequenceKeypoint, Rect, UDim, UDim2, Variant, VariantType, Vector2,
-    Vector3,
+    BinaryString, BrickColor, Color3, ColorSequence, ColorSequenceKeypoint, Font, FontStyle,
+    FontWeight, NumberRange, NumberSequence, NumberSequenceKeypoint, Rect, UDim, UDim2, Variant,
+    VariantType, Vector2, Vector3,
 };
 
-use super::{type_id, AttributeError};
+use super::{type_id, AttributeDeserializeOptions, AttributeError};
 
-/// Reads through an attribute property (AttributesSerialize) and returns a map of attribute names -> values.
+/// Reads through an attribute property (AttributesSerialize) and returns a map of attribute names -> values,
+/// along with the keys of any attributes that were skipped because `options` is lenient.
 pub(crate) fn read_attributes<R: Read>(
     mut value: R,
-) -> Result<BTreeMap<String, Variant>, AttributeError> {
+    options: &AttributeDeserializeOptions,
+) -> Result<(BTreeMap<String, Variant>, Vec<String>), AttributeError> {
     let mut attributes = BTreeMap::new();
+    let mut skipped = Vec::new();
 
     let len = match read_option_u32(&mut value) {
         Ok(Some(len)) => len,
-        Ok(None) => return Ok(attributes),
+        Ok(None) => return Ok((attributes, skipped)),
         Err(_) => return Err(AttributeError::InvalidLength),
     };
 
@@ -27,129 +30,194 @@ pub(crate) fn read_attributes<R: Read>(
         let key_buf = read_string(&mut value).map_err(|_| AttributeError::NoKey)?;
         let key = String::from_utf8(key_buf).map_err(AttributeError::KeyBadUnicode)?;
 
-        let type_id = read_u8(&mut value).map_err(|_| AttributeError::NoValueType)?;
-        let ty =
-            type_id::to_variant_type(type_id).ok_or(AttributeError::InvalidValueType(type_id))?;
+        let type_id =
+            read_u8(&mut value).map_err(|_| AttributeError::NoValueType { key: key.clone() })?;
+        let ty = match type_id::to_variant_type(type_id) {
+            Some(ty) => ty,
 
-        let value = match ty {
-            VariantType::BrickColor => {
-                let color =
-                    read_u32(&mut value).map_err(|_| AttributeError::ReadType("BrickColor"))?;
+            // There's no way to know how long a value of an unknown type is,
+            // so nothing after it can be read.
+            None if options.lenient => {
+                skipped.push(key);
+                break;
+            }
+            None => return Err(AttributeError::InvalidValueType { key, type_id }),
+        };
+
+        match read_value(ty, &mut value) {
+            Ok(value) => {
+                // Duplicate keys mean that whatever wrote the attributes has a
+                // bug, so only lenient mode accepts them, keeping the last one.
+                if attributes.contains_key(&key) {
+                    if !options.lenient {
+                        return Err(AttributeError::DuplicateKey(key));
+                    }
+
+                    log::warn!("Attribute {:?} appears more than once", key);
+                }
 
-                BrickColor::from_number(color as u16)
-                    .ok_or(AttributeError::InvalidBrickColor(color))?
-                    .into()
+                attributes.insert(key, value);
             }
 
-            VariantType::Bool => {
-                (read_u8(&mut value).map_err(|_| AttributeError::ReadType("bool"))? != 0).into()
+            // These values were read in full, but aren't ones we understand.
+            Err(
+                AttributeError::InvalidBrickColor(_)
+                | AttributeError::InvalidFontWeight(_)
+                | AttributeError::InvalidFontStyle(_),
+            ) if options.lenient => skipped.push(key),
+
+            Err(source) => {
+                return Err(AttributeError::InvalidValue {
+                    key,
+                    source: Box::new(source),
+                })
             }
+        }
+    }
+
+    Ok((attributes, skipped))
+}
 
-            VariantType::Color3 => read_color3(&mut value)
-                .map_err(|_| AttributeError::ReadType("Color3"))?
-                .into(),
+/// Reads a single attribute value of the given type.
+fn read_value<R: Read>(ty: VariantType, mut reader: R) -> Result<Variant, AttributeError> {
+    Ok(match ty {
+        VariantType::BrickColor => {
+            let color =
+                read_u32(&mut reader).map_err(|_| AttributeError::ReadType("BrickColor"))?;
 
-            VariantType::ColorSequence => {
-                let size = read_u32(&mut value)
-                    .map_err(|_| AttributeError::ReadType("ColorSequence length"))?;
-                let mut keypoints = Vec::with_capacity(size as usize);
+            BrickColor::from_number(color as u16)
+                .ok_or(AttributeError::InvalidBrickColor(color))?
+                .into()
+        }
 
-                for _ in 0..size {
-                    // `envelope` is always zero and can be ignored.
-                    let _envelope = read_f32(&mut value)
-                        .map_err(|_| AttributeError::ReadType("ColorSequenceKeypoint envelope"))?;
+        VariantType::Bool => {
+            (read_u8(&mut reader).map_err(|_| AttributeError::ReadType("bool"))? != 0).into()
+        }
 
-                    let time = read_f32(&mut value)
-                        .map_err(|_| AttributeError::ReadType("ColorSequenceKeypoint time"))?;
+        VariantType::Color3 => read_color3(&mut reader)
+            .map_err(|_| AttributeError::ReadType("Color3"))?
+            .into(),
 
-                    let color = read_color3(&mut value)
-                        .map_err(|_| AttributeError::ReadType("ColorSequenceKeypoint color"))?;
+        VariantType::ColorSequence => {
+            let size = read_u32(&mut reader)
+                .map_err(|_| AttributeError::ReadType("ColorSequence length"))?;
+            let mut keypoints = Vec::with_capacity(size as usize);
 
-                    keypoints.push(ColorSequenceKeypoint::new(time, color));
-                }
+            for _ in 0..size {
+                // `envelope` is always zero and can be ignored.
+                let _envelope = read_f32(&mut reader)
+                    .map_err(|_| AttributeError::ReadType("ColorSequenceKeypoint envelope"))?;
 
-                ColorSequence { keypoints }.into()
-            }
+                let time = read_f32(&mut reader)
+                    .map_err(|_| AttributeError::ReadType("ColorSequenceKeypoint time"))?;
 
-            VariantType::Float32 => read_f32(&mut value)
-                .map_err(|_| AttributeError::ReadType("float32"))?
-                .into(),
+                let color = read_color3(&mut reader)
+                    .map_err(|_| AttributeError::ReadType("ColorSequenceKeypoint color"))?;
 
-            VariantType::Float64 => read_f64(&mut value)
-                .map_err(|_| AttributeError::ReadType("float64"))?
-                .into(),
+                keypoints.push(ColorSequenceKeypoint::new(time, color));
+            }
+
+            ColorSequence { keypoints }.into()
+        }
 
-            VariantType::NumberRange => NumberRange::new(
-                read_f32(&mut value).map_err(|_| AttributeError::ReadType("NumberRange min"))?,
-                read_f32(&mut value).map_err(|_| AttributeError::ReadType("NumberRange max"))?,
-            )
+        VariantType::Float32 => read_f32(&mut reader)
+            .map_err(|_| AttributeError::ReadType("float32"))?
             .into(),
 
-            VariantType::NumberSequence => {
-                let size = read_u32(&mut value)
-                    .map_err(|_| AttributeError::ReadType("NumberSequence length"))?;
+        VariantType::Float64 => read_f64(&mut reader)
+            .map_err(|_| AttributeError::ReadType("float64"))?
+            .into(),
 
-                let mut keypoints = Vec::with_capacity(size as usize);
+        VariantType::Font => {
+            let weight =
+                read_u16(&mut reader).map_err(|_| AttributeError::ReadType("Font weight"))?;
+            let style = read_u8(&mut reader).map_err(|_| AttributeError::ReadType("Font style"))?;
+            let family = read_string(&mut reader)
+                .ok()
+                .and_then(|family| String::from_utf8(family).ok())
+                .ok_or(AttributeError::ReadType("Font family"))?;
+            let cached_face_id = read_string(&mut reader)
+                .ok()
+                .and_then(|face| String::from_utf8(face).ok())
+                .ok_or(AttributeError::ReadType("Font cached face"))?;
+
+            Font {
+                family,
+                weight: FontWeight::from_u16(weight)
+                    .ok_or(AttributeError::InvalidFontWeight(weight))?,
+                style: FontStyle::from_u8(style).ok_or(AttributeError::InvalidFontStyle(style))?,
+                // Roblox writes an empty string when no face is cached.
+                cached_face_id: Some(cached_face_id).filter(|face| !face.is_empty()),
+            }
+            .into()
+        }
 
-                for _ in 0..size {
-                    let envelope = read_f32(&mut value)
-                        .map_err(|_| AttributeError::ReadType("NumberSequence envelope"))?;
+        VariantType::NumberRange => NumberRange::new(
+            read_f32(&mut reader).map_err(|_| AttributeError::ReadType("NumberRange min"))?,
+            read_f32(&mut reader).map_err(|_| AttributeError::ReadType("NumberRange max"))?,
+        )
+        .into(),
 
-                    let time = read_f32(&mut value)
-                        .map_err(|_| AttributeError::ReadType("NumberSequence time"))?;
+        VariantType::NumberSequence => {
+            let size = read_u32(&mut reader)
+                .map_err(|_| AttributeError::ReadType("NumberSequence length"))?;
 
-                    let value = read_f32(&mut value)
-                        .map_err(|_| AttributeError::ReadType("NumberSequence value"))?;
+            let mut keypoints = Vec::with_capacity(size as usize);
 
-                    keypoints.push(NumberSequenceKeypoint::new(time, value, envelope));
-                }
+            for _ in 0..size {
+                let envelope = read_f32(&mut reader)
+                    .map_err(|_| AttributeError::ReadType("NumberSequence envelope"))?;
 
-                NumberSequence { keypoints }.into()
-            }
+                let time = read_f32(&mut reader)
+                    .map_err(|_| AttributeError::ReadType("NumberSequence time"))?;
 
-            VariantType::Rect => Rect::new(
-                read_vector2(&mut value).map_err(|_| AttributeError::ReadType("Rect min"))?,
-                read_vector2(&mut value).map_err(|_| AttributeError::ReadType("Rect max"))?,
-            )
-            .into(),
+                let value = read_f32(&mut reader)
+                    .map_err(|_| AttributeError::ReadType("NumberSequence value"))?;
 
-            VariantType::BinaryString => {
-                let binary_string: BinaryString = read_string(&mut value)
-                    .map_err(|_| AttributeError::ReadType("string"))?
-                    .into();
-                binary_string.into()
+                keypoints.push(NumberSequenceKeypoint::new(time, value, envelope));
             }
 
-            VariantType::UDim => read_udim(&mut value)
-                .map_err(|_| AttributeError::ReadType("UDim"))?
-                .into(),
-
-            VariantType::UDim2 => UDim2::new(
-                read_udim(&mut value).map_err(|_| AttributeError::ReadType("UDim2 X"))?,
-                read_udim(&mut value).map_err(|_| AttributeError::ReadType("UDim2 Y"))?,
-            )
-            .into(),
+            NumberSequence { keypoints }.into()
+        }
 
-            VariantType::Vector2 => Vector2::new(
-                read_f32(&mut value).map_err(|_| AttributeError::ReadType("Vector2 X"))?,
-                read_f32(&mut value).map_err(|_| AttributeError::ReadType("Vector2 Y"))?,
-            )
-            .into(),
+        VariantType::Rect => Rect::new(
+            read_vector2(&mut reader).map_err(|_| AttributeError::ReadType("Rect min"))?,
+            read_vector2(&mut reader).map_err(|_| AttributeError::ReadType("Rect max"))?,
+        )
+        .into(),
+
+        VariantType::BinaryString => {
+            let binary_string: BinaryString = read_string(&mut reader)
+                .map_err(|_| AttributeError::ReadType("string"))?
+                .into();
+            binary_string.into()
+        }
 
-            VariantType::Vector3 => Vector3::new(
-                read_f32(&mut value).map_err(|_| AttributeError::ReadType("Vector3 X"))?,
-                read_f32(&mut value).map_err(|_| AttributeError::ReadType("Vector3 Y"))?,
-                read_f32(&mut value).map_err(|_| AttributeError::ReadType("Vector3 Z"))?,
-            )
+        VariantType::UDim => read_udim(&mut reader)
+            .map_err(|_| AttributeError::ReadType("UDim"))?
             .into(),
 
-            other => return Err(AttributeError::UnsupportedVariantType(other)),
-        };
-
-        attributes.insert(key, value);
-    }
-
-    Ok(attributes)
+        VariantType::UDim2 => UDim2::new(
+            read_udim(&mut reader).map_err(|_| AttributeError::ReadType("UDim2 X"))?,
+            read_udim(&mut reader).map_err(|_| AttributeError::ReadType("UDim2 Y"))?,
+        )
+        .into(),
+
+        VariantType::Vector2 => Vector2::new(
+            read_f32(&mut reader).map_err(|_| AttributeError::ReadType("Vector2 X"))?,
+            read_f32(&mut reader).map_err(|_| AttributeError::ReadType("Vector2 Y"))?,
+        )
+        .into(),
+
+        VariantType::Vector3 => Vector3::new(
+            read_f32(&mut reader).map_err(|_| AttributeError::ReadType("Vector3 X"))?,
+            read_f32(&mut reader).map_err(|_| AttributeError::ReadType("Vector3 Y"))?,
+            read_f32(&mut reader).map_err(|_| AttributeError::ReadType("Vector3 Z"))?,
+        )
+        .into(),
+
+        other => return Err(AttributeError::UnsupportedVariantType(other)),
+    })
 }
 
 fn read_u8<R: Read>(mut reader: R) -> io::Result<u8> {
@@ -158,6 +226,12 @@ fn read_u8<R: Read>(mut reader: R) -> io::Result<u8> {
     Ok(bytes[0])
 }
 
+fn read_u16<R: Read>(mut reader: R) -> io::Result<u16> {
+    let mut bytes = [0u8; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
 fn read_i32<R: Read>(mut reader: R) -> io::Result<i32> {
     let mut bytes = [0u8; 4];
     reader.read_exact(&mut bytes)?;
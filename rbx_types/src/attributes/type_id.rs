@@ -6,7 +6,9 @@ macro_rules! type_ids {
             match ty {
                 $( VariantType::$ty => Some($id), )*
 
-                VariantType::String => Some(0x02),
+                // Roblox has no Content attributes, so asset URLs are stored as
+                // strings, the same as they are when set from Lua.
+                VariantType::String | VariantType::Content => Some(0x02),
                 _ => None,
             }
         }
@@ -49,4 +51,9 @@ type_ids! {
     // ??? => 0x1A,
     NumberRange => 0x1B,
     Rect => 0x1C,
+    // ??? => 0x1D,
+    // ??? => 0x1E,
+    // ??? => 0x1F,
+    // ??? => 0x20,
+    Font => 0x21,
 }
@@ -1,5 +1,4 @@
 use std::{
-    borrow::Borrow,
     collections::BTreeMap,
     io::{self, Write},
 };
@@ -12,6 +11,7 @@ use crate::{
 };
 
 /// Writes the attribute property (AttributesSerialize) from a map of attribute names -> values.
+/// Entries are written in the map's order, which is sorted by key.
 pub(crate) fn write_attributes<W: Write>(
     map: &BTreeMap<String, Variant>,
     mut writer: W,
@@ -23,9 +23,7 @@ pub(crate) fn write_attributes<W: Write>(
     writer.write_all(&(map.len() as u32).to_le_bytes())?;
 
     for (name, variant) in map {
-        let variant = variant.borrow();
-
-        write_string(&mut writer, &name)?;
+        write_string(&mut writer, name)?;
 
         let type_id = type_id::from_variant_type(variant.ty())
             .ok_or_else(|| AttributeError::UnsupportedVariantType(variant.ty()))?;
@@ -46,6 +44,12 @@ pub(crate) fn write_attributes<W: Write>(
             }
             Variant::Float32(float) => write_f32(&mut writer, *float)?,
             Variant::Float64(float) => write_f64(&mut writer, *float)?,
+            Variant::Font(font) => {
+                writer.write_all(&font.weight.as_u16().to_le_bytes())?;
+                writer.write_all(&[font.style.as_u8()])?;
+                write_string(&mut writer, &font.family)?;
+                write_string(&mut writer, font.cached_face_id.as_deref().unwrap_or(""))?
+            }
             Variant::NumberRange(range) => {
                 write_f32(&mut writer, range.min)?;
                 write_f32(&mut writer, range.max)?;
@@ -65,6 +69,7 @@ pub(crate) fn write_attributes<W: Write>(
             }
             Variant::BinaryString(string) => write_string(&mut writer, string)?,
             Variant::String(string) => write_string(&mut writer, string)?,
+            Variant::Content(content) => write_string(&mut writer, AsRef::<str>::as_ref(content))?,
             Variant::UDim(udim) => write_udim(&mut writer, *udim)?,
             Variant::UDim2(udim2) => {
                 write_udim(&mut writer, udim2.x)?;
@@ -84,6 +89,55 @@ pub(crate) fn write_attributes<W: Write>(
     Ok(())
 }
 
+/// Computes the number of bytes that `write_attributes` would write for the
+/// given map. Attributes whose values can't be written aren't counted.
+pub(crate) fn attributes_len(map: &BTreeMap<String, Variant>) -> usize {
+    if map.is_empty() {
+        return 0;
+    }
+
+    let entries: usize = map
+        .iter()
+        .filter(|(_, variant)| type_id::from_variant_type(variant.ty()).is_some())
+        .map(|(name, variant)| string_len(name) + 1 + value_len(variant))
+        .sum();
+
+    4 + entries
+}
+
+/// The number of bytes that the value of an attribute takes up, not including
+/// its type ID. This needs to be kept in sync with `write_attributes`.
+fn value_len(variant: &Variant) -> usize {
+    match variant {
+        Variant::Bool(_) => 1,
+        Variant::BrickColor(_) => 4,
+        Variant::Color3(_) => 12,
+        Variant::ColorSequence(sequence) => 4 + sequence.keypoints.len() * 20,
+        Variant::Float32(_) => 4,
+        Variant::Float64(_) => 8,
+        Variant::Font(font) => {
+            2 + 1
+                + string_len(&font.family)
+                + string_len(font.cached_face_id.as_deref().unwrap_or(""))
+        }
+        Variant::NumberRange(_) => 8,
+        Variant::NumberSequence(sequence) => 4 + sequence.keypoints.len() * 12,
+        Variant::Rect(_) => 16,
+        Variant::BinaryString(string) => string_len(string),
+        Variant::String(string) => string_len(string),
+        Variant::Content(content) => string_len(AsRef::<str>::as_ref(content)),
+        Variant::UDim(_) => 8,
+        Variant::UDim2(_) => 16,
+        Variant::Vector2(_) => 8,
+        Variant::Vector3(_) => 12,
+        _ => 0,
+    }
+}
+
+fn string_len<T: AsRef<[u8]>>(string: T) -> usize {
+    4 + string.as_ref().len()
+}
+
 fn write_f32<W: Write>(mut writer: W, n: f32) -> io::Result<()> {
     writer.write_all(&n.to_le_bytes()[..])
 }
@@ -163,6 +163,23 @@ mod serde_impl {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bit_order() {
+        assert_eq!(Axes::X.bits(), 1);
+        assert_eq!(Axes::Y.bits(), 2);
+        assert_eq!(Axes::Z.bits(), 4);
+
+        assert_eq!(Axes::all().bits(), 0b111);
+        let xz = Axes::from_bits(0b101).unwrap();
+        assert!(xz.contains(Axes::X) && !xz.contains(Axes::Y) && xz.contains(Axes::Z));
+        assert_eq!(Axes::from_bits(0b1000), None);
+    }
+}
+
 #[cfg(all(test, feature = "serde"))]
 mod serde_test {
     use super::*;
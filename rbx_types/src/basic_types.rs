@@ -1,3 +1,7 @@
+use std::ops::Mul;
+
+use crate::BrickColor;
+
 /// Represents any Roblox enum value.
 ///
 /// Roblox enums are not strongly typed, so the meaning of a value depends on
@@ -75,9 +79,9 @@ pub struct Vector3 {
 }
 
 fn approx_unit_or_zero(value: f32) -> Option<i32> {
-    if value.abs() <= std::f32::EPSILON {
+    if value.abs() <= f32::EPSILON {
         Some(0)
-    } else if value.abs() - 1.0 <= std::f32::EPSILON {
+    } else if value.abs() - 1.0 <= f32::EPSILON {
         Some(1.0f32.copysign(value) as i32)
     } else {
         None
@@ -166,6 +170,36 @@ impl CFrame {
             orientation,
         }
     }
+
+    /// Returns the inverse of this `CFrame`, assuming that its orientation is
+    /// a pure rotation.
+    pub fn inverse(&self) -> Self {
+        let orientation = self.orientation.transpose();
+        let position = orientation * self.position;
+
+        Self {
+            position: Vector3::new(-position.x, -position.y, -position.z),
+            orientation,
+        }
+    }
+}
+
+impl Mul for CFrame {
+    type Output = Self;
+
+    /// Composes two `CFrame` values, like `a * b` does in Roblox.
+    fn mul(self, rhs: Self) -> Self {
+        let offset = self.orientation * rhs.position;
+
+        Self {
+            position: Vector3::new(
+                self.position.x + offset.x,
+                self.position.y + offset.y,
+                self.position.z + offset.z,
+            ),
+            orientation: self.orientation * rhs.orientation,
+        }
+    }
 }
 
 /// Used to represent the `orientation` field of `CFrame` and not a standalone
@@ -199,6 +233,34 @@ impl Matrix3 {
     }
 }
 
+impl Mul<Vector3> for Matrix3 {
+    type Output = Vector3;
+
+    fn mul(self, rhs: Vector3) -> Vector3 {
+        let dot = |row: Vector3| row.x * rhs.x + row.y * rhs.y + row.z * rhs.z;
+
+        Vector3::new(dot(self.x), dot(self.y), dot(self.z))
+    }
+}
+
+impl Mul for Matrix3 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        // Each column of the product is this matrix applied to the matching
+        // column of `rhs`, so the rows of the transposed product are easy to
+        // build.
+        let columns = rhs.transpose();
+
+        Self {
+            x: self * columns.x,
+            y: self * columns.y,
+            z: self * columns.z,
+        }
+        .transpose()
+    }
+}
+
 /// Represents any color, including HDR colors.
 ///
 /// ## See Also
@@ -216,6 +278,12 @@ impl Color3 {
     pub fn new(r: f32, g: f32, b: f32) -> Self {
         Self { r, g, b }
     }
+
+    /// Returns the color of the BrickColor with the given number, if one
+    /// exists. See [`BrickColor::to_color3`].
+    pub fn from_brick_color(value: u16) -> Option<Self> {
+        BrickColor::from_number(value).map(BrickColor::to_color3)
+    }
 }
 
 impl From<Color3uint8> for Color3 {
@@ -254,9 +322,9 @@ impl Color3uint8 {
 impl From<Color3> for Color3uint8 {
     fn from(value: Color3) -> Self {
         Self {
-            r: ((value.r.max(0.0).min(1.0)) * 255.0).round() as u8,
-            g: ((value.g.max(0.0).min(1.0)) * 255.0).round() as u8,
-            b: ((value.b.max(0.0).min(1.0)) * 255.0).round() as u8,
+            r: (value.r.clamp(0.0, 1.0) * 255.0).round() as u8,
+            g: (value.g.clamp(0.0, 1.0) * 255.0).round() as u8,
+            b: (value.b.clamp(0.0, 1.0) * 255.0).round() as u8,
         }
     }
 }
@@ -301,6 +369,28 @@ impl Region3 {
     pub fn new(min: Vector3, max: Vector3) -> Self {
         Self { min, max }
     }
+
+    /// Returns the size of the region along each axis, like `Region3.Size`
+    /// does in Roblox.
+    pub fn size(&self) -> Vector3 {
+        Vector3::new(
+            self.max.x - self.min.x,
+            self.max.y - self.min.y,
+            self.max.z - self.min.z,
+        )
+    }
+
+    /// Returns an unrotated `CFrame` at the center of the region, like
+    /// `Region3.CFrame` does in Roblox.
+    pub fn cframe(&self) -> CFrame {
+        let center = Vector3::new(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+            (self.min.z + self.max.z) / 2.0,
+        );
+
+        CFrame::new(center, Matrix3::identity())
+    }
 }
 
 /// A version of [`Region3`][Region3] that uses signed 16-bit integers instead
@@ -492,6 +582,22 @@ serde_tuple! {
     Matrix3(x: Vector3, y: Vector3, z: Vector3),
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn region3_accessors() {
+        let region = Region3::new(Vector3::new(-1.0, 0.0, 2.0), Vector3::new(3.0, 4.0, 2.0));
+
+        assert_eq!(region.size(), Vector3::new(4.0, 4.0, 0.0));
+        assert_eq!(
+            region.cframe(),
+            CFrame::new(Vector3::new(1.0, 2.0, 2.0), Matrix3::identity())
+        );
+    }
+}
+
 #[cfg(all(test, feature = "serde"))]
 mod serde_test {
     use super::*;
@@ -1,5 +1,7 @@
 use std::fmt;
 
+use crate::{Color3, Color3uint8};
+
 macro_rules! make_brick_color {
     ({
         $([
@@ -62,6 +64,57 @@ macro_rules! make_brick_color {
                     _ => None,
                 }
             }
+
+            /// Finds the BrickColor whose color is closest to the given one,
+            /// measured by squared distance in RGB space. When several are
+            /// equally close, the one with the lowest number wins.
+            ///
+            /// This is useful for converting a part's `Color` back into a
+            /// `BrickColor` for older formats.
+            pub fn from_color3_nearest(color: Color3) -> BrickColor {
+                const ALL: &[BrickColor] = &[$(BrickColor::$enum,)+];
+
+                let distance = |brick_color: BrickColor| {
+                    let other = brick_color.to_color3();
+                    let (r, g, b) = (color.r - other.r, color.g - other.g, color.b - other.b);
+
+                    r * r + g * g + b * b
+                };
+
+                // The palette is sorted by number, so only replacing the best
+                // color when another is strictly closer breaks ties in favor
+                // of the lowest number.
+                let mut best = ALL[0];
+                let mut best_distance = distance(best);
+
+                for &brick_color in &ALL[1..] {
+                    let brick_distance = distance(brick_color);
+
+                    if brick_distance < best_distance {
+                        best = brick_color;
+                        best_distance = brick_distance;
+                    }
+                }
+
+                best
+            }
+
+            /// Returns the color that this BrickColor stands for, which is
+            /// what Roblox uses when converting a part's `BrickColor` into
+            /// its `Color`.
+            pub fn to_color3uint8(self) -> Color3uint8 {
+                match self {
+                    $(
+                        BrickColor::$enum => Color3uint8::new($color3_r, $color3_g, $color3_b),
+                    )+
+                }
+            }
+
+            /// Returns the color that this BrickColor stands for as a
+            /// `Color3`. See [`BrickColor::to_color3uint8`].
+            pub fn to_color3(self) -> Color3 {
+                self.to_color3uint8().into()
+            }
         }
 
         impl fmt::Display for BrickColor {
@@ -335,6 +388,50 @@ mod tests {
     fn from_number() {
         assert_eq!(BrickColor::from_number(1030), Some(BrickColor::PastelBrown));
     }
+
+    #[test]
+    fn to_color3() {
+        assert_eq!(
+            BrickColor::ReallyRed.to_color3uint8(),
+            Color3uint8::new(255, 0, 0)
+        );
+        assert_eq!(
+            BrickColor::MediumStoneGrey.to_color3uint8(),
+            Color3uint8::new(163, 162, 165)
+        );
+        assert_eq!(
+            BrickColor::PastelBrown.to_color3(),
+            Color3uint8::new(255, 204, 153).into()
+        );
+
+        assert_eq!(
+            Color3::from_brick_color(1004),
+            Some(Color3::new(1.0, 0.0, 0.0))
+        );
+        assert_eq!(Color3::from_brick_color(4), None);
+    }
+
+    #[test]
+    fn from_color3_nearest() {
+        // Every BrickColor is the nearest BrickColor to its own color.
+        for number in 0..=u16::MAX {
+            if let Some(brick_color) = BrickColor::from_number(number) {
+                assert_eq!(
+                    BrickColor::from_color3_nearest(brick_color.to_color3()),
+                    brick_color
+                );
+            }
+        }
+
+        assert_eq!(
+            BrickColor::from_color3_nearest(Color3::new(0.95, 0.05, 0.0)),
+            BrickColor::ReallyRed
+        );
+        assert_eq!(
+            BrickColor::from_color3_nearest(Color3::new(0.0, 0.0, 0.0)),
+            BrickColor::ReallyBlack
+        );
+    }
 }
 
 #[cfg(all(test, feature = "serde"))]
@@ -21,8 +21,47 @@ impl Content {
     pub fn into_string(self) -> String {
         self.url
     }
+
+    /// Returns the ID of the asset this `Content` points to, if it is in one
+    /// of the forms Roblox uses for asset IDs:
+    ///
+    /// * `rbxassetid://12345`
+    /// * `http://www.roblox.com/asset/?id=12345`, or any other URL with an
+    ///   `id` query parameter
+    pub fn asset_id(&self) -> Option<AssetId> {
+        let url = self.url.trim();
+
+        if let Some(scheme) = url.get(..ASSET_ID_SCHEME.len()) {
+            if scheme.eq_ignore_ascii_case(ASSET_ID_SCHEME) {
+                return url[ASSET_ID_SCHEME.len()..].parse().ok().map(AssetId);
+            }
+        }
+
+        let (_, query) = url.split_once('?')?;
+        query.split('&').find_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+
+            if key.eq_ignore_ascii_case("id") {
+                value.parse().ok().map(AssetId)
+            } else {
+                None
+            }
+        })
+    }
 }
 
+const ASSET_ID_SCHEME: &str = "rbxassetid://";
+
+/// The numeric ID of a Roblox asset, like the `12345` in
+/// `rbxassetid://12345`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct AssetId(pub u64);
+
 impl From<String> for Content {
     fn from(url: String) -> Self {
         Self { url }
@@ -60,3 +99,29 @@ impl AsMut<String> for Content {
         &mut self.url
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn asset_id() {
+        let id = |url: &str| Content::from(url).asset_id();
+
+        assert_eq!(id("rbxassetid://12345"), Some(AssetId(12345)));
+        assert_eq!(id("RBXASSETID://12345"), Some(AssetId(12345)));
+        assert_eq!(
+            id("http://www.roblox.com/asset/?id=12345"),
+            Some(AssetId(12345))
+        );
+        assert_eq!(
+            id("https://www.roblox.com/asset/?version=1&id=12345"),
+            Some(AssetId(12345))
+        );
+
+        assert_eq!(id(""), None);
+        assert_eq!(id("rbxasset://textures/face.png"), None);
+        assert_eq!(id("rbxassetid://not-a-number"), None);
+        assert_eq!(id("rbxassetid:/\u{e9}12345"), None);
+    }
+}
@@ -1,6 +1,6 @@
 use thiserror::Error;
 
-use crate::AttributeError;
+use crate::{material_colors::MaterialColorsError, AttributeError};
 
 /// Represents an error that occurred when using a fallible method.
 #[derive(Debug, Error)]
@@ -17,8 +17,19 @@ impl From<AttributeError> for Error {
     }
 }
 
+impl From<MaterialColorsError> for Error {
+    fn from(source: MaterialColorsError) -> Self {
+        Self {
+            source: Box::new(source.into()),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 enum InnerError {
     #[error(transparent)]
     AttributeError(#[from] AttributeError),
+
+    #[error(transparent)]
+    MaterialColorsError(#[from] MaterialColorsError),
 }
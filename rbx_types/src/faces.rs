@@ -205,6 +205,37 @@ mod serde_impl {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Vector3;
+
+    /// Each face is stored as the bit whose index is the face's `NormalId`,
+    /// matching Roblox's Right, Top, Back, Left, Bottom, Front order.
+    #[test]
+    fn bit_order() {
+        let faces = [
+            (Faces::RIGHT, Vector3::new(1.0, 0.0, 0.0)),
+            (Faces::TOP, Vector3::new(0.0, 1.0, 0.0)),
+            (Faces::BACK, Vector3::new(0.0, 0.0, 1.0)),
+            (Faces::LEFT, Vector3::new(-1.0, 0.0, 0.0)),
+            (Faces::BOTTOM, Vector3::new(0.0, -1.0, 0.0)),
+            (Faces::FRONT, Vector3::new(0.0, 0.0, -1.0)),
+        ];
+
+        for (face, normal) in faces {
+            let normal_id = normal.to_normal_id().unwrap();
+
+            assert_eq!(face.bits(), 1 << normal_id, "{:?}", face);
+            assert_eq!(Faces::from_bits(face.bits()), Some(face));
+        }
+
+        assert_eq!(Faces::all().bits(), 0b11_1111);
+        assert_eq!(Faces::from_bits(0b100_0000), None);
+    }
+}
+
 #[cfg(all(test, feature = "serde"))]
 mod serde_test {
     use super::*;
@@ -0,0 +1,124 @@
+/// The weight of a [`Font`], from thinnest to heaviest.
+///
+/// ## See Also
+/// * [FontWeight on Roblox Developer Hub](https://create.roblox.com/docs/reference/engine/enums/FontWeight)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FontWeight {
+    Thin,
+    ExtraLight,
+    Light,
+    #[default]
+    Regular,
+    Medium,
+    SemiBold,
+    Bold,
+    ExtraBold,
+    Heavy,
+}
+
+impl FontWeight {
+    /// Converts a numeric weight, like `400` for `Regular`, into a
+    /// `FontWeight`. Returns `None` for weights that Roblox doesn't define.
+    pub fn from_u16(weight: u16) -> Option<Self> {
+        Some(match weight {
+            100 => FontWeight::Thin,
+            200 => FontWeight::ExtraLight,
+            300 => FontWeight::Light,
+            400 => FontWeight::Regular,
+            500 => FontWeight::Medium,
+            600 => FontWeight::SemiBold,
+            700 => FontWeight::Bold,
+            800 => FontWeight::ExtraBold,
+            900 => FontWeight::Heavy,
+            _ => return None,
+        })
+    }
+
+    /// Returns the numeric weight of this `FontWeight`.
+    pub fn as_u16(self) -> u16 {
+        match self {
+            FontWeight::Thin => 100,
+            FontWeight::ExtraLight => 200,
+            FontWeight::Light => 300,
+            FontWeight::Regular => 400,
+            FontWeight::Medium => 500,
+            FontWeight::SemiBold => 600,
+            FontWeight::Bold => 700,
+            FontWeight::ExtraBold => 800,
+            FontWeight::Heavy => 900,
+        }
+    }
+}
+
+/// The style of a [`Font`].
+///
+/// ## See Also
+/// * [FontStyle on Roblox Developer Hub](https://create.roblox.com/docs/reference/engine/enums/FontStyle)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FontStyle {
+    #[default]
+    Normal,
+    Italic,
+}
+
+impl FontStyle {
+    /// Converts the number Roblox uses for a style into a `FontStyle`.
+    /// Returns `None` for styles that Roblox doesn't define.
+    pub fn from_u8(style: u8) -> Option<Self> {
+        match style {
+            0 => Some(FontStyle::Normal),
+            1 => Some(FontStyle::Italic),
+            _ => None,
+        }
+    }
+
+    /// Returns the number Roblox uses for this `FontStyle`.
+    pub fn as_u8(self) -> u8 {
+        match self {
+            FontStyle::Normal => 0,
+            FontStyle::Italic => 1,
+        }
+    }
+}
+
+/// A font face, made of a font family along with a weight and style.
+///
+/// ## See Also
+/// * [Font on Roblox Developer Hub](https://create.roblox.com/docs/reference/engine/datatypes/Font)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct Font {
+    /// The content URL of the font family, like
+    /// `rbxasset://fonts/families/SourceSansPro.json`.
+    pub family: String,
+    pub weight: FontWeight,
+    pub style: FontStyle,
+
+    /// The content URL of the font file that Roblox last resolved this face
+    /// to, if any. Roblox fills this in itself, so it's safe to leave empty.
+    pub cached_face_id: Option<String>,
+}
+
+impl Font {
+    /// Creates a regular, non-italic `Font` from the given family.
+    pub fn new<S: Into<String>>(family: S) -> Self {
+        Self {
+            family: family.into(),
+            weight: FontWeight::default(),
+            style: FontStyle::default(),
+            cached_face_id: None,
+        }
+    }
+}
+
+impl Default for Font {
+    fn default() -> Self {
+        Self::new("rbxasset://fonts/families/SourceSansPro.json")
+    }
+}
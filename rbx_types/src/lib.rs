@@ -2,6 +2,7 @@
 #[macro_use]
 mod serde_util;
 
+mod approx_eq;
 mod attributes;
 mod axes;
 mod basic_types;
@@ -10,12 +11,18 @@ mod brick_color;
 mod content;
 mod error;
 mod faces;
+mod font;
 mod lister;
+mod material;
+mod material_colors;
 mod physical_properties;
+mod protected_string;
 mod referent;
 mod shared_string;
 mod tags;
+mod unique_id;
 mod variant;
+mod variant_key;
 
 pub use attributes::*;
 pub use axes::*;
@@ -25,8 +32,14 @@ pub use brick_color::*;
 pub use content::*;
 pub use error::*;
 pub use faces::*;
+pub use font::*;
+pub use material::*;
+pub use material_colors::*;
 pub use physical_properties::*;
+pub use protected_string::*;
 pub use referent::*;
 pub use shared_string::*;
 pub use tags::*;
+pub use unique_id::*;
 pub use variant::*;
+pub use variant_key::*;
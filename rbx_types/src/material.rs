@@ -0,0 +1,261 @@
+use crate::{CustomPhysicalProperties, Enum, PhysicalProperties};
+
+/// The material of a part, which decides how it looks and, unless the part has
+/// custom physical properties, how it behaves.
+///
+/// ## See Also
+/// * [Material on Roblox Developer Hub](https://create.roblox.com/docs/reference/engine/enums/Material)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Material {
+    Plastic,
+    SmoothPlastic,
+    Neon,
+    Wood,
+    WoodPlanks,
+    Marble,
+    Basalt,
+    Slate,
+    CrackedLava,
+    Concrete,
+    Limestone,
+    Granite,
+    Pavement,
+    Brick,
+    Pebble,
+    Cobblestone,
+    Rock,
+    Sandstone,
+    CorrodedMetal,
+    DiamondPlate,
+    Foil,
+    Metal,
+    Grass,
+    LeafyGrass,
+    Sand,
+    Fabric,
+    Snow,
+    Mud,
+    Ground,
+    Asphalt,
+    Salt,
+    Ice,
+    Glacier,
+    Glass,
+    ForceField,
+    Air,
+    Water,
+}
+
+/// Shorthand for writing out `MATERIALS`.
+const fn physics(
+    density: f32,
+    friction: f32,
+    elasticity: f32,
+    friction_weight: f32,
+    elasticity_weight: f32,
+) -> CustomPhysicalProperties {
+    CustomPhysicalProperties {
+        density,
+        friction,
+        elasticity,
+        friction_weight,
+        elasticity_weight,
+    }
+}
+
+/// Every material, along with the value Roblox gives it in the `Material`
+/// enum and the physical properties that parts made of it have by default.
+static MATERIALS: [(Material, u32, CustomPhysicalProperties); 37] = [
+    (Material::Plastic, 256, physics(0.7, 0.3, 0.5, 1.0, 1.0)),
+    (
+        Material::SmoothPlastic,
+        272,
+        physics(0.7, 0.2, 0.5, 1.0, 1.0),
+    ),
+    (Material::Neon, 288, physics(0.7, 0.3, 0.2, 1.0, 1.0)),
+    (Material::Wood, 512, physics(0.35, 0.48, 0.2, 1.0, 1.0)),
+    (
+        Material::WoodPlanks,
+        528,
+        physics(0.35, 0.48, 0.2, 1.0, 1.0),
+    ),
+    (Material::Marble, 784, physics(2.563, 0.2, 0.17, 1.0, 1.0)),
+    (Material::Basalt, 788, physics(2.691, 0.7, 0.15, 0.3, 1.0)),
+    (Material::Slate, 800, physics(2.691, 0.4, 0.2, 1.0, 1.0)),
+    (
+        Material::CrackedLava,
+        804,
+        physics(2.691, 0.65, 0.15, 1.0, 1.0),
+    ),
+    (Material::Concrete, 816, physics(2.403, 0.7, 0.2, 0.3, 1.0)),
+    (
+        Material::Limestone,
+        820,
+        physics(2.691, 0.5, 0.15, 1.0, 1.0),
+    ),
+    (Material::Granite, 832, physics(2.691, 0.4, 0.2, 1.0, 1.0)),
+    (Material::Pavement, 836, physics(2.691, 0.5, 0.17, 0.3, 1.0)),
+    (Material::Brick, 848, physics(1.92, 0.8, 0.15, 0.3, 1.0)),
+    (Material::Pebble, 864, physics(2.403, 0.4, 0.17, 1.0, 1.0)),
+    (
+        Material::Cobblestone,
+        880,
+        physics(2.691, 0.5, 0.17, 1.0, 1.0),
+    ),
+    (Material::Rock, 896, physics(2.691, 0.5, 0.17, 1.0, 1.0)),
+    (
+        Material::Sandstone,
+        912,
+        physics(2.691, 0.5, 0.15, 5.0, 1.0),
+    ),
+    (
+        Material::CorrodedMetal,
+        1040,
+        physics(7.85, 0.7, 0.2, 1.0, 1.0),
+    ),
+    (
+        Material::DiamondPlate,
+        1056,
+        physics(7.85, 0.35, 0.25, 1.0, 1.0),
+    ),
+    (Material::Foil, 1072, physics(2.7, 0.4, 0.25, 1.0, 1.0)),
+    (Material::Metal, 1088, physics(7.85, 0.4, 0.25, 1.0, 1.0)),
+    (Material::Grass, 1280, physics(0.9, 0.4, 0.1, 1.0, 1.5)),
+    (Material::LeafyGrass, 1284, physics(0.9, 0.4, 0.1, 2.0, 2.0)),
+    (Material::Sand, 1296, physics(1.6, 0.5, 0.05, 5.0, 2.5)),
+    (Material::Fabric, 1312, physics(0.7, 0.35, 0.05, 1.0, 1.0)),
+    (Material::Snow, 1328, physics(0.9, 0.3, 0.03, 3.0, 4.0)),
+    (Material::Mud, 1344, physics(0.9, 0.3, 0.07, 3.0, 4.0)),
+    (Material::Ground, 1360, physics(0.9, 0.45, 0.1, 1.0, 1.0)),
+    (Material::Asphalt, 1376, physics(2.36, 0.8, 0.2, 0.3, 1.0)),
+    (Material::Salt, 1392, physics(2.16, 0.5, 0.05, 1.0, 1.0)),
+    (Material::Ice, 1536, physics(0.919, 0.02, 0.15, 3.0, 1.0)),
+    (
+        Material::Glacier,
+        1552,
+        physics(0.919, 0.05, 0.15, 2.0, 1.0),
+    ),
+    (Material::Glass, 1568, physics(2.4, 0.25, 0.2, 1.0, 1.0)),
+    (
+        Material::ForceField,
+        1584,
+        physics(2.4, 0.25, 0.2, 1.0, 1.0),
+    ),
+    (Material::Air, 1792, physics(0.01, 0.01, 0.01, 1.0, 1.0)),
+    (Material::Water, 2048, physics(1.0, 0.0, 0.0, 1.0, 1.0)),
+];
+
+impl Material {
+    /// Converts a value of Roblox's `Material` enum, like `256` for
+    /// `Plastic`, into a `Material`. Returns `None` for values that aren't
+    /// known.
+    pub fn from_u32(value: u32) -> Option<Self> {
+        MATERIALS
+            .iter()
+            .find(|(_, material_value, _)| *material_value == value)
+            .map(|(material, _, _)| *material)
+    }
+
+    /// Returns the value of this material in Roblox's `Material` enum.
+    pub fn to_u32(self) -> u32 {
+        self.entry().1
+    }
+
+    /// The physical properties that a part made of this material has when
+    /// its `CustomPhysicalProperties` are [`PhysicalProperties::Default`].
+    pub fn default_physical_properties(self) -> CustomPhysicalProperties {
+        self.entry().2
+    }
+
+    fn entry(self) -> &'static (Material, u32, CustomPhysicalProperties) {
+        MATERIALS
+            .iter()
+            .find(|(material, _, _)| *material == self)
+            .expect("every Material should be in MATERIALS")
+    }
+}
+
+impl From<Material> for Enum {
+    fn from(material: Material) -> Self {
+        Enum::from_u32(material.to_u32())
+    }
+}
+
+impl PhysicalProperties {
+    /// Returns the physical properties that a part made of `material` with
+    /// these properties actually has: the custom properties if there are any,
+    /// or the material's defaults otherwise.
+    pub fn resolve(&self, material: Material) -> CustomPhysicalProperties {
+        match self {
+            PhysicalProperties::Default => material.default_physical_properties(),
+            PhysicalProperties::Custom(custom) => *custom,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn table_is_consistent() {
+        let materials: HashSet<_> = MATERIALS.iter().map(|(material, _, _)| material).collect();
+        let values: HashSet<_> = MATERIALS.iter().map(|(_, value, _)| value).collect();
+        assert_eq!(materials.len(), MATERIALS.len());
+        assert_eq!(values.len(), MATERIALS.len());
+
+        for &(material, value, properties) in &MATERIALS {
+            assert_eq!(Material::from_u32(value), Some(material));
+            assert_eq!(material.to_u32(), value);
+            assert_eq!(material.default_physical_properties(), properties);
+        }
+
+        // Values are in the same order as the enum's items in Roblox.
+        assert!(MATERIALS.windows(2).all(|pair| pair[0].1 < pair[1].1));
+    }
+
+    #[test]
+    fn known_values() {
+        assert_eq!(Material::from_u32(256), Some(Material::Plastic));
+        assert_eq!(Material::from_u32(2048), Some(Material::Water));
+        assert_eq!(Material::from_u32(0), None);
+        assert_eq!(Enum::from(Material::Neon), Enum::from_u32(288));
+
+        assert_eq!(
+            Material::Plastic.default_physical_properties(),
+            CustomPhysicalProperties {
+                density: 0.7,
+                friction: 0.3,
+                elasticity: 0.5,
+                friction_weight: 1.0,
+                elasticity_weight: 1.0,
+            }
+        );
+        assert_eq!(Material::Ice.default_physical_properties().friction, 0.02);
+        assert_eq!(Material::Metal.default_physical_properties().density, 7.85);
+    }
+
+    #[test]
+    fn resolve() {
+        let custom = CustomPhysicalProperties {
+            density: 1.0,
+            friction: 2.0,
+            elasticity: 0.5,
+            friction_weight: 1.0,
+            elasticity_weight: 1.0,
+        };
+
+        assert_eq!(
+            PhysicalProperties::Custom(custom).resolve(Material::Sand),
+            custom
+        );
+        assert_eq!(
+            PhysicalProperties::Default.resolve(Material::Sand),
+            Material::Sand.default_physical_properties()
+        );
+    }
+}
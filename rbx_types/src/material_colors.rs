@@ -0,0 +1,218 @@
+use thiserror::Error;
+
+use crate::{Color3uint8, Error};
+
+/// Every terrain material that has a color in [`MaterialColors`], in the
+/// order that Roblox stores them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TerrainMaterials {
+    Grass,
+    Slate,
+    Concrete,
+    Brick,
+    Sand,
+    WoodPlanks,
+    Rock,
+    Glacier,
+    Snow,
+    Sandstone,
+    Mud,
+    Basalt,
+    Ground,
+    CrackedLava,
+    Asphalt,
+    Cobblestone,
+    Ice,
+    LeafyGrass,
+    Salt,
+    Limestone,
+    Pavement,
+}
+
+impl TerrainMaterials {
+    /// Every material, in the order that Roblox stores them.
+    pub const ALL: [TerrainMaterials; 21] = [
+        Self::Grass,
+        Self::Slate,
+        Self::Concrete,
+        Self::Brick,
+        Self::Sand,
+        Self::WoodPlanks,
+        Self::Rock,
+        Self::Glacier,
+        Self::Snow,
+        Self::Sandstone,
+        Self::Mud,
+        Self::Basalt,
+        Self::Ground,
+        Self::CrackedLava,
+        Self::Asphalt,
+        Self::Cobblestone,
+        Self::Ice,
+        Self::LeafyGrass,
+        Self::Salt,
+        Self::Limestone,
+        Self::Pavement,
+    ];
+}
+
+/// The number of unused bytes at the start of an encoded `MaterialColors`.
+const HEADER_LEN: usize = 6;
+
+/// The number of bytes in an encoded `MaterialColors`.
+const ENCODED_LEN: usize = HEADER_LEN + TerrainMaterials::ALL.len() * 3;
+
+/// The colors of each terrain material, which Roblox stores in the
+/// `Terrain.MaterialColors` property.
+///
+/// The property holds 6 bytes that are always zero, followed by the red,
+/// green, and blue parts of each material's color, in the order of
+/// [`TerrainMaterials::ALL`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaterialColors {
+    colors: [Color3uint8; 21],
+}
+
+impl Default for MaterialColors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MaterialColors {
+    /// Creates a `MaterialColors` where every material is black.
+    pub fn new() -> Self {
+        Self {
+            colors: [Color3uint8::new(0, 0, 0); 21],
+        }
+    }
+
+    /// Returns the color of the given material.
+    pub fn get(&self, material: TerrainMaterials) -> Color3uint8 {
+        self.colors[material as usize]
+    }
+
+    /// Sets the color of the given material.
+    pub fn set(&mut self, material: TerrainMaterials, color: Color3uint8) {
+        self.colors[material as usize] = color;
+    }
+
+    /// Iterates over every material and its color, in the order that Roblox
+    /// stores them.
+    pub fn iter(&self) -> impl Iterator<Item = (TerrainMaterials, Color3uint8)> + '_ {
+        TerrainMaterials::ALL
+            .iter()
+            .zip(&self.colors)
+            .map(|(&material, &color)| (material, color))
+    }
+
+    /// Decodes a `MaterialColors` from the contents of a
+    /// `Terrain.MaterialColors` property. Returns an error if the buffer isn't
+    /// the right length.
+    pub fn decode(buf: &[u8]) -> Result<Self, Error> {
+        if buf.len() != ENCODED_LEN {
+            return Err(MaterialColorsError::WrongLength {
+                expected: ENCODED_LEN,
+                actual: buf.len(),
+            }
+            .into());
+        }
+
+        let mut colors = Self::new().colors;
+        for (color, bytes) in colors.iter_mut().zip(buf[HEADER_LEN..].chunks_exact(3)) {
+            *color = Color3uint8::new(bytes[0], bytes[1], bytes[2]);
+        }
+
+        Ok(Self { colors })
+    }
+
+    /// Encodes this `MaterialColors` the way Roblox stores it in the
+    /// `Terrain.MaterialColors` property.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(ENCODED_LEN);
+        buf.extend_from_slice(&[0; HEADER_LEN]);
+
+        for color in &self.colors {
+            buf.extend_from_slice(&[color.r, color.g, color.b]);
+        }
+
+        buf
+    }
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum MaterialColorsError {
+    #[error("MaterialColors must be {expected} bytes long, but this one is {actual} bytes")]
+    WrongLength { expected: usize, actual: usize },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The colors of every material, in storage order, as a blob laid out the
+    /// way Roblox stores it.
+    const BLOB: [u8; ENCODED_LEN] = [
+        0, 0, 0, 0, 0, 0, // header
+        106, 127, 63, // Grass
+        63, 127, 107, // Slate
+        127, 102, 63, // Concrete
+        138, 86, 62, // Brick
+        143, 126, 95, // Sand
+        139, 109, 79, // WoodPlanks
+        102, 108, 111, // Rock
+        101, 176, 234, // Glacier
+        195, 199, 218, // Snow
+        137, 90, 71, // Sandstone
+        58, 46, 36, // Mud
+        30, 30, 37, // Basalt
+        102, 92, 59, // Ground
+        232, 156, 74, // CrackedLava
+        115, 123, 107, // Asphalt
+        132, 123, 90, // Cobblestone
+        129, 194, 224, // Ice
+        115, 132, 74, // LeafyGrass
+        198, 189, 181, // Salt
+        206, 173, 148, // Limestone
+        148, 148, 140, // Pavement
+    ];
+
+    #[test]
+    fn decode_encode() {
+        let colors = MaterialColors::decode(&BLOB).unwrap();
+
+        assert_eq!(
+            colors.get(TerrainMaterials::Grass),
+            Color3uint8::new(106, 127, 63)
+        );
+        assert_eq!(
+            colors.get(TerrainMaterials::CrackedLava),
+            Color3uint8::new(232, 156, 74)
+        );
+        assert_eq!(
+            colors.get(TerrainMaterials::Pavement),
+            Color3uint8::new(148, 148, 140)
+        );
+        assert_eq!(colors.iter().count(), 21);
+        assert_eq!(colors.encode(), BLOB);
+    }
+
+    #[test]
+    fn set() {
+        let mut colors = MaterialColors::new();
+        colors.set(TerrainMaterials::Slate, Color3uint8::new(1, 2, 3));
+
+        let encoded = colors.encode();
+        assert_eq!(encoded.len(), 69);
+        assert_eq!(&encoded[9..12], &[1, 2, 3]);
+        assert_eq!(MaterialColors::decode(&encoded).unwrap(), colors);
+    }
+
+    #[test]
+    fn wrong_length() {
+        let error = MaterialColors::decode(&BLOB[..68]).unwrap_err();
+        assert!(error.to_string().contains("68"), "{}", error);
+    }
+}
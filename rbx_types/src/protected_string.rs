@@ -0,0 +1,70 @@
+/// A string that Roblox keeps on the server, like the source of a script.
+///
+/// Roblox doesn't replicate `ProtectedString` properties to clients, but
+/// otherwise treats them like strings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct ProtectedString {
+    value: String,
+}
+
+impl ProtectedString {
+    #[inline]
+    pub fn new() -> Self {
+        ProtectedString {
+            value: String::new(),
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.value
+    }
+}
+
+impl From<String> for ProtectedString {
+    fn from(value: String) -> Self {
+        Self { value }
+    }
+}
+
+impl From<&'_ str> for ProtectedString {
+    fn from(value: &str) -> Self {
+        Self {
+            value: value.to_owned(),
+        }
+    }
+}
+
+impl AsRef<str> for ProtectedString {
+    fn as_ref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl AsRef<String> for ProtectedString {
+    fn as_ref(&self) -> &String {
+        &self.value
+    }
+}
+
+impl AsMut<str> for ProtectedString {
+    fn as_mut(&mut self) -> &mut str {
+        &mut self.value
+    }
+}
+
+impl AsMut<String> for ProtectedString {
+    fn as_mut(&mut self) -> &mut String {
+        &mut self.value
+    }
+}
@@ -29,6 +29,41 @@ impl Tags {
         self.members.push(tag.to_owned());
     }
 
+    /// Tells whether the container has the given tag.
+    pub fn contains(&self, tag: &str) -> bool {
+        self.members.iter().any(|member| member == tag)
+    }
+
+    /// Adds a tag to the container unless it's already there, like
+    /// `CollectionService:AddTag` does. Returns whether the tag was added.
+    pub fn add(&mut self, tag: &str) -> bool {
+        if self.contains(tag) {
+            return false;
+        }
+
+        self.push(tag);
+        true
+    }
+
+    /// Removes every copy of a tag from the container. Returns whether the tag
+    /// was there.
+    pub fn remove(&mut self, tag: &str) -> bool {
+        let len = self.members.len();
+        self.members.retain(|member| member != tag);
+
+        self.members.len() != len
+    }
+
+    /// Returns the number of tags in the container, including duplicates.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Tells whether the container has no tags.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
     /// Returns an iterator over all of the tags in the container.
     pub fn iter(&self) -> TagsIter<'_> {
         TagsIter {
@@ -37,6 +72,7 @@ impl Tags {
     }
 
     /// Decodes tags from a buffer containing `\0`-delimited tag names.
+    /// Returns an error if any tag isn't valid UTF-8.
     pub fn decode(buf: &[u8]) -> Result<Self, FromUtf8Error> {
         Ok(buf
             .split(|element| *element == 0)
@@ -46,6 +82,22 @@ impl Tags {
             .into())
     }
 
+    /// Decodes tags from a buffer containing `\0`-delimited tag names,
+    /// skipping any tag that isn't valid UTF-8 instead of failing.
+    pub fn decode_lenient(buf: &[u8]) -> Self {
+        buf.split(|element| *element == 0)
+            .filter(|tag_name| !tag_name.is_empty())
+            .filter_map(|tag_name| match String::from_utf8(tag_name.to_vec()) {
+                Ok(tag_name) => Some(tag_name),
+                Err(err) => {
+                    log::warn!("Skipping tag that isn't valid UTF-8: {}", err);
+                    None
+                }
+            })
+            .collect::<Vec<String>>()
+            .into()
+    }
+
     /// Encodes tags into a buffer by joining them with `\0` bytes.
     pub fn encode(&self) -> Vec<u8> {
         self.members.join("\0").into_bytes()
@@ -107,4 +159,36 @@ mod test {
 
         assert_eq!(result.iter().collect::<Vec<_>>(), expected);
     }
+
+    #[test]
+    fn add_remove() {
+        let mut tags = Tags::decode(b"Enemy\0Boss\0Enemy").unwrap();
+        assert!(tags.contains("Boss"));
+        assert!(!tags.contains("boss"));
+
+        assert!(!tags.add("Boss"));
+        assert!(tags.add("Flying"));
+        assert_eq!(tags.encode(), b"Enemy\0Boss\0Enemy\0Flying");
+
+        assert!(tags.remove("Enemy"));
+        assert!(!tags.remove("Enemy"));
+        assert!(!tags.contains("Enemy"));
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags.encode(), b"Boss\0Flying");
+
+        assert!(tags.remove("Boss"));
+        assert!(tags.remove("Flying"));
+        assert!(tags.is_empty());
+        assert_eq!(tags.encode(), b"");
+    }
+
+    #[test]
+    fn decode_invalid_utf8() {
+        let input = b"good\0b\xffd\0also good";
+
+        assert!(Tags::decode(input).is_err());
+
+        let tags = Tags::decode_lenient(input);
+        assert_eq!(tags.iter().collect::<Vec<_>>(), &["good", "also good"]);
+    }
 }
@@ -0,0 +1,109 @@
+use std::{convert::TryInto, fmt, str::FromStr};
+
+/// An identifier that Roblox gives to instances in places, which stays the
+/// same when the place is saved and loaded again.
+///
+/// No two instances in a place should share a `UniqueId`, except for the nil
+/// value, which is all zeroes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UniqueId {
+    /// A random number that is chosen once per Roblox Studio session.
+    pub random: i64,
+
+    /// A timestamp from when `random` was chosen.
+    pub time: u32,
+
+    /// A counter that makes IDs created in the same session distinct.
+    pub index: u32,
+}
+
+impl UniqueId {
+    /// Creates a new `UniqueId` from its parts.
+    pub fn new(random: i64, time: u32, index: u32) -> Self {
+        Self {
+            random,
+            time,
+            index,
+        }
+    }
+
+    /// Returns the nil `UniqueId`, which is all zeroes.
+    pub fn nil() -> Self {
+        Self::default()
+    }
+
+    /// Tells whether this is the nil `UniqueId`.
+    pub fn is_nil(&self) -> bool {
+        *self == Self::nil()
+    }
+
+    /// Reads a `UniqueId` from 16 bytes holding `random`, `time`, and
+    /// `index`, in that order and big-endian. This is the order the parts are
+    /// shown in by this type's `Display` implementation.
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self {
+            random: i64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            time: u32::from_be_bytes(bytes[8..12].try_into().unwrap()),
+            index: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    /// Writes this `UniqueId` into 16 bytes in the same layout that
+    /// [`UniqueId::from_bytes`] reads.
+    pub fn to_bytes(self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[0..8].copy_from_slice(&self.random.to_be_bytes());
+        bytes[8..12].copy_from_slice(&self.time.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.index.to_be_bytes());
+        bytes
+    }
+}
+
+/// Formats a `UniqueId` as 32 lowercase hex digits, like Roblox does.
+impl fmt::Display for UniqueId {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{:016x}{:08x}{:08x}",
+            self.random, self.time, self.index
+        )
+    }
+}
+
+impl FromStr for UniqueId {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let value = u128::from_str_radix(input, 16)?;
+
+        Ok(Self::from_bytes(value.to_be_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bytes_round_trip() {
+        let id = UniqueId::new(-2, 0x0123_4567, 42);
+        let bytes = id.to_bytes();
+
+        assert_eq!(
+            bytes,
+            [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0x01, 0x23, 0x45, 0x67, 0, 0, 0, 42]
+        );
+        assert_eq!(UniqueId::from_bytes(bytes), id);
+    }
+
+    #[test]
+    fn display() {
+        let id = UniqueId::new(-2, 0x0123_4567, 42);
+        let formatted = id.to_string();
+
+        assert_eq!(formatted, "fffffffffffffffe012345670000002a");
+        assert_eq!(formatted.parse::<UniqueId>().unwrap(), id);
+        assert_eq!(UniqueId::nil().to_string(), "0".repeat(32));
+    }
+}
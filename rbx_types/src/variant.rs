@@ -1,7 +1,10 @@
+use thiserror::Error;
+
 use crate::{
     Attributes, Axes, BinaryString, BrickColor, CFrame, Color3, Color3uint8, ColorSequence,
-    Content, Enum, Faces, NumberRange, NumberSequence, PhysicalProperties, Ray, Rect, Ref, Region3,
-    Region3int16, SharedString, Tags, UDim, UDim2, Vector2, Vector2int16, Vector3, Vector3int16,
+    Content, Enum, Faces, Font, NumberRange, NumberSequence, PhysicalProperties, ProtectedString,
+    Ray, Rect, Ref, Region3, Region3int16, SharedString, Tags, UDim, UDim2, UniqueId, Vector2,
+    Vector2int16, Vector3, Vector3int16,
 };
 
 /// Reduces boilerplate from listing different values of Variant by wrapping
@@ -128,6 +131,71 @@ make_variant! {
     OptionalCFrame(Option<CFrame>),
     Tags(Tags),
     Attributes(Attributes),
+    Font(Font),
+    UniqueId(UniqueId),
+    ProtectedString(ProtectedString),
+}
+
+impl Variant {
+    /// Converts this value into a value of the given type, the same way that
+    /// Roblox does when a property's type has changed since a file was saved.
+    ///
+    /// These conversions are supported:
+    /// - `Int32` to `Int64`
+    /// - `Float32` to `Float64`
+    /// - `Color3` to `Color3uint8`, which rounds each channel
+    /// - `Color3uint8` to `Color3`
+    /// - `String` to `ProtectedString`, and back
+    ///
+    /// Values that already have the given type are returned unchanged. Any
+    /// other conversion returns an error.
+    pub fn try_convert(self, target: VariantType) -> Result<Variant, ConvertError> {
+        let source = self.ty();
+
+        if source == target {
+            return Ok(self);
+        }
+
+        match (self, target) {
+            (Variant::Int32(value), VariantType::Int64) => Ok(Variant::Int64(value.into())),
+            (Variant::Float32(value), VariantType::Float64) => Ok(Variant::Float64(value.into())),
+            (Variant::Color3(value), VariantType::Color3uint8) => {
+                Ok(Variant::Color3uint8(value.into()))
+            }
+            (Variant::Color3uint8(value), VariantType::Color3) => Ok(Variant::Color3(value.into())),
+            (Variant::String(value), VariantType::ProtectedString) => {
+                Ok(Variant::ProtectedString(value.into()))
+            }
+            (Variant::ProtectedString(value), VariantType::String) => {
+                Ok(Variant::String(value.into_string()))
+            }
+            _ => Err(ConvertError {
+                from: source,
+                to: target,
+            }),
+        }
+    }
+}
+
+/// An error returned by [`Variant::try_convert`] when a value can't be
+/// converted into the requested type.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("cannot convert a value of type {from:?} into {to:?}")]
+pub struct ConvertError {
+    from: VariantType,
+    to: VariantType,
+}
+
+impl ConvertError {
+    /// The type of the value that was being converted.
+    pub fn source_type(&self) -> VariantType {
+        self.from
+    }
+
+    /// The type that the value was being converted into.
+    pub fn target_type(&self) -> VariantType {
+        self.to
+    }
 }
 
 impl From<&'_ str> for Variant {
@@ -136,6 +204,57 @@ impl From<&'_ str> for Variant {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn try_convert() {
+        assert_eq!(
+            Variant::Int32(-5).try_convert(VariantType::Int64),
+            Ok(Variant::Int64(-5))
+        );
+        assert_eq!(
+            Variant::Float32(0.5).try_convert(VariantType::Float64),
+            Ok(Variant::Float64(0.5))
+        );
+        assert_eq!(
+            Variant::Color3(Color3::new(1.0, 0.5, 0.0)).try_convert(VariantType::Color3uint8),
+            Ok(Variant::Color3uint8(Color3uint8::new(255, 128, 0)))
+        );
+        assert_eq!(
+            Variant::Color3uint8(Color3uint8::new(255, 0, 0)).try_convert(VariantType::Color3),
+            Ok(Variant::Color3(Color3::new(1.0, 0.0, 0.0)))
+        );
+        assert_eq!(
+            Variant::String("print()".to_owned()).try_convert(VariantType::ProtectedString),
+            Ok(Variant::ProtectedString("print()".into()))
+        );
+        assert_eq!(
+            Variant::ProtectedString("print()".into()).try_convert(VariantType::String),
+            Ok(Variant::String("print()".to_owned()))
+        );
+        assert_eq!(
+            Variant::Bool(true).try_convert(VariantType::Bool),
+            Ok(Variant::Bool(true))
+        );
+    }
+
+    #[test]
+    fn try_convert_invalid() {
+        let error = Variant::Int64(5)
+            .try_convert(VariantType::Int32)
+            .unwrap_err();
+
+        assert_eq!(error.source_type(), VariantType::Int64);
+        assert_eq!(error.target_type(), VariantType::Int32);
+
+        assert!(Variant::String("1".to_owned())
+            .try_convert(VariantType::Int32)
+            .is_err());
+    }
+}
+
 #[cfg(all(test, feature = "serde"))]
 mod serde_test {
     use super::*;
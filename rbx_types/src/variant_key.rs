@@ -0,0 +1,263 @@
+use std::hash::{Hash, Hasher};
+
+use crate::{
+    CFrame, Color3, PhysicalProperties, UDim, Variant, VariantType, Vector2, Vector3, Vector3int16,
+};
+
+/// Wraps a [`Variant`] so that it can be used as the key of a `HashMap` or
+/// `HashSet`.
+///
+/// `Variant` can't implement `Eq` or `Hash` because it contains floats. Two
+/// `VariantKey` values are equal when their variants have the same type and
+/// the same bit pattern, which means that `0.0` and `-0.0` are different keys
+/// and that a `NaN` is equal to another `NaN` with the same bits.
+#[derive(Debug, Clone)]
+pub struct VariantKey {
+    variant: Variant,
+    bits: Vec<u8>,
+}
+
+impl VariantKey {
+    /// Creates a new `VariantKey` from the given value.
+    pub fn new(variant: Variant) -> Self {
+        let mut bits = Vec::new();
+        write_bits(&variant, &mut bits);
+
+        Self { variant, bits }
+    }
+
+    /// Returns the value this key wraps.
+    pub fn variant(&self) -> &Variant {
+        &self.variant
+    }
+
+    /// Consumes the key, returning the value it wraps.
+    pub fn into_variant(self) -> Variant {
+        self.variant
+    }
+
+    fn ty(&self) -> VariantType {
+        self.variant.ty()
+    }
+}
+
+impl From<Variant> for VariantKey {
+    fn from(variant: Variant) -> Self {
+        Self::new(variant)
+    }
+}
+
+impl PartialEq for VariantKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ty() == other.ty() && self.bits == other.bits
+    }
+}
+
+impl Eq for VariantKey {}
+
+impl Hash for VariantKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.ty().hash(state);
+        self.bits.hash(state);
+    }
+}
+
+/// Compares two values the same way that `VariantKey` does, without needing
+/// to take ownership of them.
+pub(crate) fn bits_eq(a: &Variant, b: &Variant) -> bool {
+    if a.ty() != b.ty() {
+        return false;
+    }
+
+    let mut a_bits = Vec::new();
+    let mut b_bits = Vec::new();
+    write_bits(a, &mut a_bits);
+    write_bits(b, &mut b_bits);
+
+    a_bits == b_bits
+}
+
+/// Writes an unambiguous representation of the given value's contents into
+/// `output`, using the bit patterns of any floats it contains.
+fn write_bits(variant: &Variant, output: &mut Vec<u8>) {
+    match variant {
+        Variant::Axes(value) => output.push(value.bits()),
+        Variant::BinaryString(value) => write_bytes(AsRef::<[u8]>::as_ref(value), output),
+        Variant::Bool(value) => output.push(*value as u8),
+        Variant::BrickColor(value) => output.extend_from_slice(&(*value as u16).to_le_bytes()),
+        Variant::CFrame(value) => write_cframe(value, output),
+        Variant::Color3(value) => write_color3(value, output),
+        Variant::Color3uint8(value) => output.extend_from_slice(&[value.r, value.g, value.b]),
+        Variant::ColorSequence(value) => {
+            write_len(value.keypoints.len(), output);
+            for keypoint in &value.keypoints {
+                write_f32(keypoint.time, output);
+                write_color3(&keypoint.color, output);
+            }
+        }
+        Variant::Content(value) => write_bytes(AsRef::<str>::as_ref(value).as_bytes(), output),
+        Variant::Enum(value) => output.extend_from_slice(&value.to_u32().to_le_bytes()),
+        Variant::Faces(value) => output.push(value.bits()),
+        Variant::Float32(value) => write_f32(*value, output),
+        Variant::Float64(value) => output.extend_from_slice(&value.to_bits().to_le_bytes()),
+        Variant::UniqueId(value) => output.extend_from_slice(&value.to_bytes()),
+        Variant::Font(value) => {
+            write_bytes(value.family.as_bytes(), output);
+            output.extend_from_slice(&value.weight.as_u16().to_le_bytes());
+            output.push(value.style.as_u8());
+            match &value.cached_face_id {
+                Some(face) => {
+                    output.push(1);
+                    write_bytes(face.as_bytes(), output);
+                }
+                None => output.push(0),
+            }
+        }
+        Variant::Int32(value) => output.extend_from_slice(&value.to_le_bytes()),
+        Variant::Int64(value) => output.extend_from_slice(&value.to_le_bytes()),
+        Variant::NumberRange(value) => {
+            write_f32(value.min, output);
+            write_f32(value.max, output);
+        }
+        Variant::NumberSequence(value) => {
+            write_len(value.keypoints.len(), output);
+            for keypoint in &value.keypoints {
+                write_f32(keypoint.time, output);
+                write_f32(keypoint.value, output);
+                write_f32(keypoint.envelope, output);
+            }
+        }
+        Variant::ProtectedString(value) => write_bytes(value.as_str().as_bytes(), output),
+        Variant::PhysicalProperties(value) => match value {
+            PhysicalProperties::Default => output.push(0),
+            PhysicalProperties::Custom(custom) => {
+                output.push(1);
+                write_f32(custom.density, output);
+                write_f32(custom.friction, output);
+                write_f32(custom.elasticity, output);
+                write_f32(custom.friction_weight, output);
+                write_f32(custom.elasticity_weight, output);
+            }
+        },
+        Variant::Ray(value) => {
+            write_vector3(&value.origin, output);
+            write_vector3(&value.direction, output);
+        }
+        Variant::Rect(value) => {
+            write_vector2(&value.min, output);
+            write_vector2(&value.max, output);
+        }
+        Variant::Ref(value) => write_bytes(value.to_string().as_bytes(), output),
+        Variant::Region3(value) => {
+            write_vector3(&value.min, output);
+            write_vector3(&value.max, output);
+        }
+        Variant::Region3int16(value) => {
+            write_vector3int16(&value.min, output);
+            write_vector3int16(&value.max, output);
+        }
+        Variant::SharedString(value) => output.extend_from_slice(value.hash().as_bytes()),
+        Variant::String(value) => write_bytes(value.as_bytes(), output),
+        Variant::UDim(value) => write_udim(value, output),
+        Variant::UDim2(value) => {
+            write_udim(&value.x, output);
+            write_udim(&value.y, output);
+        }
+        Variant::Vector2(value) => write_vector2(value, output),
+        Variant::Vector2int16(value) => {
+            output.extend_from_slice(&value.x.to_le_bytes());
+            output.extend_from_slice(&value.y.to_le_bytes());
+        }
+        Variant::Vector3(value) => write_vector3(value, output),
+        Variant::Vector3int16(value) => write_vector3int16(value, output),
+        Variant::OptionalCFrame(value) => match value {
+            Some(cframe) => {
+                output.push(1);
+                write_cframe(cframe, output);
+            }
+            None => output.push(0),
+        },
+        Variant::Tags(value) => {
+            write_len(value.iter().count(), output);
+            for tag in value.iter() {
+                write_bytes(tag.as_bytes(), output);
+            }
+        }
+        Variant::Attributes(value) => {
+            write_len(value.iter().count(), output);
+            for (key, value) in value.iter() {
+                write_bytes(key.as_bytes(), output);
+                output.push(value.ty() as u8);
+                write_bits(value, output);
+            }
+        }
+    }
+}
+
+fn write_len(len: usize, output: &mut Vec<u8>) {
+    output.extend_from_slice(&(len as u64).to_le_bytes());
+}
+
+fn write_bytes(bytes: &[u8], output: &mut Vec<u8>) {
+    write_len(bytes.len(), output);
+    output.extend_from_slice(bytes);
+}
+
+fn write_f32(value: f32, output: &mut Vec<u8>) {
+    output.extend_from_slice(&value.to_bits().to_le_bytes());
+}
+
+fn write_udim(value: &UDim, output: &mut Vec<u8>) {
+    write_f32(value.scale, output);
+    output.extend_from_slice(&value.offset.to_le_bytes());
+}
+
+fn write_vector2(value: &Vector2, output: &mut Vec<u8>) {
+    write_f32(value.x, output);
+    write_f32(value.y, output);
+}
+
+fn write_vector3(value: &Vector3, output: &mut Vec<u8>) {
+    write_f32(value.x, output);
+    write_f32(value.y, output);
+    write_f32(value.z, output);
+}
+
+fn write_vector3int16(value: &Vector3int16, output: &mut Vec<u8>) {
+    output.extend_from_slice(&value.x.to_le_bytes());
+    output.extend_from_slice(&value.y.to_le_bytes());
+    output.extend_from_slice(&value.z.to_le_bytes());
+}
+
+fn write_color3(value: &Color3, output: &mut Vec<u8>) {
+    write_f32(value.r, output);
+    write_f32(value.g, output);
+    write_f32(value.b, output);
+}
+
+fn write_cframe(value: &CFrame, output: &mut Vec<u8>) {
+    write_vector3(&value.position, output);
+    write_vector3(&value.orientation.x, output);
+    write_vector3(&value.orientation.y, output);
+    write_vector3(&value.orientation.z, output);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn float_bits() {
+        let mut set = HashSet::new();
+        set.insert(VariantKey::new(Variant::Float32(0.0)));
+        set.insert(VariantKey::new(Variant::Float32(-0.0)));
+        set.insert(VariantKey::new(Variant::Float32(f32::NAN)));
+        set.insert(VariantKey::new(Variant::Float32(f32::NAN)));
+        set.insert(VariantKey::new(Variant::Float64(0.0)));
+
+        assert_eq!(set.len(), 4);
+        assert!(set.contains(&VariantKey::new(Variant::Float32(f32::NAN))));
+    }
+}
@@ -4,7 +4,7 @@
 use std::borrow::{Borrow, Cow};
 use std::convert::TryInto;
 
-use rbx_dom_weak::types::{Attributes, BrickColor, Color3uint8, Tags, Variant, VariantType};
+use rbx_dom_weak::types::{Attributes, BrickColor, Tags, Variant, VariantType};
 
 pub trait ConvertVariant: Clone + Sized {
     fn try_convert(self, target_type: VariantType) -> Result<Self, String> {
@@ -37,9 +37,6 @@ impl ConvertVariant for Variant {
                     .map(Into::into)
                     .map(Cow::Owned)
             }
-            (Variant::Color3(value), VariantType::Color3uint8) => {
-                Ok(Cow::Owned(Color3uint8::from(*value).into()))
-            }
             (Variant::BinaryString(value), VariantType::Tags) => Ok(Cow::Owned(
                 Tags::decode(value.as_ref())
                     .map_err(|_| "Tags contain invalid UTF-8")?
@@ -54,6 +51,12 @@ impl ConvertVariant for Variant {
                         .into(),
                 ))
             }
+            (other, _) if other.ty() != target_type => {
+                match other.clone().try_convert(target_type) {
+                    Ok(converted) => Ok(Cow::Owned(converted)),
+                    Err(_) => Ok(value),
+                }
+            }
             (_, _) => Ok(value),
         }
     }
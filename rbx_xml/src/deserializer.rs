@@ -151,7 +151,7 @@ struct SharedStringRewrite {
 }
 
 impl<'a> ParseState<'a> {
-    fn new(tree: &mut WeakDom, options: DecodeOptions) -> ParseState {
+    fn new(tree: &mut WeakDom, options: DecodeOptions) -> ParseState<'_> {
         ParseState {
             tree,
             options,
@@ -299,14 +299,9 @@ fn deserialize_root<R: Read>(
                     }
                 }
             }
-            XmlReadEvent::EndElement { name } => {
-                if name.local_name == "roblox" {
-                    reader.expect_next().unwrap();
-                    break;
-                } else {
-                    let event = reader.expect_next().unwrap();
-                    return Err(reader.error(DecodeErrorKind::UnexpectedXmlEvent(event)));
-                }
+            XmlReadEvent::EndElement { name } if name.local_name == "roblox" => {
+                reader.expect_next().unwrap();
+                break;
             }
             XmlReadEvent::EndDocument => break,
             _ => {
@@ -352,21 +347,11 @@ fn deserialize_shared_string_dict<R: Read>(
 
     loop {
         match reader.expect_peek()? {
-            XmlReadEvent::StartElement { name, .. } => {
-                if name.local_name == "SharedString" {
-                    deserialize_shared_string(reader, state)?;
-                } else {
-                    let event = reader.expect_next().unwrap();
-                    return Err(reader.error(DecodeErrorKind::UnexpectedXmlEvent(event)));
-                }
+            XmlReadEvent::StartElement { name, .. } if name.local_name == "SharedString" => {
+                deserialize_shared_string(reader, state)?;
             }
-            XmlReadEvent::EndElement { name } => {
-                if name.local_name == "SharedStrings" {
-                    break;
-                } else {
-                    let event = reader.expect_next().unwrap();
-                    return Err(reader.error(DecodeErrorKind::UnexpectedXmlEvent(event)));
-                }
+            XmlReadEvent::EndElement { name } if name.local_name == "SharedStrings" => {
+                break;
             }
             _ => {
                 let event = reader.expect_next().unwrap();
@@ -535,14 +520,9 @@ fn deserialize_properties<R: Read>(
 
                     (name.local_name.to_owned(), xml_property_name)
                 }
-                XmlReadEvent::EndElement { name } => {
-                    if name.local_name == "Properties" {
-                        reader.expect_next()?;
-                        return Ok(());
-                    } else {
-                        let err = DecodeErrorKind::UnexpectedXmlEvent(reader.expect_next()?);
-                        return Err(reader.error(err));
-                    }
+                XmlReadEvent::EndElement { name } if name.local_name == "Properties" => {
+                    reader.expect_next()?;
+                    return Ok(());
                 }
                 _ => {
                     let err = DecodeErrorKind::UnexpectedXmlEvent(reader.expect_next()?);
@@ -589,7 +569,7 @@ fn deserialize_properties<R: Read>(
                 _ => unimplemented!(),
             };
 
-            let value = match value.try_convert(expected_type) {
+            let value = match ConvertVariant::try_convert(value, expected_type) {
                 Ok(value) => value,
 
                 // The property descriptor disagreed, and there was no
@@ -35,8 +35,8 @@ use std::io::{Read, Write};
 
 use rbx_dom_weak::types::{
     Axes, BinaryString, CFrame, Color3, Color3uint8, ColorSequence, Content, Enum, Faces,
-    NumberRange, NumberSequence, PhysicalProperties, Ray, Rect, Ref, UDim, UDim2, Variant, Vector2,
-    Vector2int16, Vector3, Vector3int16,
+    NumberRange, NumberSequence, PhysicalProperties, ProtectedString, Ray, Rect, Ref, UDim, UDim2,
+    Variant, Vector2, Vector2int16, Vector3, Vector3int16,
 };
 
 use crate::{
@@ -73,12 +73,6 @@ macro_rules! declare_rbx_types {
             match xml_type_name {
                 $(<$inner_type>::XML_TAG_NAME => Ok(Some(Variant::$variant_name(<$inner_type>::read_outer_xml(reader)?))),)*
 
-                // Protected strings are only read, never written
-                self::strings::ProtectedStringDummy::XML_TAG_NAME => {
-                    let value = self::strings::ProtectedStringDummy::read_outer_xml(reader)?;
-                    Ok(Some(Variant::String(value.0)))
-                },
-
                 self::referent::XML_TAG_NAME => Ok(Some(Variant::Ref(read_ref(reader, instance_id, property_name, state)?))),
                 self::shared_string::XML_TAG_NAME => read_shared_string(reader, instance_id, property_name, state).map(Some),
 
@@ -139,6 +133,7 @@ declare_rbx_types! {
     NumberSequence: NumberSequence,
     OptionalCFrame: Option<CFrame>,
     PhysicalProperties: PhysicalProperties,
+    ProtectedString: ProtectedString,
     Ray: Ray,
     Rect: Rect,
     String: String,
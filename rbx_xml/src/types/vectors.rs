@@ -55,6 +55,7 @@ mod test {
     #[test]
     fn round_trip_vector2int16() {
         test_util::test_xml_round_trip(&Vector2int16::new(1234, 4567));
+        test_util::test_xml_round_trip(&Vector2int16::new(-1234, i16::MIN));
     }
 
     #[test]
@@ -65,5 +66,6 @@ mod test {
     #[test]
     fn round_trip_vector3int16() {
         test_util::test_xml_round_trip(&Vector3int16::new(1234, 4567, 8913));
+        test_util::test_xml_round_trip(&Vector3int16::new(-1, i16::MIN, i16::MAX));
     }
 }